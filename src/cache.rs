@@ -0,0 +1,142 @@
+use crate::model::Operation;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// A file's last-seen content hash, recorded alongside the hash of the
+/// operation set that produced it so a later run under a different
+/// find/replace spec doesn't trust a stale entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    ops_hash: u64,
+    content_hash: u64,
+}
+
+/// Persistent "unchanged since last run" cache backing `--cache-file`. Keyed
+/// by absolute path; loading a missing or corrupt file is treated as an
+/// empty cache rather than an error, since a cold cache is just a slower
+/// first run, not a failure.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl Cache {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).unwrap_or_default();
+        std::fs::write(path, json)
+    }
+
+    /// Whether `path`'s current content hash matches the cached entry for
+    /// the same operation-set hash.
+    pub fn is_unchanged(&self, path: &Path, ops_hash: u64, content_hash: u64) -> bool {
+        matches!(
+            self.entries.get(path),
+            Some(entry) if entry.ops_hash == ops_hash && entry.content_hash == content_hash
+        )
+    }
+
+    pub fn update(&mut self, path: &Path, ops_hash: u64, content_hash: u64) {
+        self.entries.insert(path.to_path_buf(), CacheEntry { ops_hash, content_hash });
+    }
+}
+
+/// Hash of the active operation set. Two runs with different find/replace
+/// specs hash differently, so every cached entry is effectively invalidated
+/// the moment the pipeline's operations change.
+pub fn hash_operations(operations: &[Operation]) -> u64 {
+    let json = serde_json::to_string(operations).unwrap_or_default();
+    let mut hasher = DefaultHasher::new();
+    json.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash of a file's raw bytes, as read from disk (or produced by a
+/// processing run, for the entry written back afterwards).
+pub fn hash_content(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Operation;
+
+    #[test]
+    fn load_missing_file_is_empty_cache() {
+        let cache = Cache::load(Path::new("/nonexistent/does-not-exist.json"));
+        assert!(!cache.is_unchanged(Path::new("/a.txt"), 1, 2));
+    }
+
+    #[test]
+    fn update_then_is_unchanged_round_trips() {
+        let mut cache = Cache::default();
+        let path = Path::new("/a.txt");
+        cache.update(path, 1, 2);
+        assert!(cache.is_unchanged(path, 1, 2));
+    }
+
+    #[test]
+    fn is_unchanged_false_on_content_hash_mismatch() {
+        let mut cache = Cache::default();
+        let path = Path::new("/a.txt");
+        cache.update(path, 1, 2);
+        assert!(!cache.is_unchanged(path, 1, 3));
+    }
+
+    #[test]
+    fn is_unchanged_false_on_ops_hash_mismatch() {
+        let mut cache = Cache::default();
+        let path = Path::new("/a.txt");
+        cache.update(path, 1, 2);
+        assert!(!cache.is_unchanged(path, 99, 2));
+    }
+
+    #[test]
+    fn save_then_load_round_trips_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("cache.json");
+
+        let mut cache = Cache::default();
+        cache.update(Path::new("/a.txt"), 1, 2);
+        cache.save(&cache_path).unwrap();
+
+        let reloaded = Cache::load(&cache_path);
+        assert!(reloaded.is_unchanged(Path::new("/a.txt"), 1, 2));
+    }
+
+    #[test]
+    fn hash_operations_differs_when_find_differs() {
+        let a = vec![Operation::Replace {
+            find: "foo".into(),
+            with: "bar".into(),
+            literal: true,
+            ignore_case: false,
+            smart_case: false,
+            word: false,
+            multiline: false,
+            dot_matches_newline: false,
+            no_unicode: false,
+            limit: 0,
+            ranges: None,
+            expand: false,
+            validation_mode: Default::default(),
+        }];
+        let mut b = a.clone();
+        if let Operation::Replace { find, .. } = &mut b[0] {
+            *find = "baz".into();
+        }
+        assert_ne!(hash_operations(&a), hash_operations(&b));
+    }
+}
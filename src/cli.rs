@@ -32,6 +32,16 @@ pub enum PermissionsMode {
     Fixed,
 }
 
+#[derive(Debug, Clone, clap::ValueEnum, PartialEq)]
+#[clap(rename_all = "kebab-case")]
+pub enum TextEncoding {
+    Auto,
+    Utf8,
+    Utf16le,
+    Utf16be,
+    Latin1,
+}
+
 #[derive(Debug, Clone, clap::ValueEnum, PartialEq, Copy)]
 #[clap(rename_all = "kebab-case")]
 pub enum ValidationMode {
@@ -40,12 +50,48 @@ pub enum ValidationMode {
     None,
 }
 
+#[derive(Debug, Clone, clap::ValueEnum, PartialEq)]
+#[clap(rename_all = "kebab-case")]
+pub enum NewlineStyle {
+    Auto,
+    Native,
+    Unix,
+    Windows,
+}
+
+#[derive(Debug, Clone, clap::ValueEnum, PartialEq)]
+#[clap(rename_all = "kebab-case")]
+pub enum WriteStrategy {
+    Atomic,
+    InPlace,
+    Mmap,
+}
+
 #[derive(Debug, Clone, clap::ValueEnum, PartialEq)]
 pub enum OutputFormat {
     Diff,
     Summary,
     Json,
     Agent, // This is specific to the agent, not directly in helptext.txt's explicit formats.
+    /// Ripgrep-compatible newline-delimited JSON events (`begin`/`match`/`end`
+    /// per file), for piping into other `rg --json`-aware tooling.
+    JsonLines,
+    /// A standard unified diff (`---`/`+++` headers, `@@` hunks) with no
+    /// other commentary, so a `--dry-run` can be piped straight into `git
+    /// apply`/`patch`. Complements `--patch`, which reads this same format
+    /// back in as input.
+    Patch,
+    /// Terse tab-separated lines for shell scripting (one line per file:
+    /// `modified`/`unmodified`/`skipped`/`error`, the path, then a
+    /// replacement count or reason), ending with a
+    /// `files=N modified=M replacements=R errors=true|false` summary line.
+    /// No JSON parser required.
+    Shell,
+    /// One NUL-terminated `<path>\t<modified>\t<replacements>` record per
+    /// file, for piping into `xargs -0`-style consumers where paths may
+    /// contain spaces or newlines. Complements `--files0`, which reads this
+    /// same delimiter on input.
+    Files0,
 }
 #[derive(Parser, Debug)]
 #[command(
@@ -77,6 +123,15 @@ pub enum Commands {
     /// Apply a manifest (multi-file, multi-op), with full validation and atomic commit.
     #[command(visible_alias = "a")]
     Apply(ApplyArgs),
+    /// Print a shell completion script to stdout.
+    Completions {
+        /// Shell to generate completions for.
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Run a named recipe from the user's recipe config
+    /// (`~/.config/txed/recipes.toml`) against FILES.
+    Use(UseArgs),
 }
 
 /// Default command: txed FIND REPLACE [FILES...]
@@ -109,25 +164,77 @@ pub struct DefaultArgs {
     // Input Mode options
     // ========================================================================
     /// Force stdin to be interpreted as newline-delimited paths.
-    #[arg(long = "stdin-paths", conflicts_with_all = ["files0", "stdin_text", "rg_json", "files_arg"], help_heading = "Input Options")]
+    #[arg(long = "stdin-paths", conflicts_with_all = ["files0", "stdin_text", "rg_json", "edit_plan", "patch", "files_arg"], help_heading = "Input Options")]
     pub stdin_paths: bool,
 
     /// Read NUL-delimited paths from stdin (for find -print0, fd -0).
-    #[arg(long = "files0", conflicts_with_all = ["stdin_paths", "stdin_text", "rg_json", "files_arg"], help_heading = "Input Options")]
+    #[arg(long = "files0", conflicts_with_all = ["stdin_paths", "stdin_text", "rg_json", "edit_plan", "patch", "files_arg"], help_heading = "Input Options")]
     pub files0: bool,
 
     /// Treat stdin as content and write transformed content to stdout.
-    #[arg(long = "stdin-text", conflicts_with_all = ["stdin_paths", "files0", "rg_json", "files_arg"], help_heading = "Input Options")]
+    #[arg(long = "stdin-text", conflicts_with_all = ["stdin_paths", "files0", "rg_json", "edit_plan", "patch", "files_arg"], help_heading = "Input Options")]
     pub stdin_text: bool,
 
     /// Consume rg --json output from stdin and apply edits to matched spans.
-    #[arg(long = "rg-json", conflicts_with_all = ["stdin_paths", "files0", "stdin_text", "files_arg"], help_heading = "Input Options")]
+    #[arg(long = "rg-json", conflicts_with_all = ["stdin_paths", "files0", "stdin_text", "edit_plan", "patch", "files_arg"], help_heading = "Input Options")]
     pub rg_json: bool,
 
+    /// Consume a tool-agnostic NDJSON edit plan from stdin: one
+    /// `{"path": "...", "ranges": [{"start": N, "end": M}], "replacement": "..."?}`
+    /// object per line. A per-line `replacement` overrides FIND/REPLACE for
+    /// that file; omitted `ranges` applies the configured pattern to the
+    /// whole file. Lets editors, LSPs, or custom scripts drive precise
+    /// byte-range edits without producing ripgrep's `--json` format.
+    #[arg(long = "edit-plan", conflicts_with_all = ["stdin_paths", "files0", "stdin_text", "rg_json", "patch", "files_arg"], help_heading = "Input Options")]
+    pub edit_plan: bool,
+
+    /// Consume a standard unified diff from stdin (as produced by `--format
+    /// patch`, `git diff`, or `diff -u`) and apply its hunks directly to
+    /// their target files. No FIND/REPLACE is needed or accepted: each
+    /// hunk's replacement text comes from the patch itself. A hunk whose
+    /// context no longer matches the file on disk is reported as a conflict
+    /// rather than applied.
+    #[arg(long = "patch", conflicts_with_all = ["stdin_paths", "files0", "stdin_text", "rg_json", "edit_plan", "rustfix", "files_arg", "find", "replace"], help_heading = "Input Options")]
+    pub patch: bool,
+
+    /// Consume a `cargo build`/`cargo clippy --message-format=json`
+    /// diagnostic stream from stdin and apply every machine-applicable
+    /// suggestion directly, like `cargo fix`. No FIND/REPLACE is needed or
+    /// accepted: each suggestion's replacement text comes from the
+    /// diagnostic itself. Diagnostics whose applicability isn't
+    /// `MachineApplicable` are ignored; overlapping suggestions in the same
+    /// file keep the one with the higher byte offset and drop the other.
+    #[arg(long = "rustfix", conflicts_with_all = ["stdin_paths", "files0", "stdin_text", "rg_json", "edit_plan", "patch", "files_arg", "find", "replace"], help_heading = "Input Options")]
+    pub rustfix: bool,
+
     /// Force positional arguments to be treated as files even if stdin is present.
-    #[arg(long = "files", conflicts_with_all = ["stdin_paths", "files0", "stdin_text", "rg_json"], visible_alias = "files-arg", help_heading = "Input Options")]
+    #[arg(long = "files", conflicts_with_all = ["stdin_paths", "files0", "stdin_text", "rg_json", "edit_plan", "patch", "rustfix"], visible_alias = "files-arg", help_heading = "Input Options")]
     pub files_arg: bool,
 
+    /// Process input in fixed-size chunks and write transformed output as it
+    /// goes, instead of buffering the whole input. With --stdin-text, reads
+    /// stdin and writes to stdout; with file-path inputs, streams each file
+    /// through a temp file (--write-strategy=in-place is rejected).
+    /// Needed for multi-gigabyte inputs; only a single FIND/REPLACE operation
+    /// is supported (no manifest), --dry-run is rejected, and --transaction
+    /// all isn't supported for file inputs (there's no staged content left
+    /// to roll back). The JSON report's `generated_content` is omitted in
+    /// this mode since the full content is never held in memory.
+    #[arg(long = "stream", help_heading = "Input Options")]
+    pub stream: bool,
+
+    /// With --stream, how many trailing bytes of a chunk to hold back as
+    /// overlap for the next chunk, so a match straddling a chunk boundary
+    /// isn't missed. Must be at least as long as the longest possible match;
+    /// regex patterns with unbounded match length need this raised manually.
+    #[arg(
+        long = "max-match-window",
+        value_name = "BYTES",
+        default_value_t = 4096,
+        help_heading = "Input Options"
+    )]
+    pub max_match_window: usize,
+
     // ========================================================================
     // Match options
     // ========================================================================
@@ -181,10 +288,15 @@ pub struct DefaultArgs {
     )]
     pub limit: Option<usize>,
 
-    /// Only apply replacements in a line range (1-based, START[:END]).
+    /// Only apply replacements in one or more line ranges (1-based,
+    /// comma-separated START[:END] tokens). A trailing `:` leaves the upper
+    /// bound open to end of file; negative numbers count back from the
+    /// last line (e.g. `1:10,25,-5:` is lines 1-10, line 25, and the last
+    /// five lines).
     #[arg(
         long = "range",
-        value_name = "START[:END]",
+        value_name = "START[:END][,START[:END]...]",
+        allow_hyphen_values = true,
         help_heading = "Scope Options"
     )]
     pub range: Option<String>,
@@ -218,6 +330,188 @@ pub struct DefaultArgs {
     )]
     pub glob_exclude: Vec<String>,
 
+    /// Restrict edits to files matching a named file type's globs (e.g.
+    /// `rust`, `md`, `py`). Repeatable; a file matching any selected type is
+    /// included. See `--type-list` for the resolved table.
+    #[arg(short = 't', long = "type", value_name = "TYPE", help_heading = "Scope Options")]
+    pub file_type: Vec<String>,
+
+    /// Exclude files matching a named file type's globs. Repeatable.
+    #[arg(short = 'T', long = "type-not", value_name = "TYPE", help_heading = "Scope Options")]
+    pub type_not: Vec<String>,
+
+    /// Define or extend a file type: `NAME:GLOB`, or `NAME:include:OTHER` to
+    /// compose an existing type's globs into a new or existing one.
+    /// Repeatable.
+    #[arg(long = "type-add", value_name = "SPEC", help_heading = "Scope Options")]
+    pub type_add: Vec<String>,
+
+    /// Print the resolved type name -> glob table (built-ins plus any
+    /// `--type-add`) and exit.
+    #[arg(long = "type-list", help_heading = "Scope Options")]
+    pub type_list: bool,
+
+    /// When a positional path is a directory, walk it recursively instead
+    /// of erroring, honoring `.gitignore`/`.ignore`/global ignore rules
+    /// (see `--no-ignore`) and `--symlinks` during descent. A subdirectory
+    /// the walker can't visit (permission denied, etc.) is reported as a
+    /// `file` event with an error rather than aborting the run.
+    #[arg(short = 'r', long = "recursive", help_heading = "Scope Options")]
+    pub recursive: bool,
+
+    /// While walking recursively, also descend into hidden files and
+    /// directories (dotfiles). Ignored without `--recursive`.
+    #[arg(long = "hidden", help_heading = "Scope Options")]
+    pub hidden: bool,
+
+    /// While walking recursively, do not apply `.gitignore`/`.ignore`/global
+    /// ignore rules; visit every file under the directory. Ignored without
+    /// `--recursive`.
+    #[arg(long = "no-ignore", help_heading = "Scope Options")]
+    pub no_ignore: bool,
+
+    /// While walking recursively, descend at most N levels below the
+    /// starting directory. Ignored without `--recursive`.
+    #[arg(long = "max-depth", value_name = "N", help_heading = "Scope Options")]
+    pub max_depth: Option<usize>,
+
+    /// Keep running and re-apply the pipeline to the resolved input paths
+    /// whenever one of them changes on disk, printing a report for just the
+    /// files touched by that cycle. Runs until interrupted (Ctrl-C). Not
+    /// compatible with stdin-based input modes.
+    #[arg(long = "watch", help_heading = "Scope Options")]
+    pub watch: bool,
+
+    /// With `--watch`, how long to wait after the first change in a burst
+    /// before re-running, so a single editor save (which often fires several
+    /// write/rename events) triggers one cycle instead of several. Ignored
+    /// without `--watch`.
+    #[arg(long = "watch-debounce-ms", value_name = "MS", default_value_t = 200, help_heading = "Scope Options")]
+    pub watch_debounce_ms: u64,
+
+    /// Gate which resolved paths get processed using a small boolean
+    /// expression language modeled on Cargo's `cfg(...)` syntax, e.g.
+    /// `all(ext = "rs", not(hidden))`. See the `--filter` atoms: `ext`,
+    /// `name`, `path`, `hidden`, `symlink`.
+    #[arg(long = "filter", value_name = "EXPR", help_heading = "Scope Options")]
+    pub filter: Option<String>,
+
+    /// Confine writes to files under this directory. Repeatable; a file is
+    /// allowed if it's contained in any `--allow-write` root. The check runs
+    /// against the fully resolved (symlink- and `..`-free) absolute path, not
+    /// the raw argument. Unset (the default) means no restriction.
+    #[arg(long = "allow-write", value_name = "DIR", help_heading = "Scope Options")]
+    pub allow_write: Vec<String>,
+
+    /// Exclude files under this directory from writes, even if also covered
+    /// by `--allow-write`. Repeatable; checked the same way as `--allow-write`.
+    #[arg(long = "deny-write", value_name = "DIR", help_heading = "Scope Options")]
+    pub deny_write: Vec<String>,
+
+    /// Gate whether each file is edited using a `cfg()`-style boolean
+    /// expression over its path, content, and size, e.g.
+    /// `all(path = "glob:src/**/*.rs", not(contains = "@generated"))`.
+    /// Unlike `--filter`, this runs after the file is read, so it can
+    /// combine `ext = "rs"`, `path = "glob:..."`, `contains = "..."`, and
+    /// `size > 4096` (also `<`, `>=`, `<=`, `=`) with `all`/`any`/`not`.
+    #[arg(long = "when", value_name = "EXPR", help_heading = "Scope Options")]
+    pub when: Option<String>,
+
+    /// Replace a volatile substring (e.g. a temp-dir path) with a stable
+    /// placeholder before rendering a `--dry-run` diff, and in its `---`/
+    /// `+++` file paths under `--format patch`. Repeatable,
+    /// `PATTERN=PLACEHOLDER`; prefix PATTERN with `regex:` (mirroring
+    /// `--when`'s `glob:` prefix) to match a regex instead of a literal
+    /// substring.
+    #[arg(
+        long = "redact",
+        value_name = "PATTERN=PLACEHOLDER",
+        help_heading = "Output Options"
+    )]
+    pub redact: Vec<String>,
+
+    /// Unchanged context lines kept around each hunk in a `--dry-run` diff
+    /// preview (mirrors `diff -u`'s `-U`). Adjacent hunks whose unchanged gap
+    /// is at most twice this are merged into one, sharing a single `@@`
+    /// header rather than repeating it. Defaults to 3.
+    #[arg(
+        long = "context",
+        value_name = "N",
+        visible_alias = "diff-context",
+        help_heading = "Output Options"
+    )]
+    pub context: Option<usize>,
+
+    /// Always read files with a buffered read, skipping the memory-mapped
+    /// fast path used for large files.
+    #[arg(long = "no-mmap", global = true, help_heading = "Configuration")]
+    pub no_mmap: bool,
+
+    /// Files at or above this size (in bytes) are memory-mapped rather than
+    /// read with a buffered read. Defaults to 64 KiB. Ignored if `--no-mmap`
+    /// is set.
+    #[arg(long = "mmap-min-size", global = true, value_name = "BYTES", help_heading = "Configuration")]
+    pub mmap_min_size: Option<u64>,
+
+    /// How a modified file's new content is committed: `atomic` (default)
+    /// writes to a temp file in the target directory and renames it into
+    /// place; `in-place` truncates and rewrites the target file directly,
+    /// for directories that are read-only but whose files are still
+    /// writable (incompatible with `--transaction all`); `mmap` always
+    /// memory-maps the input for scanning regardless of `--mmap-min-size`,
+    /// still committing the write atomically — the mapping is guaranteed
+    /// to be dropped before any rename, on every platform.
+    #[arg(long = "write-strategy", value_enum, global = true, help_heading = "Configuration")]
+    pub write_strategy: Option<WriteStrategy>,
+
+    /// Best-effort: restore the original owner/group after writing (Unix only).
+    #[arg(long = "preserve-ownership", global = true, help_heading = "Configuration")]
+    pub preserve_ownership: bool,
+
+    /// Best-effort: restore the original mtime/atime after writing.
+    #[arg(long = "preserve-timestamps", global = true, help_heading = "Configuration")]
+    pub preserve_timestamps: bool,
+
+    /// Best-effort: copy extended attributes after writing (Unix only).
+    #[arg(long = "preserve-xattrs", global = true, help_heading = "Configuration")]
+    pub preserve_xattrs: bool,
+
+    /// Fsync each written file (and, for `--transaction=all`, each touched
+    /// parent directory) so writes survive a crash immediately after commit.
+    #[arg(long = "durable", global = true, help_heading = "Configuration")]
+    pub durable: bool,
+
+    /// Report per-replacement detail (byte span, line number, matched and
+    /// substituted text) on each JSON success event.
+    #[arg(long = "emit-edits", global = true, help_heading = "Output Options")]
+    pub emit_edits: bool,
+
+    /// Before overwriting a modified file, preserve its original bytes at
+    /// `<path>~` (a shorthand for `--backup-suffix=~`). Off by default.
+    #[arg(long = "backup", global = true, help_heading = "Configuration")]
+    pub backup: bool,
+
+    /// Before overwriting a modified file, preserve its original bytes at
+    /// `<path><SUFFIX>` (e.g. `--backup-suffix=.bak`). Implies `--backup`;
+    /// off by default.
+    #[arg(long = "backup-suffix", global = true, value_name = "SUFFIX", help_heading = "Configuration")]
+    pub backup_suffix: Option<String>,
+
+    /// Persist a per-file content hash across runs at PATH, keyed to the
+    /// active operation set, and skip re-processing any file whose hash
+    /// hasn't changed since it was last written here. Speeds up repeated
+    /// runs over mostly-static trees (CI, pre-commit); ignored under
+    /// `--dry-run`/`--validate-only`, since neither ever updates the cache.
+    #[arg(long = "cache-file", global = true, value_name = "FILE", help_heading = "Configuration")]
+    pub cache_file: Option<PathBuf>,
+
+    /// Worker thread count for processing files concurrently. Defaults to
+    /// all available parallelism. Only takes effect when built with the
+    /// `parallel` feature; `--transaction=all` still only writes after every
+    /// worker's result has been staged and the aggregate policy checks pass.
+    #[arg(long = "threads", global = true, value_name = "N", help_heading = "Configuration")]
+    pub threads: Option<usize>,
+
     // ========================================================================
     // Safety and guarantees
     // ========================================================================
@@ -241,6 +535,12 @@ pub struct DefaultArgs {
     #[arg(long = "fail-on-change", help_heading = "Safety Options")]
     pub fail_on_change: bool,
 
+    /// Exit non-zero if any file was rejected for being outside the
+    /// `--allow-write`/`--deny-write` roots. Without this, a blocked file is
+    /// just a `skipped` event.
+    #[arg(long = "fail-on-blocked", help_heading = "Safety Options")]
+    pub fail_on_blocked: bool,
+
     // ========================================================================
     // Transaction model
     // ========================================================================
@@ -274,6 +574,30 @@ pub struct DefaultArgs {
     )]
     pub binary: Option<BinaryFileMode>,
 
+    /// Text encoding: 'auto' (default, BOM-sniffed) or an explicit
+    /// 'utf8'/'utf16le'/'utf16be'/'latin1'. Non-UTF-8 input is transcoded
+    /// to UTF-8 for matching and back to its original encoding on write.
+    #[arg(
+        long = "encoding",
+        value_enum,
+        global = true,
+        help_heading = "Configuration"
+    )]
+    pub encoding: Option<TextEncoding>,
+
+    /// Line-ending style to write: 'auto' (default, keeps each file's
+    /// existing dominant style), 'unix' (rewrite `\r\n` to `\n`), 'windows'
+    /// (rewrite `\n` to `\r\n`), or 'native' (the host platform's
+    /// convention). Applied after all replacement operations, so matches
+    /// and replacement text both see the file's original line endings.
+    #[arg(
+        long = "newline-style",
+        value_enum,
+        global = true,
+        help_heading = "Configuration"
+    )]
+    pub newline_style: Option<NewlineStyle>,
+
     /// Permissions handling: 'preserve' (default) or 'fixed'.
     #[arg(
         long = "permissions",
@@ -340,3 +664,25 @@ pub struct ApplyArgs {
     #[arg(long = "json")]
     pub json: bool,
 }
+
+/// Arguments for the 'use' subcommand.
+#[derive(Args, Debug)]
+pub struct UseArgs {
+    /// Name of the recipe (or alias) to run, as defined in the recipe config.
+    pub recipe: String,
+
+    /// Files to process (or read from stdin if empty).
+    pub files: Vec<PathBuf>,
+
+    /// Print a unified diff, perform no writes.
+    #[arg(long = "dry-run", short = 'p')]
+    pub dry_run: bool,
+
+    /// Validate manifest and semantic checks without running.
+    #[arg(long = "validate-only")]
+    pub validate_only: bool,
+
+    /// Force JSON event output even on a TTY.
+    #[arg(long = "json")]
+    pub json: bool,
+}
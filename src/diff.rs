@@ -0,0 +1,542 @@
+use regex::Regex;
+use similar::TextDiff;
+use std::path::PathBuf;
+
+/// A caller-supplied rule that replaces a volatile substring (a temp-dir
+/// path, a timestamp, an absolute prefix) with a stable placeholder before a
+/// diff is rendered, so preview output stays reproducible across runs. A
+/// `pattern` prefixed with `regex:` (mirroring `--when`'s `glob:` prefix) is
+/// matched as a regex; anything else is matched literally.
+#[derive(Debug, Clone)]
+pub struct RedactionRule {
+    pub pattern: String,
+    pub placeholder: String,
+}
+
+/// Build [`RedactionRule`]s from the flat `(pattern, placeholder)` pairs
+/// `Pipeline::diff_redactions`/`--redact` store them as.
+pub fn redaction_rules(pairs: &[(String, String)]) -> Vec<RedactionRule> {
+    pairs
+        .iter()
+        .map(|(pattern, placeholder)| RedactionRule {
+            pattern: pattern.clone(),
+            placeholder: placeholder.clone(),
+        })
+        .collect()
+}
+
+/// Apply every redaction rule, in order, to `text`. A rule whose `regex:`
+/// pattern fails to compile is skipped (with a warning) rather than failing
+/// the whole diff, since a preview is still more useful with one un-redacted
+/// rule than with none at all.
+pub fn apply_redactions(text: &str, rules: &[RedactionRule]) -> String {
+    if rules.is_empty() {
+        return text.to_string();
+    }
+    let mut out = text.to_string();
+    for rule in rules {
+        out = match rule.pattern.strip_prefix("regex:") {
+            Some(pattern) => match Regex::new(pattern) {
+                Ok(re) => re.replace_all(&out, rule.placeholder.as_str()).into_owned(),
+                Err(e) => {
+                    eprintln!("WARN: invalid --redact regex {:?}: {}", pattern, e);
+                    out
+                }
+            },
+            None => out.replace(&rule.pattern, &rule.placeholder),
+        };
+    }
+    out
+}
+
+/// One `@@ -a,b +c,d @@` hunk of a unified diff, already formatted as the
+/// context/`-`/`+` lines that follow the header.
+#[derive(Debug, Clone)]
+pub struct DiffHunk {
+    pub header: String,
+    pub lines: Vec<String>,
+}
+
+/// Whether a [`StructuredLine`] was present on both sides (`Context`), only
+/// the old side (`Removed`), or only the new side (`Added`) — the same
+/// three states the `' '`/`-`/`+` line markers carry in rendered text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineTag {
+    Context,
+    Removed,
+    Added,
+    /// Not a content line: unified diff's `\ No newline at end of file`
+    /// marker, which follows the preceding [`StructuredLine`] to say that
+    /// line has no trailing newline in the file it came from. Carried as
+    /// its own line (with an empty `text`) rather than folded into the
+    /// line it describes, since it's schema, not content.
+    NoNewline,
+}
+
+/// Whether `line` (one element of [`DiffHunk::lines`]) is the `\ No newline
+/// at end of file` marker rather than an actual `' '`/`-`/`+` content line.
+/// [`unified_diff`] and [`parse_patch`] both produce this marker as its own
+/// line element, so it's recognized the same way regardless of which one
+/// produced the hunk.
+fn is_no_newline_marker(line: &str) -> bool {
+    line.trim_end_matches('\n') == "\\ No newline at end of file"
+}
+
+/// One line of a [`DiffHunk`], decomposed into its marker and text for
+/// consumers (`--format json`) that want structure instead of parsing the
+/// leading `' '`/`-`/`+` character themselves.
+#[derive(Debug, Clone)]
+pub struct StructuredLine {
+    pub tag: LineTag,
+    pub text: String,
+}
+
+/// A [`DiffHunk`], decomposed into the four `@@ -a,b +c,d @@` header numbers
+/// plus its lines split into [`StructuredLine`]s.
+#[derive(Debug, Clone)]
+pub struct StructuredHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<StructuredLine>,
+}
+
+/// Parse a hunk's `@@ -a,b +c,d @@` header and split its lines into
+/// [`StructuredLine`]s. The `,b`/`,d` counts are optional in unified-diff
+/// headers (a single-line side is sometimes written as just `-a`/`+c`), so
+/// both forms are accepted.
+pub fn structure_hunk(hunk: &DiffHunk) -> std::result::Result<StructuredHunk, String> {
+    let (old_start, old_lines, new_start, new_lines) = parse_hunk_header(&hunk.header)?;
+    let mut lines = hunk
+        .lines
+        .iter()
+        .map(|line| {
+            if is_no_newline_marker(line) {
+                return Ok(StructuredLine { tag: LineTag::NoNewline, text: String::new() });
+            }
+            let (marker, rest) = line.split_at(1);
+            let tag = match marker {
+                " " => LineTag::Context,
+                "-" => LineTag::Removed,
+                "+" => LineTag::Added,
+                other => return Err(format!("unrecognized diff line marker {:?}", other)),
+            };
+            Ok(StructuredLine { tag, text: rest.to_string() })
+        })
+        .collect::<std::result::Result<Vec<_>, String>>()?;
+    // A line followed by the marker has no trailing newline in its file;
+    // drop the one the patch text added to it, as `hunk_sides` does.
+    for i in 1..lines.len() {
+        if lines[i].tag == LineTag::NoNewline && lines[i - 1].tag != LineTag::NoNewline {
+            if lines[i - 1].text.ends_with('\n') {
+                lines[i - 1].text.pop();
+            }
+        }
+    }
+    Ok(StructuredHunk { old_start, old_lines, new_start, new_lines, lines })
+}
+
+fn parse_hunk_header(header: &str) -> std::result::Result<(usize, usize, usize, usize), String> {
+    let malformed = || format!("malformed hunk header: {}", header);
+    let rest = header.strip_prefix("@@ -").ok_or_else(malformed)?;
+    let (old_part, rest) = rest.split_once(' ').ok_or_else(malformed)?;
+    let new_part = rest.strip_prefix('+').ok_or_else(malformed)?;
+    let new_part = new_part.split(' ').next().ok_or_else(malformed)?;
+    let (old_start, old_lines) = parse_hunk_range(old_part).ok_or_else(malformed)?;
+    let (new_start, new_lines) = parse_hunk_range(new_part).ok_or_else(malformed)?;
+    Ok((old_start, old_lines, new_start, new_lines))
+}
+
+fn parse_hunk_range(part: &str) -> Option<(usize, usize)> {
+    match part.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((part.parse().ok()?, 1)),
+    }
+}
+
+/// Build grouped unified-diff hunks between `old` and `new`, keeping up to
+/// `context` unchanged lines around each run of changes (mirroring `diff -u`).
+pub fn unified_diff(old: &str, new: &str, context: usize) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let unified = diff.unified_diff().context_radius(context).to_owned();
+
+    unified
+        .iter_hunks()
+        .map(|hunk| {
+            let header = hunk.header().to_string();
+            let lines = hunk
+                .iter_changes()
+                .flat_map(|change| {
+                    let line = format!("{}{}", change.tag(), change);
+                    if change.missing_newline() {
+                        // `change`'s value has no trailing newline (it's the
+                        // file's last line and the file itself doesn't end in
+                        // one); diff -u's convention is to still newline-
+                        // terminate this line in the patch text and carry the
+                        // marker as its own following line — matching the
+                        // line-per-element shape `parse_patch` produces, so
+                        // both sides of this module agree on one
+                        // representation (see `LineTag::NoNewline`).
+                        vec![format!("{}\n", line), "\\ No newline at end of file\n".to_string()]
+                    } else {
+                        vec![line]
+                    }
+                })
+                .collect();
+            DiffHunk { header, lines }
+        })
+        .collect()
+}
+
+/// Render hunks as plain unified-diff text (no `---`/`+++` file headers;
+/// callers that know the file path add those themselves).
+pub fn format_unified_diff(hunks: &[DiffHunk]) -> String {
+    let mut out = String::new();
+    for hunk in hunks {
+        out.push_str(&hunk.header);
+        out.push('\n');
+        for line in &hunk.lines {
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+/// Compute a diff preview between `old` and `new`, applying redactions first
+/// so the output is stable regardless of where the run happened. Returns
+/// `None` when the (redacted) content is unchanged.
+pub fn preview(old: &str, new: &str, context: usize, redactions: &[RedactionRule]) -> Option<String> {
+    preview_with_hunks(old, new, context, redactions).map(|(text, _)| text)
+}
+
+/// Same as [`preview`], but also returns the hunks the text was rendered
+/// from, for callers (`--format json`) that want the diff as structured data
+/// rather than re-parsing the rendered text.
+pub fn preview_with_hunks(
+    old: &str,
+    new: &str,
+    context: usize,
+    redactions: &[RedactionRule],
+) -> Option<(String, Vec<DiffHunk>)> {
+    let old = apply_redactions(old, redactions);
+    let new = apply_redactions(new, redactions);
+    if old == new {
+        return None;
+    }
+    let hunks = unified_diff(&old, &new, context);
+    if hunks.is_empty() {
+        return None;
+    }
+    let text = format_unified_diff(&hunks);
+    Some((text, hunks))
+}
+
+/// One file's complete unified diff: the path its hunks apply to, plus the
+/// hunks themselves. Shared by [`parse_patch`] (reader) and `--format
+/// patch`'s writer (`Report::print_patch`, which reuses each file's already
+/// rendered `---`/`+++`/hunk text) so the two directions describe the same
+/// shape.
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    pub path: PathBuf,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Parse one or more unified diffs (as produced by `--format patch`, `git
+/// diff`, or `diff -u`) out of `input`, grouping hunks by the file they
+/// target. Only the `+++ b/...` header is trusted for the path; `---`'s
+/// `a/...` side is assumed to name the same file pre-edit and is otherwise
+/// ignored. Lines outside any `+++`/`@@` block (e.g. a `diff --git` line) are
+/// skipped rather than rejected, so output from common diff tools parses
+/// without extra flags.
+pub fn parse_patch(input: &str) -> std::result::Result<Vec<FilePatch>, String> {
+    let mut files = Vec::new();
+    let mut current: Option<FilePatch> = None;
+    let mut hunk: Option<(String, Vec<String>)> = None;
+
+    for raw_line in input.lines() {
+        if let Some(rest) = raw_line.strip_prefix("+++ ") {
+            if let Some(mut file) = current.take() {
+                flush_hunk(&mut file, &mut hunk);
+                files.push(file);
+            }
+            current = Some(FilePatch {
+                path: strip_patch_path(rest),
+                hunks: Vec::new(),
+            });
+        } else if raw_line.starts_with("--- ") {
+            // The old-file header; the path used comes from `+++` instead.
+        } else if raw_line.starts_with("@@ ") {
+            let file = current
+                .as_mut()
+                .ok_or_else(|| format!("hunk header before any '+++' file line: {}", raw_line))?;
+            flush_hunk(file, &mut hunk);
+            hunk = Some((raw_line.to_string(), Vec::new()));
+        } else if let Some((_, lines)) = hunk.as_mut() {
+            lines.push(format!("{}\n", raw_line));
+        }
+    }
+
+    if let Some(mut file) = current.take() {
+        flush_hunk(&mut file, &mut hunk);
+        files.push(file);
+    }
+
+    Ok(files)
+}
+
+fn flush_hunk(file: &mut FilePatch, hunk: &mut Option<(String, Vec<String>)>) {
+    if let Some((header, lines)) = hunk.take() {
+        file.hunks.push(DiffHunk { header, lines });
+    }
+}
+
+fn strip_patch_path(header_rest: &str) -> PathBuf {
+    // Strips a trailing tab-separated timestamp (`+++ b/foo.rs\t2026-...`)
+    // and the `a/`/`b/` prefix `git diff` and `format_patch` both use.
+    let raw = header_rest.split('\t').next().unwrap_or(header_rest);
+    let stripped = raw.strip_prefix("b/").or_else(|| raw.strip_prefix("a/")).unwrap_or(raw);
+    PathBuf::from(stripped)
+}
+
+/// Split a hunk's rendered lines back into its old-side and new-side line
+/// sequences (unchanged context lines appear on both sides), plus the
+/// 1-based starting line number of the old side, parsed from its `@@
+/// -N,M +... @@` header.
+fn hunk_sides(hunk: &DiffHunk) -> std::result::Result<(usize, Vec<String>, Vec<String>), String> {
+    let old_start = parse_hunk_old_start(&hunk.header)?;
+    let mut old: Vec<String> = Vec::with_capacity(hunk.lines.len());
+    let mut new: Vec<String> = Vec::with_capacity(hunk.lines.len());
+    let mut last_tag: Option<&str> = None;
+    for line in &hunk.lines {
+        if is_no_newline_marker(line) {
+            // The line it follows is newline-terminated in the patch text
+            // itself (unified diff always newline-terminates patch lines),
+            // but that trailing newline doesn't actually exist in the file
+            // this marker's side came from — strip it back off so
+            // `apply_patch` doesn't invent a newline the original lacked.
+            match last_tag {
+                Some(" ") => {
+                    if let Some(l) = old.last_mut() {
+                        l.pop();
+                    }
+                    if let Some(l) = new.last_mut() {
+                        l.pop();
+                    }
+                }
+                Some("-") => {
+                    if let Some(l) = old.last_mut() {
+                        l.pop();
+                    }
+                }
+                Some("+") => {
+                    if let Some(l) = new.last_mut() {
+                        l.pop();
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let (tag, rest) = line.split_at(1);
+        match tag {
+            " " => {
+                old.push(rest.to_string());
+                new.push(rest.to_string());
+            }
+            "-" => old.push(rest.to_string()),
+            "+" => new.push(rest.to_string()),
+            other => return Err(format!("unrecognized diff line marker {:?}", other)),
+        }
+        last_tag = Some(tag);
+    }
+    Ok((old_start, old, new))
+}
+
+fn parse_hunk_old_start(header: &str) -> std::result::Result<usize, String> {
+    let rest = header
+        .strip_prefix("@@ -")
+        .ok_or_else(|| format!("malformed hunk header: {}", header))?;
+    let num = rest.split(|c: char| c == ',' || c == ' ').next().unwrap_or("");
+    num.parse::<usize>()
+        .map_err(|_| format!("malformed hunk header: {}", header))
+}
+
+/// Apply every hunk in `patch` to `original`, returning the patched content
+/// and the number of hunks applied. Hunks are spliced in from the bottom of
+/// the file up, since their old-side line numbers are all relative to
+/// `original` and applying bottom-up keeps earlier offsets valid.
+///
+/// Each hunk's old-side lines must match `original` byte-for-byte at the
+/// hunk's recorded line number, exactly like [`crate::input::RipgrepAnchor`]
+/// checks a ripgrep match's reported line — a mismatch means the file has
+/// changed since the patch was generated, and is reported as a conflict
+/// rather than silently applied against the wrong lines.
+pub fn apply_patch(original: &str, patch: &FilePatch) -> std::result::Result<(String, usize), String> {
+    let mut lines: Vec<String> = original.split_inclusive('\n').map(|l| l.to_string()).collect();
+
+    let mut ordered: Vec<(usize, &DiffHunk)> = Vec::with_capacity(patch.hunks.len());
+    for hunk in &patch.hunks {
+        ordered.push((parse_hunk_old_start(&hunk.header)?, hunk));
+    }
+    ordered.sort_by_key(|&(start, _)| std::cmp::Reverse(start));
+
+    let mut applied = 0usize;
+    for (old_start, hunk) in ordered {
+        let (_, old_side, new_side) = hunk_sides(hunk)?;
+        let start_idx = old_start.saturating_sub(1);
+        let end_idx = start_idx + old_side.len();
+        if end_idx > lines.len() || lines[start_idx..end_idx] != old_side[..] {
+            return Err(format!(
+                "patch conflict at line {}: file content no longer matches the hunk's context (file changed since the patch was generated)",
+                old_start
+            ));
+        }
+        lines.splice(start_idx..end_idx, new_side);
+        applied += 1;
+    }
+
+    Ok((lines.concat(), applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redaction_replaces_volatile_substrings() {
+        let rules = [RedactionRule {
+            pattern: "/tmp/abc123".into(),
+            placeholder: "[ROOT]".into(),
+        }];
+        assert_eq!(apply_redactions("path: /tmp/abc123/file", &rules), "path: [ROOT]/file");
+    }
+
+    #[test]
+    fn redaction_regex_prefix_matches_pattern() {
+        let rules = [RedactionRule {
+            pattern: "regex:/tmp/[a-z0-9]+".into(),
+            placeholder: "[TMPDIR]".into(),
+        }];
+        assert_eq!(apply_redactions("path: /tmp/abc123/file", &rules), "path: [TMPDIR]/file");
+    }
+
+    #[test]
+    fn redaction_invalid_regex_is_skipped_not_fatal() {
+        let rules = [RedactionRule {
+            pattern: "regex:[".into(),
+            placeholder: "[X]".into(),
+        }];
+        assert_eq!(apply_redactions("unchanged", &rules), "unchanged");
+    }
+
+    #[test]
+    fn preview_none_when_equal_after_redaction() {
+        let rules = [RedactionRule {
+            pattern: "/tmp/a".into(),
+            placeholder: "[ROOT]".into(),
+        }];
+        assert_eq!(preview("x /tmp/a\n", "x /tmp/a\n", 3, &rules), None);
+    }
+
+    #[test]
+    fn preview_groups_changes_into_hunks() {
+        let old = "a\nb\nc\n";
+        let new = "a\nX\nc\n";
+        let out = preview(old, new, 1, &[]).unwrap();
+        assert!(out.starts_with("@@"));
+        assert!(out.contains("-b\n"));
+        assert!(out.contains("+X\n"));
+    }
+
+    #[test]
+    fn parse_patch_and_apply_round_trips_a_preview() {
+        let old = "a\nb\nc\n";
+        let new = "a\nX\nc\n";
+        let hunks = unified_diff(old, new, 1);
+        let text = format!("--- a/f.txt\n+++ b/f.txt\n{}", format_unified_diff(&hunks));
+
+        let files = parse_patch(&text).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, PathBuf::from("f.txt"));
+
+        let (patched, applied) = apply_patch(old, &files[0]).unwrap();
+        assert_eq!(patched, new);
+        assert_eq!(applied, 1);
+    }
+
+    #[test]
+    fn structure_hunk_decomposes_header_and_lines() {
+        let old = "a\nb\nc\n";
+        let new = "a\nX\nc\n";
+        let hunks = unified_diff(old, new, 1);
+        assert_eq!(hunks.len(), 1);
+
+        let structured = structure_hunk(&hunks[0]).unwrap();
+        assert_eq!(structured.old_start, 1);
+        assert_eq!(structured.new_start, 1);
+        assert_eq!(
+            structured.lines.iter().map(|l| l.tag).collect::<Vec<_>>(),
+            vec![LineTag::Context, LineTag::Removed, LineTag::Added, LineTag::Context]
+        );
+        assert_eq!(structured.lines[1].text, "b\n");
+        assert_eq!(structured.lines[2].text, "X\n");
+    }
+
+    #[test]
+    fn apply_patch_rejects_stale_context() {
+        let old = "a\nb\nc\n";
+        let new = "a\nX\nc\n";
+        let hunks = unified_diff(old, new, 1);
+        let patch = FilePatch { path: PathBuf::from("f.txt"), hunks };
+
+        let changed = "a\nb\nc\nd\n";
+        assert!(apply_patch(changed, &patch).is_ok());
+
+        let conflicting = "a\nZ\nc\n";
+        assert!(apply_patch(conflicting, &patch).is_err());
+    }
+
+    #[test]
+    fn unified_diff_marks_missing_trailing_newline() {
+        let old = "a\nb";
+        let new = "a\nX";
+        let hunks = unified_diff(old, new, 1);
+        let text = format_unified_diff(&hunks);
+        assert!(text.contains("-b\n\\ No newline at end of file\n"));
+        assert!(text.contains("+X\n\\ No newline at end of file\n"));
+    }
+
+    #[test]
+    fn structure_hunk_marks_no_newline_as_its_own_line_not_content() {
+        let old = "a\nb";
+        let new = "a\nX";
+        let hunks = unified_diff(old, new, 1);
+        assert_eq!(hunks.len(), 1);
+
+        let structured = structure_hunk(&hunks[0]).unwrap();
+        assert_eq!(
+            structured.lines.iter().map(|l| l.tag).collect::<Vec<_>>(),
+            vec![LineTag::Context, LineTag::Removed, LineTag::NoNewline, LineTag::Added, LineTag::NoNewline]
+        );
+        // The marker carries no content of its own; it doesn't get glued
+        // onto the line it follows.
+        assert_eq!(structured.lines[1].text, "b");
+        assert_eq!(structured.lines[2].text, "");
+        assert_eq!(structured.lines[3].text, "X");
+        assert_eq!(structured.lines[4].text, "");
+    }
+
+    #[test]
+    fn parse_patch_accepts_no_newline_marker_from_a_real_diff() {
+        let text = "--- a/f.txt\n+++ b/f.txt\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+X\n\\ No newline at end of file\n";
+
+        let files = parse_patch(text).unwrap();
+        assert_eq!(files.len(), 1);
+
+        let (patched, applied) = apply_patch("a\nb", &files[0]).unwrap();
+        assert_eq!(patched, "a\nX");
+        assert_eq!(applied, 1);
+    }
+}
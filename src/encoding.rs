@@ -0,0 +1,136 @@
+use crate::model::TextEncoding;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// A file's bytes, decoded to UTF-8 for matching, plus enough information
+/// ([`Self::encode_back`]) to transcode the replaced result back to the
+/// file's original on-disk encoding and BOM convention.
+pub struct DecodedText {
+    pub text: String,
+    encoding: &'static Encoding,
+    had_bom: bool,
+}
+
+impl DecodedText {
+    /// Decode `bytes` to UTF-8 text for the `Replacer` to match against.
+    ///
+    /// [`TextEncoding::Auto`] sniffs a UTF-8/UTF-16LE/UTF-16BE BOM via
+    /// [`Encoding::for_bom`], falling back to UTF-8 when none is present;
+    /// an explicit encoding is used as-is, ignoring any BOM. Already-UTF-8
+    /// bytes (the common case, including pure ASCII, which is a UTF-8
+    /// subset) are copied once straight into a `String` — no
+    /// transcoding pass over the buffer at all.
+    pub fn decode(bytes: &[u8], requested: TextEncoding) -> Self {
+        let (encoding, had_bom) = match requested {
+            TextEncoding::Auto => match Encoding::for_bom(bytes) {
+                Some((encoding, _bom_len)) => (encoding, true),
+                None => (UTF_8, false),
+            },
+            TextEncoding::Utf8 => (UTF_8, bytes.starts_with(b"\xEF\xBB\xBF")),
+            TextEncoding::Utf16Le => (UTF_16LE, bytes.starts_with(b"\xFF\xFE")),
+            TextEncoding::Utf16Be => (UTF_16BE, bytes.starts_with(b"\xFE\xFF")),
+            TextEncoding::Latin1 => (WINDOWS_1252, false),
+        };
+
+        if encoding == UTF_8 && !had_bom {
+            if let Ok(text) = String::from_utf8(bytes.to_vec()) {
+                return Self { text, encoding, had_bom };
+            }
+        }
+
+        let content = if had_bom {
+            &bytes[bom_len(encoding)..]
+        } else {
+            bytes
+        };
+        let (text, _had_errors) = encoding.decode_without_bom_handling(content);
+        Self { text: text.into_owned(), encoding, had_bom }
+    }
+
+    /// Re-encode `text` (the `Replacer`'s output) back to this file's
+    /// original encoding, re-adding the BOM it was decoded with, if any.
+    /// Unmappable characters (e.g. a CJK character written into a
+    /// Latin-1 file) are replaced per the Encoding Standard rather than
+    /// failing the write outright.
+    pub fn encode_back(&self, text: &str) -> Vec<u8> {
+        if self.encoding == UTF_8 && !self.had_bom {
+            return text.as_bytes().to_vec();
+        }
+
+        let (encoded, _, _) = self.encoding.encode(text);
+        if self.had_bom {
+            let mut out = bom_bytes(self.encoding).to_vec();
+            out.extend_from_slice(&encoded);
+            out
+        } else {
+            encoded.into_owned()
+        }
+    }
+}
+
+fn bom_len(encoding: &'static Encoding) -> usize {
+    bom_bytes(encoding).len()
+}
+
+fn bom_bytes(encoding: &'static Encoding) -> &'static [u8] {
+    if encoding == UTF_16LE {
+        b"\xFF\xFE"
+    } else if encoding == UTF_16BE {
+        b"\xFE\xFF"
+    } else {
+        b"\xEF\xBB\xBF"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_utf8_is_a_zero_copy_passthrough() {
+        let decoded = DecodedText::decode(b"hello world", TextEncoding::Auto);
+        assert_eq!(decoded.text, "hello world");
+        assert_eq!(decoded.encode_back(&decoded.text), b"hello world");
+    }
+
+    #[test]
+    fn utf8_bom_is_detected_and_round_trips() {
+        let mut bytes = b"\xEF\xBB\xBF".to_vec();
+        bytes.extend_from_slice(b"hi");
+        let decoded = DecodedText::decode(&bytes, TextEncoding::Auto);
+        assert_eq!(decoded.text, "hi");
+        assert_eq!(decoded.encode_back("bye"), b"\xEF\xBB\xBFbye");
+    }
+
+    #[test]
+    fn utf16le_bom_is_auto_detected() {
+        // "hi" as UTF-16LE with a BOM: FF FE 68 00 69 00
+        let bytes = b"\xFF\xFEh\x00i\x00".to_vec();
+        let decoded = DecodedText::decode(&bytes, TextEncoding::Auto);
+        assert_eq!(decoded.text, "hi");
+        assert_eq!(decoded.encode_back("hi"), bytes);
+    }
+
+    #[test]
+    fn utf16be_without_bom_uses_the_explicit_encoding() {
+        // "hi" as UTF-16BE, no BOM: 00 68 00 69
+        let bytes = b"\x00h\x00i".to_vec();
+        let decoded = DecodedText::decode(&bytes, TextEncoding::Utf16Be);
+        assert_eq!(decoded.text, "hi");
+        assert_eq!(decoded.encode_back("hi"), bytes);
+    }
+
+    #[test]
+    fn latin1_high_bytes_decode_one_to_one() {
+        // 0xE9 is "é" in Latin-1.
+        let bytes = vec![b'c', b'a', b'f', 0xE9];
+        let decoded = DecodedText::decode(&bytes, TextEncoding::Latin1);
+        assert_eq!(decoded.text, "caf\u{e9}");
+        assert_eq!(decoded.encode_back(&decoded.text), bytes);
+    }
+
+    #[test]
+    fn auto_with_no_bom_falls_back_to_utf8() {
+        let decoded = DecodedText::decode("caf\u{e9}".as_bytes(), TextEncoding::Auto);
+        assert_eq!(decoded.text, "caf\u{e9}");
+    }
+}
@@ -1,21 +1,42 @@
+use crate::encoding::DecodedText;
 use crate::error::{Error, Result};
-use crate::model::{Pipeline, Operation, Transaction, Symlinks, BinaryFileMode};
+use crate::model::{Pipeline, Operation, Transaction, Symlinks, BinaryFileMode, ValidationMode, CaseKind, InsertPosition, LineRange, NewlineStyle, WriteStrategy};
 use crate::replacer::Replacer;
-use crate::write::{write_file, stage_file, WriteOptions, StagedEntry};
+use crate::write::{write_file, write_file_streamed, stage_file, WriteOptions, StagedEntry};
 use crate::reporter::{Report, FileResult};
-use crate::input::InputItem;
+use crate::input::{InputItem, RipgrepAnchor};
 use crate::model::ReplacementRange;
 use crate::transaction::TransactionManager;
-use similar::{ChangeTag, TextDiff};
+use crate::diff::FilePatch;
+use crate::rustfix::{apply_rustfix, RustfixPatch};
+use crate::when::{FileContext, WhenExpr};
+use crate::cache::Cache;
 use std::fs;
 use std::path::{Path, PathBuf, Component};
 use std::env;
+use std::sync::Mutex;
 use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+/// Shared, lock-protected `--cache-file` state threaded through
+/// `process_file` for the life of one `execute` run. `ops_hash` is computed
+/// once up front since the pipeline's operations don't change per file.
+struct CacheContext {
+    cache: Mutex<Cache>,
+    ops_hash: u64,
+}
+
 /// Execute a pipeline and produce a report.
-pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report> {
+///
+/// `pre_errors` are folded into the report before any file is processed and
+/// before the `--transaction all` commit decision is made — e.g. directory-
+/// walk failures the caller collected before it had any `InputItem`s to hand
+/// over. Folding them in here, rather than merging them into the returned
+/// report afterward, ensures a walk failure makes `report.exit_code()`
+/// non-zero in time to block the commit, not just the process exit code.
+pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>, pre_errors: Vec<FileResult>) -> Result<Report> {
     // validate semantic constraints
     if inputs.is_empty() {
          return Err(Error::Validation("No input sources specified".into()));
@@ -23,10 +44,19 @@ pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report>
     if pipeline.operations.is_empty() {
         return Err(Error::Validation("No operations specified".into()));
     }
+    if pipeline.transaction == Transaction::All && pipeline.write_strategy == WriteStrategy::InPlace {
+        return Err(Error::Validation(
+            "--write-strategy in-place is incompatible with --transaction all: in-place writes commit immediately and have no temp file to stage for an all-or-nothing swap".into(),
+        ));
+    }
 
     // Build glob sets
     let (include_set, exclude_set) = build_glob_sets(&pipeline.glob_include, &pipeline.glob_exclude)?;
 
+    // Parse the --when expression once up front so a bad expression is
+    // reported before any file is touched, rather than mid-run.
+    let when_expr = pipeline.when.as_deref().map(WhenExpr::parse).transpose()?;
+
     let validate_only = pipeline.validate_only;
     // If validate_only is set, force dry_run to true
     if validate_only {
@@ -34,6 +64,9 @@ pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report>
     }
 
     let mut report = Report::new(pipeline.dry_run, validate_only);
+    for pre_error in pre_errors {
+        report.add_result(pre_error);
+    }
 
     let mut tm = if pipeline.transaction == Transaction::All {
         Some(TransactionManager::new())
@@ -44,12 +77,25 @@ pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report>
     let cwd = env::current_dir().map_err(|e| Error::Validation(format!("Failed to get current directory: {}", e)))?;
     let should_stage = pipeline.transaction == Transaction::All;
 
+    // `--cache-file`: disabled outright under `dry_run`/`validate_only`,
+    // since neither run should be allowed to mark a file as "already
+    // processed" when nothing was actually written.
+    let cache_ctx = if !pipeline.dry_run && !validate_only {
+        pipeline.cache_path.as_ref().map(|path| CacheContext {
+            cache: Mutex::new(Cache::load(Path::new(path))),
+            ops_hash: crate::cache::hash_operations(&pipeline.operations),
+        })
+    } else {
+        None
+    };
+
     // Define the processing function (closure)
     let process_item = |input: InputItem| -> (FileResult, Option<StagedEntry>) {
         // Check globs first
         let path_for_glob = match &input {
             InputItem::Path(p) => Some(p.as_path()),
             InputItem::RipgrepMatch { path, .. } => Some(path.as_path()),
+            InputItem::EditPlan { path, .. } => Some(path.as_path()),
             InputItem::StdinText(_) => None,
         };
 
@@ -65,7 +111,11 @@ pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report>
                         error: None,
                         skipped: Some("glob exclude".into()), // "glob exclude" covers "not in include"
                         diff: None,
+                        diff_hunks: None,
+                        io: None,
                         generated_content: None,
+                        edits: None,
+                        backup_path: None,
                     }, None);
                 }
              }
@@ -79,7 +129,11 @@ pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report>
                         error: None,
                         skipped: Some("glob exclude".into()),
                         diff: None,
+                        diff_hunks: None,
+                        io: None,
                         generated_content: None,
+                        edits: None,
+                        backup_path: None,
                     }, None);
                  }
              }
@@ -88,11 +142,16 @@ pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report>
         match input {
             InputItem::Path(path_buf) => {
                 let path_str = path_buf.to_string_lossy().into_owned();
-                process_file(&path_str, &pipeline.operations, &pipeline, None, should_stage)
+                process_file(&path_str, &pipeline.operations, &pipeline, None, None, should_stage, when_expr.as_ref(), &[], &cwd, cache_ctx.as_ref())
             }
-            InputItem::RipgrepMatch { path, matches } => {
+            InputItem::RipgrepMatch { path, matches, anchors } => {
                 let path_str = path.to_string_lossy().into_owned();
-                process_file(&path_str, &pipeline.operations, &pipeline, Some(&matches), should_stage)
+                process_file(&path_str, &pipeline.operations, &pipeline, Some(&matches), None, should_stage, when_expr.as_ref(), &anchors, &cwd, cache_ctx.as_ref())
+            }
+            InputItem::EditPlan { path, ranges, replacement } => {
+                let path_str = path.to_string_lossy().into_owned();
+                let matches = if ranges.is_empty() { None } else { Some(ranges.as_slice()) };
+                process_file(&path_str, &pipeline.operations, &pipeline, matches, replacement.as_deref(), should_stage, when_expr.as_ref(), &[], &cwd, cache_ctx.as_ref())
             }
             InputItem::StdinText(text) => {
                  let result = process_text(text, &pipeline.operations, &pipeline);
@@ -101,54 +160,888 @@ pub fn execute(mut pipeline: Pipeline, inputs: Vec<InputItem>) -> Result<Report>
         }
     };
 
-    // Execute in parallel or serial
+    // Execute in parallel or serial. `into_par_iter().map(..).collect()` is an
+    // *indexed* parallel map, so results come back in the original input
+    // order even though files are processed out of order across workers —
+    // required for deterministic `file` events and `run_end` aggregation.
     #[cfg(feature = "parallel")]
-    let results: Vec<(FileResult, Option<StagedEntry>)> = inputs.into_par_iter().map(process_item).collect();
+    let results: Vec<(FileResult, Option<StagedEntry>)> = {
+        let run_all = || inputs.into_par_iter().map(process_item).collect();
+        match pipeline.threads {
+            Some(n) => {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(n)
+                    .build()
+                    .map_err(|e| Error::Validation(format!("Failed to build thread pool: {}", e)))?;
+                pool.install(run_all)
+            }
+            // `None` uses rayon's global pool, already sized to available parallelism.
+            None => run_all(),
+        }
+    };
 
     #[cfg(not(feature = "parallel"))]
     let results: Vec<(FileResult, Option<StagedEntry>)> = inputs.into_iter().map(process_item).collect();
 
-    // Aggregate results
-    for (result, staged) in results {
-        let has_error = result.error.is_some();
-        report.add_result(result);
+    // Aggregate results
+    for (result, staged) in results {
+        let has_error = result.error.is_some();
+        report.add_result(result);
+
+        if let Some(s) = staged {
+            if let Some(manager) = &mut tm {
+                manager.stage(s);
+            }
+        }
+
+        if has_error {
+            break;
+        }
+    }
+
+    // Policy checks
+    if pipeline.require_match && report.replacements == 0 {
+        report.policy_violation = Some("No matches found (--require-match)".into());
+    } else if let Some(expected) = pipeline.expect {
+        if report.replacements != expected {
+            report.policy_violation = Some(format!(
+                "Expected {} replacements, found {} (--expect)",
+                expected, report.replacements
+            ));
+        }
+    } else if pipeline.fail_on_change && report.modified > 0 {
+        report.policy_violation = Some(format!(
+            "Changes detected in {} files (--fail-on-change)",
+            report.modified
+        ));
+    } else if pipeline.fail_on_blocked {
+        if let Some(blocked) = count_blocked(&report) {
+            report.policy_violation = Some(format!(
+                "{} file(s) rejected as outside the allowed write roots (--fail-on-blocked)",
+                blocked
+            ));
+        }
+    }
+
+    // Commit if no errors and no policy violations
+    if report.exit_code() == 0 {
+        if let Some(manager) = tm {
+            manager.commit().map_err(|e| Error::TransactionFailure(e.to_string()))?;
+        }
+    }
+
+    // Persist `--cache-file` updates picked up this run. Best-effort: a
+    // failed write here just means the next run starts from a colder cache,
+    // not a reason to fail an otherwise-successful run.
+    if let Some(ctx) = &cache_ctx {
+        let path = pipeline.cache_path.as_ref().expect("cache_ctx is only built from a Some(cache_path)");
+        if let Err(e) = ctx.cache.lock().unwrap().save(Path::new(path)) {
+            eprintln!("WARN: failed to save cache file {}: {}", path, e);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Run `--stdin-text --stream`: apply the pipeline's one operation to stdin
+/// in bounded-memory chunks (see [`crate::input::stream_stdin_text`]) and
+/// write the result straight to stdout, rather than going through
+/// `execute`'s whole-buffer `InputItem::StdinText` path.
+///
+/// Only a single `Operation::Replace` is supported — there's no full buffer
+/// to thread a second operation's output back into — and `--dry-run` is
+/// rejected outright, since producing a diff needs the whole transformed
+/// content, which streaming mode never holds at once.
+pub fn execute_stdin_streaming(pipeline: &Pipeline, max_match_window: usize) -> Result<Report> {
+    let op = match pipeline.operations.as_slice() {
+        [op @ Operation::Replace { .. }] => op,
+        [] => return Err(Error::Validation("No operations specified".into())),
+        _ => return Err(Error::Validation(
+            "--stream only supports a single FIND/REPLACE operation".into(),
+        )),
+    };
+    if pipeline.dry_run {
+        return Err(Error::Validation(
+            "--stream does not support --dry-run: no full content is retained to diff".into(),
+        ));
+    }
+
+    let with = match op {
+        Operation::Replace { with, .. } => with,
+        _ => unreachable!("narrowed to Operation::Replace above"),
+    };
+    let replacer = build_replacer(op, with, None)?;
+    let (modified, replacements) = crate::input::stream_stdin_text(&replacer, max_match_window)?;
+
+    let mut report = Report::new(pipeline.dry_run, pipeline.validate_only);
+    report.add_result(FileResult {
+        path: PathBuf::from("<stdin>"),
+        modified,
+        replacements,
+        error: None,
+        skipped: None,
+        diff: None,
+        diff_hunks: None,
+        io: None,
+        generated_content: None,
+        edits: None,
+        backup_path: None,
+    });
+
+    if pipeline.require_match && report.replacements == 0 {
+        report.policy_violation = Some("No matches found (--require-match)".into());
+    } else if let Some(expected) = pipeline.expect {
+        if report.replacements != expected {
+            report.policy_violation = Some(format!(
+                "Expected {} replacements, found {} (--expect)",
+                expected, report.replacements
+            ));
+        }
+    } else if pipeline.fail_on_change && report.modified > 0 {
+        report.policy_violation = Some(format!(
+            "Changes detected in {} files (--fail-on-change)",
+            report.modified
+        ));
+    }
+
+    Ok(report)
+}
+
+/// Run `--stream` against on-disk files: apply the pipeline's one operation
+/// to each path in bounded memory via [`Replacer::replace_stream`], instead
+/// of `process_file`'s whole-buffer read (see `write::read_file`) — whose
+/// mmap fast path still maps the whole file into address space, rather than
+/// bounding how much of it is resident at once.
+///
+/// Shares [`execute_stdin_streaming`]'s restrictions (single
+/// `Operation::Replace`, no `--dry-run`), plus two more, both rejected
+/// outright rather than risking data loss or silently dropping a
+/// guarantee: each file is written as it's read, so there's no staged
+/// content left for `--transaction all` to roll back if a later file
+/// fails; and `WriteStrategy::InPlace` truncates the target before
+/// anything is read from it, which for `--stream` is the same path read
+/// and written through the same inode — the read side would see the file
+/// already empty.
+///
+/// `pre_errors` are folded into the report the same way [`execute`] does —
+/// e.g. directory-walk failures the caller collected before resolving any
+/// `--stream`-able paths — so a `--recursive --stream` run that partly
+/// failed to walk still reports those failures instead of silently
+/// dropping them.
+pub fn execute_file_streaming(pipeline: &Pipeline, paths: Vec<PathBuf>, pre_errors: Vec<FileResult>) -> Result<Report> {
+    if paths.is_empty() && pre_errors.is_empty() {
+        return Err(Error::Validation("No input sources specified".into()));
+    }
+    let op = match pipeline.operations.as_slice() {
+        [op @ Operation::Replace { .. }] => op,
+        [] => return Err(Error::Validation("No operations specified".into())),
+        _ => return Err(Error::Validation(
+            "--stream only supports a single FIND/REPLACE operation".into(),
+        )),
+    };
+    if pipeline.dry_run {
+        return Err(Error::Validation(
+            "--stream does not support --dry-run: no full content is retained to diff".into(),
+        ));
+    }
+    if pipeline.transaction == Transaction::All {
+        return Err(Error::Validation(
+            "--stream does not support --transaction all: each file is written as it's read, \
+             leaving nothing staged to roll back if a later file fails"
+                .into(),
+        ));
+    }
+    if pipeline.write_strategy == WriteStrategy::InPlace {
+        return Err(Error::Validation(
+            "--stream does not support --write-strategy in-place: that truncates the target \
+             file before reading it, and --stream reads and writes the same path through the \
+             same inode, so the read side would see the file already empty"
+                .into(),
+        ));
+    }
+
+    let with = match op {
+        Operation::Replace { with, .. } => with,
+        _ => unreachable!("narrowed to Operation::Replace above"),
+    };
+    let replacer = build_replacer(op, with, None)?;
+
+    let cwd = env::current_dir().map_err(|e| Error::Validation(format!("Failed to get current directory: {}", e)))?;
+    let options = WriteOptions {
+        no_follow_symlinks: pipeline.symlinks != Symlinks::Follow,
+        permissions: pipeline.permissions.clone(),
+        force_buffered_read: pipeline.no_mmap,
+        mmap_min_size: pipeline.mmap_min_size.unwrap_or(crate::write::MMAP_MIN_SIZE),
+        preserve_ownership: pipeline.preserve_ownership,
+        preserve_timestamps: pipeline.preserve_timestamps,
+        preserve_xattrs: pipeline.preserve_xattrs,
+        durable: pipeline.durable,
+        backup: pipeline.backup_suffix.clone().map(|suffix| crate::write::BackupSpec { suffix }),
+        write_strategy: pipeline.write_strategy,
+    };
+
+    let mut report = Report::new(pipeline.dry_run, pipeline.validate_only);
+    for pre_error in pre_errors {
+        report.add_result(pre_error);
+    }
+    for path in paths {
+        report.add_result(stream_one_file(&path, &replacer, &pipeline.allow_write, &pipeline.deny_write, &cwd, &options));
+    }
+
+    if pipeline.require_match && report.replacements == 0 {
+        report.policy_violation = Some("No matches found (--require-match)".into());
+    } else if let Some(expected) = pipeline.expect {
+        if report.replacements != expected {
+            report.policy_violation = Some(format!(
+                "Expected {} replacements, found {} (--expect)",
+                expected, report.replacements
+            ));
+        }
+    } else if pipeline.fail_on_change && report.modified > 0 {
+        report.policy_violation = Some(format!(
+            "Changes detected in {} files (--fail-on-change)",
+            report.modified
+        ));
+    }
+
+    Ok(report)
+}
+
+/// Stream one file through `replacer` via `write::write_file_streamed`,
+/// producing the `FileResult` for it. No `diff`/`generated_content` is
+/// attached — same as `execute_stdin_streaming` — since the full
+/// transformed content is never held in memory to report.
+fn stream_one_file(
+    path: &Path,
+    replacer: &Replacer,
+    allow_write: &[String],
+    deny_write: &[String],
+    cwd: &Path,
+    options: &WriteOptions,
+) -> FileResult {
+    let path_buf = path.to_path_buf();
+
+    if !check_write_allowed(&path_buf, cwd, allow_write, deny_write) {
+        return FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: None,
+            skipped: Some("outside-allowed-root".into()),
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        };
+    }
+
+    let result = fs::File::open(&path_buf)
+        .map_err(Error::Io)
+        .and_then(|src| write_file_streamed(&path_buf, options, |dst| replacer.replace_stream(src, dst)));
+
+    match result {
+        Ok((preservation, backup_path, replacements)) => {
+            warn_on_preservation_failures(&path_buf, preservation);
+            FileResult {
+                path: path_buf,
+                modified: replacements > 0,
+                replacements,
+                error: None,
+                skipped: None,
+                diff: None,
+                diff_hunks: None,
+                io: None,
+                generated_content: None,
+                edits: None,
+                backup_path,
+            }
+        }
+        Err(e) => FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: Some(e.to_string()),
+            skipped: None,
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        },
+    }
+}
+
+/// Run `--patch`: apply a set of unified diffs directly to their target
+/// files. Bypasses the `Operation::Replace` pipeline entirely, since each
+/// hunk already carries its own exact replacement text rather than a
+/// FIND/REPLACE pattern, but otherwise shares `Pipeline`'s dry-run,
+/// transaction, and write settings with the normal `execute` path.
+pub fn execute_patch(pipeline: &Pipeline, patches: Vec<FilePatch>) -> Result<Report> {
+    if patches.is_empty() {
+        return Err(Error::Validation("No input sources specified".into()));
+    }
+    if pipeline.transaction == Transaction::All && pipeline.write_strategy == WriteStrategy::InPlace {
+        return Err(Error::Validation(
+            "--write-strategy in-place is incompatible with --transaction all: in-place writes commit immediately and have no temp file to stage for an all-or-nothing swap".into(),
+        ));
+    }
+
+    let mut pipeline = pipeline.clone();
+    let validate_only = pipeline.validate_only;
+    if validate_only {
+        pipeline.dry_run = true;
+    }
+
+    let mut report = Report::new(pipeline.dry_run, validate_only);
+    let mut tm = if pipeline.transaction == Transaction::All {
+        Some(TransactionManager::new())
+    } else {
+        None
+    };
+    let should_stage = pipeline.transaction == Transaction::All;
+    let cwd = env::current_dir().map_err(|e| Error::Validation(format!("Failed to get current directory: {}", e)))?;
+
+    for patch in &patches {
+        let (result, staged) = process_patch_file(patch, &pipeline, should_stage, &cwd);
+        let has_error = result.error.is_some();
+        report.add_result(result);
+
+        if let Some(s) = staged {
+            if let Some(manager) = &mut tm {
+                manager.stage(s);
+            }
+        }
+
+        if has_error {
+            break;
+        }
+    }
+
+    if pipeline.require_match && report.replacements == 0 {
+        report.policy_violation = Some("No matches found (--require-match)".into());
+    } else if let Some(expected) = pipeline.expect {
+        if report.replacements != expected {
+            report.policy_violation = Some(format!(
+                "Expected {} replacements, found {} (--expect)",
+                expected, report.replacements
+            ));
+        }
+    } else if pipeline.fail_on_change && report.modified > 0 {
+        report.policy_violation = Some(format!(
+            "Changes detected in {} files (--fail-on-change)",
+            report.modified
+        ));
+    } else if pipeline.fail_on_blocked {
+        if let Some(blocked) = count_blocked(&report) {
+            report.policy_violation = Some(format!(
+                "{} file(s) rejected as outside the allowed write roots (--fail-on-blocked)",
+                blocked
+            ));
+        }
+    }
+
+    if report.exit_code() == 0 {
+        if let Some(manager) = tm {
+            manager.commit().map_err(|e| Error::TransactionFailure(e.to_string()))?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Count files skipped for falling outside the allowed write roots
+/// (`check_write_allowed`), for the `--fail-on-blocked` policy check.
+/// Returns `None` (no violation) when none were blocked.
+fn count_blocked(report: &Report) -> Option<usize> {
+    let blocked = report
+        .files
+        .iter()
+        .filter(|f| f.skipped.as_deref() == Some("outside-allowed-root"))
+        .count();
+    if blocked > 0 { Some(blocked) } else { None }
+}
+
+/// Apply one file's patch: read it, splice in every hunk via
+/// [`crate::diff::apply_patch`], then diff/write/stage exactly as
+/// `process_file` would for a regular FIND/REPLACE edit.
+fn process_patch_file(patch: &FilePatch, pipeline: &Pipeline, should_stage: bool, cwd: &Path) -> (FileResult, Option<StagedEntry>) {
+    let path_buf = patch.path.clone();
+
+    if !check_write_allowed(&path_buf, cwd, &pipeline.allow_write, &pipeline.deny_write) {
+        return (FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: None,
+            skipped: Some("outside-allowed-root".into()),
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None);
+    }
+
+    if let Ok(metadata) = fs::symlink_metadata(&path_buf) {
+        if metadata.is_symlink() {
+            match pipeline.symlinks {
+                Symlinks::Follow => {}
+                Symlinks::Skip => {
+                    return (FileResult {
+                        path: path_buf,
+                        modified: false,
+                        replacements: 0,
+                        error: None,
+                        skipped: Some("symlink".into()),
+                        diff: None,
+                        diff_hunks: None,
+                        io: None,
+                        generated_content: None,
+                        edits: None,
+                        backup_path: None,
+                    }, None);
+                }
+                Symlinks::Error => {
+                    return (FileResult {
+                        path: path_buf,
+                        modified: false,
+                        replacements: 0,
+                        error: Some("Encountered symlink with --symlinks error".into()),
+                        skipped: None,
+                        diff: None,
+                        diff_hunks: None,
+                        io: None,
+                        generated_content: None,
+                        edits: None,
+                        backup_path: None,
+                    }, None);
+                }
+            }
+        }
+    }
+
+    let options = WriteOptions {
+        no_follow_symlinks: pipeline.symlinks != Symlinks::Follow,
+        permissions: pipeline.permissions.clone(),
+        force_buffered_read: pipeline.no_mmap,
+        mmap_min_size: pipeline.mmap_min_size.unwrap_or(crate::write::MMAP_MIN_SIZE),
+        preserve_ownership: pipeline.preserve_ownership,
+        preserve_timestamps: pipeline.preserve_timestamps,
+        preserve_xattrs: pipeline.preserve_xattrs,
+        durable: pipeline.durable,
+        backup: pipeline.backup_suffix.clone().map(|suffix| crate::write::BackupSpec { suffix }),
+        write_strategy: pipeline.write_strategy,
+    };
+
+    let content_bytes = match crate::write::read_file(&path_buf, &options) {
+        Ok(b) => b,
+        Err(e) => return (FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: Some(e.to_string()),
+            skipped: None,
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None),
+    };
+
+    let io = match &content_bytes {
+        crate::write::FileBytes::Buffered(_) => "buffered",
+        crate::write::FileBytes::Mapped(_) => "mmap",
+    };
+
+    let decoded = DecodedText::decode(&content_bytes, pipeline.encoding);
+    let original = decoded.text.clone();
+    drop(content_bytes);
+
+    let (new_content, applied) = match crate::diff::apply_patch(&original, patch) {
+        Ok(result) => result,
+        Err(e) => return (FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: Some(e),
+            skipped: None,
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None),
+    };
+
+    let modified = new_content != original;
+    let (diff, diff_hunks) = if pipeline.dry_run {
+        generate_diff(&original, &new_content, pipeline.diff_context, &pipeline.diff_redactions)
+    } else {
+        (None, None)
+    };
+
+    if !modified || pipeline.dry_run || pipeline.no_write {
+        return (FileResult {
+            path: path_buf,
+            modified,
+            replacements: applied,
+            error: None,
+            skipped: None,
+            diff,
+            diff_hunks,
+            io: Some(io.to_string()),
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None);
+    }
+
+    if should_stage {
+        match stage_file(&path_buf, &decoded.encode_back(&new_content), &options) {
+            Ok(staged) => {
+                warn_on_preservation_failures(&path_buf, staged.preservation());
+                let backup_path = staged.backup_path();
+                (FileResult {
+                    path: path_buf,
+                    modified,
+                    replacements: applied,
+                    error: None,
+                    skipped: None,
+                    diff,
+                    diff_hunks,
+                    io: Some(io.to_string()),
+                    generated_content: None,
+                    edits: None,
+                    backup_path,
+                }, Some(staged))
+            }
+            Err(e) => (FileResult {
+                path: path_buf,
+                modified: false,
+                replacements: 0,
+                error: Some(e.to_string()),
+                skipped: None,
+                diff: None,
+                diff_hunks: None,
+                io: None,
+                generated_content: None,
+                edits: None,
+                backup_path: None,
+            }, None),
+        }
+    } else {
+        let backup_path = match write_file(&path_buf, &decoded.encode_back(&new_content), &options) {
+            Ok((preservation, backup_path)) => {
+                warn_on_preservation_failures(&path_buf, preservation);
+                backup_path
+            }
+            Err(e) => return (FileResult {
+                path: path_buf,
+                modified: false,
+                replacements: 0,
+                error: Some(e.to_string()),
+                skipped: None,
+                diff: None,
+                diff_hunks: None,
+                io: None,
+                generated_content: None,
+                edits: None,
+                backup_path: None,
+            }, None),
+        };
+
+        (FileResult {
+            path: path_buf,
+            modified,
+            replacements: applied,
+            error: None,
+            skipped: None,
+            diff,
+            diff_hunks,
+            io: Some(io.to_string()),
+            generated_content: None,
+            edits: None,
+            backup_path,
+        }, None)
+    }
+}
+
+/// Same shape as [`execute_patch`], but for a `--rustfix` run: each
+/// [`RustfixPatch`] already carries its surviving, non-overlapping
+/// suggestions straight from rustc/clippy, with no FIND/REPLACE operation
+/// involved.
+pub fn execute_rustfix(pipeline: &Pipeline, patches: Vec<RustfixPatch>) -> Result<Report> {
+    if patches.is_empty() {
+        return Err(Error::Validation("No input sources specified".into()));
+    }
+    if pipeline.transaction == Transaction::All && pipeline.write_strategy == WriteStrategy::InPlace {
+        return Err(Error::Validation(
+            "--write-strategy in-place is incompatible with --transaction all: in-place writes commit immediately and have no temp file to stage for an all-or-nothing swap".into(),
+        ));
+    }
+
+    let mut pipeline = pipeline.clone();
+    let validate_only = pipeline.validate_only;
+    if validate_only {
+        pipeline.dry_run = true;
+    }
+
+    let mut report = Report::new(pipeline.dry_run, validate_only);
+    let mut tm = if pipeline.transaction == Transaction::All {
+        Some(TransactionManager::new())
+    } else {
+        None
+    };
+    let should_stage = pipeline.transaction == Transaction::All;
+    let cwd = env::current_dir().map_err(|e| Error::Validation(format!("Failed to get current directory: {}", e)))?;
+
+    for patch in &patches {
+        let (result, staged) = process_rustfix_file(patch, &pipeline, should_stage, &cwd);
+        let has_error = result.error.is_some();
+        report.add_result(result);
+
+        if let Some(s) = staged {
+            if let Some(manager) = &mut tm {
+                manager.stage(s);
+            }
+        }
+
+        if has_error {
+            break;
+        }
+    }
+
+    if pipeline.require_match && report.replacements == 0 {
+        report.policy_violation = Some("No matches found (--require-match)".into());
+    } else if let Some(expected) = pipeline.expect {
+        if report.replacements != expected {
+            report.policy_violation = Some(format!(
+                "Expected {} replacements, found {} (--expect)",
+                expected, report.replacements
+            ));
+        }
+    } else if pipeline.fail_on_change && report.modified > 0 {
+        report.policy_violation = Some(format!(
+            "Changes detected in {} files (--fail-on-change)",
+            report.modified
+        ));
+    } else if pipeline.fail_on_blocked {
+        if let Some(blocked) = count_blocked(&report) {
+            report.policy_violation = Some(format!(
+                "{} file(s) rejected as outside the allowed write roots (--fail-on-blocked)",
+                blocked
+            ));
+        }
+    }
+
+    if report.exit_code() == 0 {
+        if let Some(manager) = tm {
+            manager.commit().map_err(|e| Error::TransactionFailure(e.to_string()))?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Apply one file's surviving rustfix suggestions: read it, splice them in
+/// via [`apply_rustfix`], then diff/write/stage exactly as
+/// `process_patch_file` would for a `--patch` hunk.
+fn process_rustfix_file(patch: &RustfixPatch, pipeline: &Pipeline, should_stage: bool, cwd: &Path) -> (FileResult, Option<StagedEntry>) {
+    let path_buf = patch.path.clone();
+
+    if !check_write_allowed(&path_buf, cwd, &pipeline.allow_write, &pipeline.deny_write) {
+        return (FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: None,
+            skipped: Some("outside-allowed-root".into()),
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None);
+    }
+
+    if let Ok(metadata) = fs::symlink_metadata(&path_buf) {
+        if metadata.is_symlink() {
+            match pipeline.symlinks {
+                Symlinks::Follow => {}
+                Symlinks::Skip => {
+                    return (FileResult {
+                        path: path_buf,
+                        modified: false,
+                        replacements: 0,
+                        error: None,
+                        skipped: Some("symlink".into()),
+                        diff: None,
+                        diff_hunks: None,
+                        io: None,
+                        generated_content: None,
+                        edits: None,
+                        backup_path: None,
+                    }, None);
+                }
+                Symlinks::Error => {
+                    return (FileResult {
+                        path: path_buf,
+                        modified: false,
+                        replacements: 0,
+                        error: Some("Encountered symlink with --symlinks error".into()),
+                        skipped: None,
+                        diff: None,
+                        diff_hunks: None,
+                        io: None,
+                        generated_content: None,
+                        edits: None,
+                        backup_path: None,
+                    }, None);
+                }
+            }
+        }
+    }
+
+    let options = WriteOptions {
+        no_follow_symlinks: pipeline.symlinks != Symlinks::Follow,
+        permissions: pipeline.permissions.clone(),
+        force_buffered_read: pipeline.no_mmap,
+        mmap_min_size: pipeline.mmap_min_size.unwrap_or(crate::write::MMAP_MIN_SIZE),
+        preserve_ownership: pipeline.preserve_ownership,
+        preserve_timestamps: pipeline.preserve_timestamps,
+        preserve_xattrs: pipeline.preserve_xattrs,
+        durable: pipeline.durable,
+        backup: pipeline.backup_suffix.clone().map(|suffix| crate::write::BackupSpec { suffix }),
+        write_strategy: pipeline.write_strategy,
+    };
+
+    let content_bytes = match crate::write::read_file(&path_buf, &options) {
+        Ok(b) => b,
+        Err(e) => return (FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: Some(e.to_string()),
+            skipped: None,
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None),
+    };
+
+    let io = match &content_bytes {
+        crate::write::FileBytes::Buffered(_) => "buffered",
+        crate::write::FileBytes::Mapped(_) => "mmap",
+    };
+
+    let decoded = DecodedText::decode(&content_bytes, pipeline.encoding);
+    let original = decoded.text.clone();
+    drop(content_bytes);
+
+    let (new_content, applied) = apply_rustfix(&original, patch);
 
-        if let Some(s) = staged {
-            if let Some(manager) = &mut tm {
-                manager.stage(s);
-            }
-        }
+    let modified = new_content != original;
+    let (diff, diff_hunks) = if pipeline.dry_run {
+        generate_diff(&original, &new_content, pipeline.diff_context, &pipeline.diff_redactions)
+    } else {
+        (None, None)
+    };
 
-        if has_error {
-            break;
-        }
+    if !modified || pipeline.dry_run || pipeline.no_write {
+        return (FileResult {
+            path: path_buf,
+            modified,
+            replacements: applied,
+            error: None,
+            skipped: None,
+            diff,
+            diff_hunks,
+            io: Some(io.to_string()),
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None);
     }
 
-    // Policy checks
-    if pipeline.require_match && report.replacements == 0 {
-        report.policy_violation = Some("No matches found (--require-match)".into());
-    } else if let Some(expected) = pipeline.expect {
-        if report.replacements != expected {
-            report.policy_violation = Some(format!(
-                "Expected {} replacements, found {} (--expect)",
-                expected, report.replacements
-            ));
+    if should_stage {
+        match stage_file(&path_buf, &decoded.encode_back(&new_content), &options) {
+            Ok(staged) => {
+                warn_on_preservation_failures(&path_buf, staged.preservation());
+                let backup_path = staged.backup_path();
+                (FileResult {
+                    path: path_buf,
+                    modified,
+                    replacements: applied,
+                    error: None,
+                    skipped: None,
+                    diff,
+                    diff_hunks,
+                    io: Some(io.to_string()),
+                    generated_content: None,
+                    edits: None,
+                    backup_path,
+                }, Some(staged))
+            }
+            Err(e) => (FileResult {
+                path: path_buf,
+                modified: false,
+                replacements: 0,
+                error: Some(e.to_string()),
+                skipped: None,
+                diff: None,
+                diff_hunks: None,
+                io: None,
+                generated_content: None,
+                edits: None,
+                backup_path: None,
+            }, None),
         }
-    } else if pipeline.fail_on_change && report.modified > 0 {
-        report.policy_violation = Some(format!(
-            "Changes detected in {} files (--fail-on-change)",
-            report.modified
-        ));
-    }
+    } else {
+        let backup_path = match write_file(&path_buf, &decoded.encode_back(&new_content), &options) {
+            Ok((preservation, backup_path)) => {
+                warn_on_preservation_failures(&path_buf, preservation);
+                backup_path
+            }
+            Err(e) => return (FileResult {
+                path: path_buf,
+                modified: false,
+                replacements: 0,
+                error: Some(e.to_string()),
+                skipped: None,
+                diff: None,
+                diff_hunks: None,
+                io: None,
+                generated_content: None,
+                edits: None,
+                backup_path: None,
+            }, None),
+        };
 
-    // Commit if no errors and no policy violations
-    if report.exit_code() == 0 {
-        if let Some(manager) = tm {
-            manager.commit().map_err(|e| Error::TransactionFailure(e.to_string()))?;
-        }
+        (FileResult {
+            path: path_buf,
+            modified,
+            replacements: applied,
+            error: None,
+            skipped: None,
+            diff,
+            diff_hunks,
+            io: Some(io.to_string()),
+            generated_content: None,
+            edits: None,
+            backup_path,
+        }, None)
     }
-
-    Ok(report)
 }
 
 fn build_glob_sets(
@@ -190,8 +1083,8 @@ fn process_text(
     // For stdin text, we use a dummy path or "<stdin>"
     let path_buf = PathBuf::from("<stdin>");
     
-    match process_content_inner(original.clone(), operations, pipeline, None) {
-        Ok((modified, replacements, diff, new_content)) => {
+    match process_content_inner(original.clone(), operations, pipeline, None, None) {
+        Ok((modified, replacements, diff, diff_hunks, new_content, edits)) => {
             let generated_content = if !pipeline.dry_run {
                 if modified {
                     Some(new_content)
@@ -209,7 +1102,11 @@ fn process_text(
                 error: None,
                 skipped: None,
                 diff,
+                diff_hunks,
+                io: None,
                 generated_content,
+                edits: if pipeline.emit_edits { Some(edits) } else { None },
+                backup_path: None,
             }
         },
         Err(e) => FileResult {
@@ -219,21 +1116,63 @@ fn process_text(
             error: Some(e.to_string()),
             skipped: None,
             diff: None,
+            diff_hunks: None,
+            io: None,
             generated_content: None,
+            edits: None,
+            backup_path: None,
         },
     }
 }
 
+/// Check whether the file's current bytes still match every recorded
+/// `rg --json` line anchor, returning the offset of the first mismatch.
+/// `rg --json` reports offsets from its own scan of the file, which can go
+/// stale if the file is edited between that scan and this run; applying
+/// (or silently skipping) edits against stale offsets would be a correctness
+/// bug, so a mismatch is surfaced as a conflict instead.
+fn find_ripgrep_conflict(content: &[u8], anchors: &[RipgrepAnchor]) -> Option<usize> {
+    for anchor in anchors {
+        let end = anchor.offset.checked_add(anchor.expected.len())?;
+        if end > content.len() || content[anchor.offset..end] != anchor.expected[..] {
+            return Some(anchor.offset);
+        }
+    }
+    None
+}
+
 /// Process a single file.
+#[allow(clippy::too_many_arguments)]
 fn process_file(
     path: &str,
     operations: &[Operation],
     pipeline: &Pipeline,
     matches: Option<&[ReplacementRange]>,
+    replacement_override: Option<&str>,
     should_stage: bool,
+    when_expr: Option<&WhenExpr>,
+    ripgrep_anchors: &[RipgrepAnchor],
+    cwd: &Path,
+    cache_ctx: Option<&CacheContext>,
 ) -> (FileResult, Option<StagedEntry>) {
     let path_buf = PathBuf::from(path);
 
+    if !check_write_allowed(&path_buf, cwd, &pipeline.allow_write, &pipeline.deny_write) {
+        return (FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: None,
+            skipped: Some("outside-allowed-root".into()),
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None);
+    }
+
     // Check for symlinks
     if let Ok(metadata) = fs::symlink_metadata(path) {
         if metadata.is_symlink() {
@@ -249,7 +1188,11 @@ fn process_file(
                         error: None,
                         skipped: Some("symlink".into()),
                         diff: None,
+                        diff_hunks: None,
+                        io: None,
                         generated_content: None,
+                        edits: None,
+                        backup_path: None,
                     }, None);
                 }
                 Symlinks::Error => {
@@ -260,15 +1203,36 @@ fn process_file(
                         error: Some("Encountered symlink with --symlinks error".into()),
                         skipped: None,
                         diff: None,
+                        diff_hunks: None,
+                        io: None,
                         generated_content: None,
+                        edits: None,
+                        backup_path: None,
                     }, None);
                 }
             }
         }
     }
-    
-    // Read file content
-    let content_bytes = match fs::read(path) {
+
+    // Built up-front: used both for the read (mmap vs buffered) decision
+    // below and, later, for staging/writing the result.
+    let options = WriteOptions {
+        no_follow_symlinks: pipeline.symlinks != crate::model::Symlinks::Follow,
+        permissions: pipeline.permissions.clone(),
+        force_buffered_read: pipeline.no_mmap,
+        mmap_min_size: pipeline.mmap_min_size.unwrap_or(crate::write::MMAP_MIN_SIZE),
+        preserve_ownership: pipeline.preserve_ownership,
+        preserve_timestamps: pipeline.preserve_timestamps,
+        preserve_xattrs: pipeline.preserve_xattrs,
+        durable: pipeline.durable,
+        backup: pipeline.backup_suffix.clone().map(|suffix| crate::write::BackupSpec { suffix }),
+        write_strategy: pipeline.write_strategy,
+    };
+
+    // Read file content. Large regular files are memory-mapped (see
+    // `write::read_file`); the mapping is consumed below when `original`
+    // is built and is gone well before any write is staged for this path.
+    let content_bytes = match crate::write::read_file(&path_buf, &options) {
         Ok(b) => b,
         Err(e) => return (FileResult {
             path: path_buf,
@@ -277,10 +1241,45 @@ fn process_file(
             error: Some(e.to_string()),
             skipped: None,
             diff: None,
+            diff_hunks: None,
+            io: None,
             generated_content: None,
+            edits: None,
+            backup_path: None,
         }, None)
     };
 
+    // Which strategy `read_file` picked for this file, surfaced on the
+    // `file` event's `io` field so JSON consumers can assert on it.
+    let io = match &content_bytes {
+        crate::write::FileBytes::Buffered(_) => "buffered",
+        crate::write::FileBytes::Mapped(_) => "mmap",
+    };
+
+    // `--cache-file`: skip re-processing a file whose content hash hasn't
+    // changed since it was last recorded under the same operation-set hash.
+    // Entries are keyed by absolute path, so a relative input is joined
+    // against `cwd` first.
+    let cache_key = if path_buf.is_absolute() { path_buf.clone() } else { cwd.join(&path_buf) };
+    if let Some(ctx) = cache_ctx {
+        let content_hash = crate::cache::hash_content(&content_bytes);
+        if ctx.cache.lock().unwrap().is_unchanged(&cache_key, ctx.ops_hash, content_hash) {
+            return (FileResult {
+                path: path_buf,
+                modified: false,
+                replacements: 0,
+                error: None,
+                skipped: Some("unchanged (cache)".into()),
+                diff: None,
+                diff_hunks: None,
+                io: None,
+                generated_content: None,
+                edits: None,
+                backup_path: None,
+            }, None);
+        }
+    }
+
     // Check for binary content
     if content_bytes.contains(&0) {
         match pipeline.binary {
@@ -292,7 +1291,11 @@ fn process_file(
                     error: None,
                     skipped: Some("binary file".into()),
                     diff: None,
+                    diff_hunks: None,
+                    io: None,
                     generated_content: None,
+                    edits: None,
+                    backup_path: None,
                 }, None);
             }
             BinaryFileMode::Error => {
@@ -303,35 +1306,99 @@ fn process_file(
                     error: Some("Binary file detected".into()),
                     skipped: None,
                     diff: None,
+                    diff_hunks: None,
+                    io: None,
                     generated_content: None,
+                    edits: None,
+                    backup_path: None,
                 }, None);
             }
         }
     }
-    
-    let original = String::from_utf8_lossy(&content_bytes).to_string();
 
-    match process_content_inner(original, operations, pipeline, matches) {
-        Ok((modified, replacements, diff, new_content)) => {
+    if let Some(offset) = find_ripgrep_conflict(&content_bytes, ripgrep_anchors) {
+        return (FileResult {
+            path: path_buf,
+            modified: false,
+            replacements: 0,
+            error: Some(format!(
+                "ripgrep match conflict at byte offset {}: file content no longer matches the line `rg --json` reported (file changed since the scan ran)",
+                offset
+            )),
+            skipped: None,
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }, None);
+    }
+
+    let len = content_bytes.len() as u64;
+    let decoded = DecodedText::decode(&content_bytes, pipeline.encoding);
+    let original = decoded.text.clone();
+    // The mapping (if any) has no remaining references past this point,
+    // so it's safe to stage/commit a write to this same path below.
+    drop(content_bytes);
+
+    if let Some(expr) = when_expr {
+        let ctx = FileContext { path: &path_buf, content: &original, len };
+        if !expr.eval(&ctx) {
+            return (FileResult {
+                path: path_buf,
+                modified: false,
+                replacements: 0,
+                error: None,
+                skipped: Some("when predicate".into()),
+                diff: None,
+                diff_hunks: None,
+                io: None,
+                generated_content: None,
+                edits: None,
+                backup_path: None,
+            }, None);
+        }
+    }
+
+    match process_content_inner(original, operations, pipeline, matches, replacement_override) {
+        Ok((modified, replacements, diff, diff_hunks, new_content, edits)) => {
+            let edits = if pipeline.emit_edits { Some(edits) } else { None };
+            // Record the resulting content's hash regardless of which write
+            // path below runs (or whether one runs at all): if `--no-write`
+            // suppressed the write, the next run will still see the
+            // original, unmodified bytes on disk and reprocess them. Hash
+            // the bytes actually written (`decoded.encode_back`), not the
+            // in-memory UTF-8 `String` — for a non-UTF-8 `--encoding` those
+            // differ, and the read side (above) always hashes the raw bytes
+            // read from disk, so a mismatched representation here would
+            // mean the cache never hits on a second run.
+            if let Some(ctx) = cache_ctx {
+                let content_hash = crate::cache::hash_content(&decoded.encode_back(&new_content));
+                ctx.cache.lock().unwrap().update(&cache_key, ctx.ops_hash, content_hash);
+            }
             // Write changes if modified and not dry_run and not no_write
             if modified && !pipeline.dry_run && !pipeline.no_write {
-                let options = WriteOptions {
-                    no_follow_symlinks: pipeline.symlinks != crate::model::Symlinks::Follow,
-                    permissions: pipeline.permissions.clone(),
-                };
-                
                 if should_stage {
                     // Stage
-                    match stage_file(&path_buf, new_content.as_bytes(), &options) {
-                        Ok(staged) => (FileResult {
-                            path: path_buf,
-                            modified,
-                            replacements,
-                            error: None,
-                            skipped: None,
-                            diff,
-                            generated_content: None,
-                        }, Some(staged)),
+                    match stage_file(&path_buf, &decoded.encode_back(&new_content), &options) {
+                        Ok(staged) => {
+                            warn_on_preservation_failures(&path_buf, staged.preservation());
+                            let backup_path = staged.backup_path();
+                            (FileResult {
+                                path: path_buf,
+                                modified,
+                                replacements,
+                                error: None,
+                                skipped: None,
+                                diff,
+                                diff_hunks,
+                                io: Some(io.to_string()),
+                                generated_content: None,
+                                edits,
+                                backup_path,
+                            }, Some(staged))
+                        }
                         Err(e) => (FileResult {
                             path: path_buf,
                             modified: false,
@@ -339,23 +1406,35 @@ fn process_file(
                             error: Some(e.to_string()),
                             skipped: None,
                             diff: None,
+                            diff_hunks: None,
+                            io: None,
                             generated_content: None,
+                            edits: None,
+                            backup_path: None,
                         }, None),
                     }
                 } else {
                     // Write immediately
-                    if let Err(e) = write_file(&path_buf, new_content.as_bytes(), &options) {
-                         return (FileResult {
+                    let backup_path = match write_file(&path_buf, &decoded.encode_back(&new_content), &options) {
+                        Ok((preservation, backup_path)) => {
+                            warn_on_preservation_failures(&path_buf, preservation);
+                            backup_path
+                        }
+                        Err(e) => return (FileResult {
                             path: path_buf,
                             modified: false,
                             replacements: 0,
                             error: Some(e.to_string()),
                             skipped: None,
                             diff: None,
+                            diff_hunks: None,
+                            io: None,
                             generated_content: None,
-                        }, None);
-                    }
-                    
+                            edits: None,
+                            backup_path: None,
+                        }, None),
+                    };
+
                     (FileResult {
                         path: path_buf,
                         modified,
@@ -363,7 +1442,11 @@ fn process_file(
                         error: None,
                         skipped: None,
                         diff,
+                        diff_hunks,
+                        io: Some(io.to_string()),
                         generated_content: None,
+                        edits,
+                        backup_path,
                     }, None)
                 }
             } else {
@@ -374,7 +1457,11 @@ fn process_file(
                     error: None,
                     skipped: None,
                     diff,
+                    diff_hunks,
+                    io: Some(io.to_string()),
                     generated_content: None,
+                    edits,
+                    backup_path: None,
                 }, None)
             }
         },
@@ -385,84 +1472,347 @@ fn process_file(
             error: Some(e.to_string()),
             skipped: None,
             diff: None,
+            diff_hunks: None,
+            io: None,
             generated_content: None,
+            edits: None,
+            backup_path: None,
         }, None),
     }
 }
 
+/// Build the [`Replacer`] for a single `Operation::Replace`/`Operation::Delete`,
+/// using `replacement` in place of the operation's own REPLACE text (an
+/// edit-plan line's per-file override, or just the operation's text; always
+/// `""` for `Delete`). Shared between whole-buffer processing and
+/// `--stream`, which needs a `Replacer` up front without going through
+/// `process_content_inner`.
+pub fn build_replacer(
+    op: &Operation,
+    replacement: &str,
+    allowed_ranges: Option<Vec<ReplacementRange>>,
+) -> Result<Replacer> {
+    match op {
+        Operation::Replace { find, literal, ignore_case, smart_case,
+            word, multiline, dot_matches_newline, no_unicode, limit, ranges, expand, validation_mode, .. } => {
+            Replacer::new(
+                find,
+                replacement,
+                *literal,
+                *ignore_case,
+                *smart_case,
+                !(*ignore_case || *smart_case), // case_sensitive
+                *word,
+                *multiline,
+                false, // single_line (not yet supported)
+                *dot_matches_newline,
+                *no_unicode,
+                false, // crlf
+                *limit,
+                ranges.clone(),
+                allowed_ranges,
+                *expand,
+                *validation_mode,
+            ).map_err(|e| Error::Validation(e.to_string()))
+        }
+        Operation::Delete { find, literal, ignore_case, smart_case,
+            word, multiline, dot_matches_newline, no_unicode, limit, ranges } => {
+            Replacer::new(
+                find,
+                replacement,
+                *literal,
+                *ignore_case,
+                *smart_case,
+                !(*ignore_case || *smart_case), // case_sensitive
+                *word,
+                *multiline,
+                false, // single_line (not yet supported)
+                *dot_matches_newline,
+                *no_unicode,
+                false, // crlf
+                *limit,
+                ranges.clone(),
+                allowed_ranges,
+                false, // expand
+                ValidationMode::default(),
+            ).map_err(|e| Error::Validation(e.to_string()))
+        }
+        _ => unreachable!("build_replacer is only called for Operation::Replace/Delete"),
+    }
+}
+
 /// Inner processing logic shared between file and text input
+#[allow(clippy::type_complexity)]
 fn process_content_inner(
     original: String,
     operations: &[Operation],
     pipeline: &Pipeline,
     matches: Option<&[ReplacementRange]>,
-) -> Result<(bool, usize, Option<String>, String)> {
-    
+    replacement_override: Option<&str>,
+) -> Result<(bool, usize, Option<String>, Option<Vec<crate::diff::DiffHunk>>, String, Vec<crate::events::EditRecord>)> {
+
     // Apply each operation sequentially
     let mut current = original.clone();
     let mut total_replacements = 0;
+    // Byte spans are reported relative to the text each operation actually
+    // saw, so when multiple operations run in sequence these only line up
+    // with the original file for the first one.
+    let mut edits = Vec::new();
 
     for op in operations {
         match op {
-            Operation::Replace { find, with: replacement, literal, ignore_case, smart_case,
-                word, multiline, dot_matches_newline, no_unicode, limit, range } => {
-                // Build replacer
-                let replacer = Replacer::new(
-                    find,
-                    replacement,
-                    *literal,
-                    *ignore_case,
-                    *smart_case,
-                    !(*ignore_case || *smart_case), // case_sensitive
-                    *word,
-                    *multiline,
-                    false, // single_line (not yet supported)
-                    *dot_matches_newline,
-                    *no_unicode,
-                    false, // crlf
-                    *limit,
-                    range.clone(),
-                    matches.map(|m| m.to_vec()),
-                ).map_err(|e| Error::Validation(e.to_string()))?;
+            Operation::Replace { find: _, with: replacement, .. } => {
+                // An edit-plan line's `replacement` (when given) takes
+                // precedence over this operation's configured REPLACE text,
+                // letting one run apply heterogeneous edits per file.
+                let replacement = replacement_override.unwrap_or(replacement);
+                let replacer = build_replacer(op, replacement, matches.map(|m| m.to_vec()))?;
 
                 // Apply replacement to current string (as bytes) and count replacements
-                let (bytes, replacements) = replacer.replace_with_count(current.as_bytes());
+                let (bytes, replacements) = if pipeline.emit_edits {
+                    let (bytes, op_edits) = replacer.replace_with_edits(current.as_bytes());
+                    let count = op_edits.len();
+                    edits.extend(op_edits);
+                    (bytes, count)
+                } else {
+                    replacer.replace_with_count(current.as_bytes())
+                };
                 let new_string = String::from_utf8(bytes.to_vec())
                     .map_err(|e| Error::Validation(format!("Invalid UTF-8 after replacement: {}", e)))?;
 
                 current = new_string;
                 total_replacements += replacements;
             }
+            Operation::Delete { .. } => {
+                let replacer = build_replacer(op, "", matches.map(|m| m.to_vec()))?;
+
+                let (bytes, replacements) = if pipeline.emit_edits {
+                    let (bytes, op_edits) = replacer.replace_with_edits(current.as_bytes());
+                    let count = op_edits.len();
+                    edits.extend(op_edits);
+                    (bytes, count)
+                } else {
+                    replacer.replace_with_count(current.as_bytes())
+                };
+                let new_string = String::from_utf8(bytes.to_vec())
+                    .map_err(|e| Error::Validation(format!("Invalid UTF-8 after deletion: {}", e)))?;
+
+                current = new_string;
+                total_replacements += replacements;
+            }
+            Operation::Transform { kind, range } => {
+                let total_lines = current.split('\n').count();
+                let mut changed = 0usize;
+                let lines: Vec<String> = current
+                    .split('\n')
+                    .enumerate()
+                    .map(|(i, line)| {
+                        let line_number = i + 1;
+                        let in_range = line_in_ranges(line_number, range, total_lines);
+                        if !in_range {
+                            return line.to_string();
+                        }
+                        let transformed = match kind {
+                            CaseKind::Upper => line.to_uppercase(),
+                            CaseKind::Lower => line.to_lowercase(),
+                            CaseKind::TitleCase => title_case(line),
+                        };
+                        if transformed != line {
+                            changed += 1;
+                        }
+                        transformed
+                    })
+                    .collect();
+                current = lines.join("\n");
+                total_replacements += changed;
+            }
+            Operation::InsertLine { anchor, text, position } => {
+                let mut inserted = 0usize;
+                let mut new_lines = Vec::new();
+                for line in current.split('\n') {
+                    let is_anchor = line.contains(anchor.as_str());
+                    if is_anchor && *position == InsertPosition::Before {
+                        new_lines.push(text.clone());
+                        inserted += 1;
+                    }
+                    new_lines.push(line.to_string());
+                    if is_anchor && *position == InsertPosition::After {
+                        new_lines.push(text.clone());
+                        inserted += 1;
+                    }
+                }
+                current = new_lines.join("\n");
+                total_replacements += inserted;
+            }
+            Operation::DeleteMatching { pattern } => {
+                let re = Regex::new(pattern)
+                    .map_err(|e| Error::Validation(format!("Invalid DeleteMatching pattern: {}", e)))?;
+                let mut removed = 0usize;
+                let kept: Vec<&str> = current
+                    .split('\n')
+                    .filter(|line| {
+                        if re.is_match(line) {
+                            removed += 1;
+                            false
+                        } else {
+                            true
+                        }
+                    })
+                    .collect();
+                current = kept.join("\n");
+                total_replacements += removed;
+            }
         }
     }
 
+    let current = normalize_newlines(current, pipeline.newline_style);
+
     let modified = current != original;
-    let diff = if pipeline.dry_run {
-        generate_diff(&original, &current)
+    let (diff, diff_hunks) = if pipeline.dry_run {
+        generate_diff(&original, &current, pipeline.diff_context, &pipeline.diff_redactions)
     } else {
-        None
+        (None, None)
+    };
+
+    Ok((modified, total_replacements, diff, diff_hunks, current, edits))
+}
+
+/// Normalize `text`'s line endings per `style`, applied after every
+/// replacement operation so matches and replacement text both see the
+/// file's original line endings — only the final written (and diffed)
+/// content reflects the chosen style. `generate_diff`'s "no trailing
+/// newline" marker still fires correctly since it's computed from this
+/// already-normalized content, not the pre-normalization one.
+fn normalize_newlines(text: String, style: NewlineStyle) -> String {
+    let target = match style {
+        NewlineStyle::Auto => {
+            // Majority of the file's own line endings: more CRLF than bare
+            // LF means this file's dominant style is CRLF. A tie (including
+            // a file with no newlines at all) is left as Unix.
+            let crlf = text.matches("\r\n").count();
+            let total_lf = text.matches('\n').count();
+            if crlf * 2 > total_lf { NewlineStyle::Windows } else { NewlineStyle::Unix }
+        }
+        NewlineStyle::Native => {
+            if cfg!(windows) { NewlineStyle::Windows } else { NewlineStyle::Unix }
+        }
+        other => other,
+    };
+
+    // Normalize to bare `\n` first, then re-apply CRLF if that's the target,
+    // so mixed-ending input ends up uniformly in the chosen style.
+    let unified = text.replace("\r\n", "\n");
+    match target {
+        NewlineStyle::Windows => unified.replace('\n', "\r\n"),
+        _ => unified,
+    }
+}
+
+/// Warn (without failing the write) about any attribute-preservation step
+/// that was requested but didn't succeed, e.g. `chown` hitting `EPERM`.
+fn warn_on_preservation_failures(path: &Path, preservation: crate::write::PreservationOutcome) {
+    if preservation.ownership == Some(false) {
+        eprintln!("WARN: failed to preserve ownership for {}", path.display());
+    }
+    if preservation.timestamps == Some(false) {
+        eprintln!("WARN: failed to preserve timestamps for {}", path.display());
+    }
+    if preservation.xattrs == Some(false) {
+        eprintln!("WARN: failed to preserve extended attributes for {}", path.display());
+    }
+}
+
+/// Check if a 1-based `line_number` falls within any of the given line
+/// ranges, once each range's (possibly negative) bounds are resolved against
+/// the file's actual line count. A range whose resolved start is after its
+/// resolved end (e.g. `-1:-5` in a short file) matches nothing rather than
+/// erroring. A missing `ranges` list (`None`) matches every line.
+///
+/// Bound resolution is shared with `replacer::resolve_bound`, which applies
+/// the same negative-index semantics against a byte offset's line number
+/// instead of a line number the caller already has in hand.
+fn line_in_ranges(line_number: usize, ranges: &Option<Vec<LineRange>>, total_lines: usize) -> bool {
+    use crate::replacer::resolve_bound;
+
+    let Some(ranges) = ranges else {
+        return true;
     };
 
-    Ok((modified, total_replacements, diff, current))
+    ranges.iter().any(|range| {
+        let start = resolve_bound(range.start, total_lines);
+        let end = range.end.map(|e| resolve_bound(e, total_lines));
+
+        if let Some(end) = end {
+            if start > end {
+                return false;
+            }
+        }
+
+        line_number >= start && end.map(|end| line_number <= end).unwrap_or(true)
+    })
 }
 
+/// Capitalize the first letter of each whitespace-separated word, lowercasing
+/// the rest; used by `Operation::Transform`'s `CaseKind::TitleCase`.
+fn title_case(line: &str) -> String {
+    line.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-/// Generate a unified diff between old and new content.
-fn generate_diff(old: &str, new: &str) -> Option<String> {
-    if old == new {
-        return None;
+/// Generate a grouped unified diff between old and new content, keeping
+/// `context` unchanged lines around each hunk and applying any configured
+/// redaction rules first so the preview stays reproducible.
+fn generate_diff(
+    old: &str,
+    new: &str,
+    context: usize,
+    redactions: &[(String, String)],
+) -> (Option<String>, Option<Vec<crate::diff::DiffHunk>>) {
+    let rules = crate::diff::redaction_rules(redactions);
+    match crate::diff::preview_with_hunks(old, new, context, &rules) {
+        Some((text, hunks)) => (Some(text), Some(hunks)),
+        None => (None, None),
     }
-    let diff = TextDiff::from_lines(old, new);
-    let mut output = String::new();
-    for change in diff.iter_all_changes() {
-        let sign = match change.tag() {
-            ChangeTag::Delete => "-",
-            ChangeTag::Insert => "+",
-            ChangeTag::Equal => " ",
-        };
-        output.push_str(&format!("{}{}", sign, change));
+}
+
+/// Resolve `path` to an absolute, symlink- and `..`-free form — joining it
+/// against `cwd` first if it's relative — and check that it's contained in
+/// at least one `allow_write` root and not in any `deny_write` root. Both
+/// root lists are resolved the same way before the containment check, so a
+/// root given as a relative path or reached only through a symlink still
+/// matches correctly. Empty `allow_write`/`deny_write` (the default) means
+/// no restriction.
+fn check_write_allowed(path: &Path, cwd: &Path, allow_write: &[String], deny_write: &[String]) -> bool {
+    if allow_write.is_empty() && deny_write.is_empty() {
+        return true;
+    }
+
+    let absolute = if path.is_absolute() { path.to_path_buf() } else { cwd.join(path) };
+    let resolved = fs::canonicalize(&absolute).unwrap_or(absolute);
+
+    let contained_in = |roots: &[String]| -> bool {
+        roots.iter().any(|root| {
+            let root = Path::new(root);
+            let root_absolute = if root.is_absolute() { root.to_path_buf() } else { cwd.join(root) };
+            match fs::canonicalize(&root_absolute) {
+                Ok(root_resolved) => resolved.starts_with(&root_resolved),
+                Err(_) => false,
+            }
+        })
+    };
+
+    if contained_in(deny_write) {
+        return false;
     }
-    Some(output)
+    allow_write.is_empty() || contained_in(allow_write)
 }
 
 fn normalize_path(path: &Path, cwd: &Path) -> PathBuf {
@@ -492,7 +1842,7 @@ fn normalize_path(path: &Path, cwd: &Path) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Pipeline, Operation};
+    use crate::model::{Pipeline, Operation, NewlineStyle};
 
     fn pipeline(dry_run: bool, validate_only: bool) -> Pipeline {
         Pipeline {
@@ -514,7 +1864,9 @@ mod tests {
             dot_matches_newline: false,
             no_unicode: false,
             limit: 0,
-            range: None,
+            ranges: None,
+            expand: false,
+            validation_mode: crate::model::ValidationMode::None,
         }
     }
 
@@ -524,13 +1876,14 @@ mod tests {
         let ops = vec![op_replace("world", "there")];
 
         let original = "hello world\n".to_string();
-        let (modified, replacements, diff, new_content) = 
-            process_content_inner(original.clone(), &ops, &p, None).unwrap();
+        let (modified, replacements, diff, diff_hunks, new_content, _edits) =
+            process_content_inner(original.clone(), &ops, &p, None, None).unwrap();
 
         assert!(modified);
         assert_eq!(replacements, 1);
         assert_eq!(new_content, "hello there\n");
         assert!(diff.is_some());
+        assert!(diff_hunks.is_some());
     }
 
     #[test]
@@ -539,13 +1892,14 @@ mod tests {
         let ops = vec![op_replace("zzz", "yyy")];
 
         let original = "abc\n".to_string();
-        let (modified, replacements, diff, new_content) = 
-            process_content_inner(original.clone(), &ops, &p, None).unwrap();
+        let (modified, replacements, diff, diff_hunks, new_content, _edits) =
+            process_content_inner(original.clone(), &ops, &p, None, None).unwrap();
 
         assert!(!modified);
         assert_eq!(replacements, 0);
         assert_eq!(new_content, original);
         assert!(diff.is_none());
+        assert!(diff_hunks.is_none());
     }
 
     #[test]
@@ -554,22 +1908,153 @@ mod tests {
         let ops = vec![op_replace("a", "b")];
 
         let original = "a\n".to_string();
-        let (_modified, _replacements, diff, _new_content) = 
-            process_content_inner(original, &ops, &p, None).unwrap();
+        let (_modified, _replacements, diff, diff_hunks, _new_content, _edits) =
+            process_content_inner(original, &ops, &p, None, None).unwrap();
 
         assert!(diff.is_none());
+        assert!(diff_hunks.is_none());
+    }
+
+    #[test]
+    fn process_content_inner_delete_removes_matches() {
+        let p = pipeline(true, false);
+        let ops = vec![Operation::Delete {
+            find: "foo".into(),
+            literal: true,
+            ignore_case: false,
+            smart_case: false,
+            word: false,
+            multiline: false,
+            dot_matches_newline: false,
+            no_unicode: false,
+            limit: 0,
+            ranges: None,
+        }];
+
+        let original = "foobar\n".to_string();
+        let (modified, replacements, _diff, _hunks, new_content, _edits) =
+            process_content_inner(original, &ops, &p, None, None).unwrap();
+
+        assert!(modified);
+        assert_eq!(replacements, 1);
+        assert_eq!(new_content, "bar\n");
+    }
+
+    #[test]
+    fn process_content_inner_transform_upper_and_lower() {
+        let p = pipeline(true, false);
+        let ops = vec![Operation::Transform {
+            kind: CaseKind::Upper,
+            range: None,
+        }];
+
+        let original = "hello\nworld".to_string();
+        let (modified, replacements, _diff, _hunks, new_content, _edits) =
+            process_content_inner(original, &ops, &p, None, None).unwrap();
+
+        assert!(modified);
+        assert_eq!(replacements, 2);
+        assert_eq!(new_content, "HELLO\nWORLD");
+    }
+
+    #[test]
+    fn process_content_inner_transform_respects_range() {
+        let p = pipeline(true, false);
+        let ops = vec![Operation::Transform {
+            kind: CaseKind::Upper,
+            range: Some(vec![LineRange { start: 2, end: Some(2) }]),
+        }];
+
+        let original = "one\ntwo\nthree".to_string();
+        let (_modified, replacements, _diff, _hunks, new_content, _edits) =
+            process_content_inner(original, &ops, &p, None, None).unwrap();
+
+        assert_eq!(replacements, 1);
+        assert_eq!(new_content, "one\nTWO\nthree");
+    }
+
+    #[test]
+    fn process_content_inner_insert_line_after_anchor() {
+        let p = pipeline(true, false);
+        let ops = vec![Operation::InsertLine {
+            anchor: "foo".into(),
+            text: "inserted".into(),
+            position: InsertPosition::After,
+        }];
+
+        let original = "foo\nbar".to_string();
+        let (modified, replacements, _diff, _hunks, new_content, _edits) =
+            process_content_inner(original, &ops, &p, None, None).unwrap();
+
+        assert!(modified);
+        assert_eq!(replacements, 1);
+        assert_eq!(new_content, "foo\ninserted\nbar");
+    }
+
+    #[test]
+    fn process_content_inner_delete_matching_removes_whole_lines() {
+        let p = pipeline(true, false);
+        let ops = vec![Operation::DeleteMatching {
+            pattern: "^#".into(),
+        }];
+
+        let original = "# comment\nkeep me\n# another".to_string();
+        let (modified, replacements, _diff, _hunks, new_content, _edits) =
+            process_content_inner(original, &ops, &p, None, None).unwrap();
+
+        assert!(modified);
+        assert_eq!(replacements, 2);
+        assert_eq!(new_content, "keep me");
+    }
+
+    #[test]
+    fn title_case_capitalizes_each_word() {
+        assert_eq!(title_case("hello WORLD foo"), "Hello World Foo");
+    }
+
+    #[test]
+    fn line_in_ranges_handles_negative_indices() {
+        assert!(line_in_ranges(5, &Some(vec![LineRange { start: -1, end: None }]), 5));
+        assert!(!line_in_ranges(4, &Some(vec![LineRange { start: -1, end: None }]), 5));
+        assert!(line_in_ranges(1, &None, 5));
+    }
+
+    #[test]
+    fn normalize_newlines_auto_keeps_majority_crlf() {
+        let text = "a\r\nb\r\nc\n".to_string();
+        assert_eq!(normalize_newlines(text, NewlineStyle::Auto), "a\r\nb\r\nc\r\n");
+    }
+
+    #[test]
+    fn normalize_newlines_auto_keeps_majority_lf() {
+        let text = "a\nb\nc\r\n".to_string();
+        assert_eq!(normalize_newlines(text, NewlineStyle::Auto), "a\nb\nc\n");
+    }
+
+    #[test]
+    fn normalize_newlines_unix_rewrites_crlf() {
+        let text = "a\r\nb\r\n".to_string();
+        assert_eq!(normalize_newlines(text, NewlineStyle::Unix), "a\nb\n");
+    }
+
+    #[test]
+    fn normalize_newlines_windows_rewrites_lf() {
+        let text = "a\nb\r\n".to_string();
+        assert_eq!(normalize_newlines(text, NewlineStyle::Windows), "a\r\nb\r\n");
     }
 
     #[test]
     fn generate_diff_returns_none_when_equal() {
-        assert_eq!(generate_diff("x\n", "x\n"), None);
+        assert_eq!(generate_diff("x\n", "x\n", 3, &[]), (None, None));
     }
 
     #[test]
     fn generate_diff_shows_insert_and_delete_markers() {
-        let d = generate_diff("a\n", "b\n").unwrap();
+        let (d, hunks) = generate_diff("a\n", "b\n", 3, &[]);
+        let d = d.unwrap();
         assert!(d.contains("-a"));
         assert!(d.contains("+b"));
+        assert_eq!(hunks.unwrap().len(), 1);
     }
 
     #[test]
@@ -591,23 +2076,57 @@ mod tests {
     #[test]
     fn execute_errors_when_no_inputs() {
         let p = pipeline(true, false);
-        let err = execute(p, vec![]).unwrap_err();
+        let err = execute(p, vec![], vec![]).unwrap_err();
         assert!(err.to_string().contains("No input sources specified"));
     }
 
     #[test]
     fn execute_errors_when_no_operations() {
         let p = pipeline(true, false);
-        let err = execute(p, vec![InputItem::StdinText("x".into())]).unwrap_err();
+        let err = execute(p, vec![InputItem::StdinText("x".into())], vec![]).unwrap_err();
         assert!(err.to_string().contains("No operations specified"));
     }
 
+    #[test]
+    fn execute_pre_errors_block_transaction_all_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "foo").unwrap();
+
+        let mut p = pipeline(false, false);
+        p.transaction = Transaction::All;
+        p.operations = vec![op_replace("foo", "bar")];
+
+        let pre_errors = vec![FileResult {
+            path: PathBuf::from("unreadable-dir"),
+            modified: false,
+            replacements: 0,
+            error: Some("permission denied".into()),
+            skipped: None,
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        }];
+
+        let report = execute(p, vec![InputItem::Path(file.clone())], pre_errors).unwrap();
+
+        // The walk failure makes the whole report an error, so the
+        // transaction must not commit any of the otherwise-successful file
+        // writes staged alongside it.
+        assert!(report.has_errors);
+        assert_ne!(report.exit_code(), 0);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "foo");
+    }
+
     #[test]
     fn execute_validate_only_forces_dry_run_and_generates_diff() {
         let mut p = pipeline(false, true);
         p.operations = vec![op_replace("a", "b")];
 
-        let report = execute(p, vec![InputItem::StdinText("a\n".into())]).unwrap();
+        let report = execute(p, vec![InputItem::StdinText("a\n".into())], vec![]).unwrap();
 
         // Check report.results via inspection or public API
         // Here we just check one result exists
@@ -623,7 +2142,7 @@ mod tests {
         p.require_match = true;
         p.operations = vec![op_replace("foo", "bar")];
         
-        let report = execute(p, vec![InputItem::StdinText("baz".into())]).unwrap();
+        let report = execute(p, vec![InputItem::StdinText("baz".into())], vec![]).unwrap();
         
         assert!(report.policy_violation.is_some());
         assert!(report.policy_violation.as_ref().unwrap().contains("No matches found"));
@@ -637,7 +2156,7 @@ mod tests {
         p.operations = vec![op_replace("foo", "bar")];
         
         // Only 1 match
-        let report = execute(p, vec![InputItem::StdinText("foo".into())]).unwrap();
+        let report = execute(p, vec![InputItem::StdinText("foo".into())], vec![]).unwrap();
         
         assert!(report.policy_violation.is_some());
         assert!(report.policy_violation.as_ref().unwrap().contains("Expected 2 replacements, found 1"));
@@ -650,11 +2169,78 @@ mod tests {
             p.fail_on_change = true;
             p.operations = vec![op_replace("foo", "bar")];
             
-            let report = execute(p, vec![InputItem::StdinText("foo".into())]).unwrap();
+            let report = execute(p, vec![InputItem::StdinText("foo".into())], vec![]).unwrap();
             
             assert!(report.modified > 0);
             assert!(report.policy_violation.is_some());
             assert!(report.policy_violation.as_ref().unwrap().contains("Changes detected"));
             assert_eq!(report.exit_code(), 2);
         }
+
+    #[test]
+    fn check_write_allowed_no_restriction_by_default() {
+        let cwd = env::current_dir().unwrap();
+        assert!(check_write_allowed(Path::new("anything.rs"), &cwd, &[], &[]));
+    }
+
+    #[test]
+    fn check_write_allowed_inside_allow_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "x").unwrap();
+        let cwd = env::current_dir().unwrap();
+        let allow = vec![dir.path().to_string_lossy().into_owned()];
+        assert!(check_write_allowed(&file, &cwd, &allow, &[]));
+    }
+
+    #[test]
+    fn check_write_allowed_rejects_outside_allow_root() {
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let file = outside.path().join("a.txt");
+        fs::write(&file, "x").unwrap();
+        let cwd = env::current_dir().unwrap();
+        let allow = vec![allowed.path().to_string_lossy().into_owned()];
+        assert!(!check_write_allowed(&file, &cwd, &allow, &[]));
+    }
+
+    #[test]
+    fn check_write_allowed_deny_wins_over_allow() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "x").unwrap();
+        let cwd = env::current_dir().unwrap();
+        let root = dir.path().to_string_lossy().into_owned();
+        assert!(!check_write_allowed(&file, &cwd, &[root.clone()], &[root]));
+    }
+
+    #[test]
+    fn process_file_skips_paths_outside_allow_write() {
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let file = outside.path().join("a.txt");
+        fs::write(&file, "foo\n").unwrap();
+
+        let mut p = pipeline(false, false);
+        p.operations = vec![op_replace("foo", "bar")];
+        p.allow_write = vec![allowed.path().to_string_lossy().into_owned()];
+
+        let cwd = env::current_dir().unwrap();
+        let (result, staged) = process_file(
+            file.to_str().unwrap(),
+            &p.operations,
+            &p,
+            None,
+            None,
+            false,
+            None,
+            &[],
+            &cwd,
+            None,
+        );
+
+        assert_eq!(result.skipped.as_deref(), Some("outside-allowed-root"));
+        assert!(staged.is_none());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "foo\n");
+    }
     }
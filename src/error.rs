@@ -20,6 +20,9 @@ pub enum Error {
     #[error("Ambiguous replacement pattern: {0}")]
     AmbiguousReplacement(String),
 
+    #[error("Unknown capture reference: {0}")]
+    UnknownCaptureReference(String),
+
     #[error("Validation error: {0}")]
     Validation(String),
 
@@ -38,8 +38,26 @@ pub enum FileEvent {
         replacements: usize,
         #[serde(skip_serializing_if = "Option::is_none")]
         diff: Option<String>,
+        /// Structured form of `diff`: one entry per hunk, with the `@@`
+        /// header already broken into fields and each line tagged
+        /// `context`/`removed`/`added`, so machine consumers don't need to
+        /// parse unified-diff text back out of `diff`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        diff_hunks: Option<Vec<DiffHunkData>>,
+        /// Which read strategy was used for this file: `"mmap"` or
+        /// `"buffered"`. Absent when the file was never read.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        io: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         generated_content: Option<String>,
+        /// One record per applied replacement. Only populated when the
+        /// caller opts in (`--emit-edits`/`Pipeline::emit_edits`), since
+        /// computing and serializing per-match detail isn't free.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        edits: Option<Vec<EditRecord>>,
+        /// Path of the pre-edit safety copy, if `--backup-suffix` was set.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        backup_path: Option<PathBuf>,
     },
     Skipped {
         path: PathBuf,
@@ -61,6 +79,81 @@ pub enum SkipReason {
                  // Actually the TODO says "changed/skipped/error stats + reason enums".
                  // "NotModified" is usually a Success case with 0 replacements.
                  // "Skipped" usually implies we didn't even try to replace because of some property of the file.
+    /// Resolved path fell outside every `--allow-write` root, or inside a
+    /// `--deny-write` root.
+    OutsideAllowedRoot,
+}
+
+/// Matched or substituted text for a single replacement. Edited files
+/// aren't guaranteed to be valid UTF-8, so the payload inlines as a JSON
+/// string when it is, and as a raw byte array when it isn't, rather than
+/// forcing a lossy conversion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EditPayload {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl EditPayload {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(s) => EditPayload::Text(s.to_string()),
+            Err(_) => EditPayload::Bytes(bytes.to_vec()),
+        }
+    }
+}
+
+/// One applied replacement: where it landed in the original bytes, and
+/// what text it matched and was replaced with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditRecord {
+    /// Byte offset of the start of the match, in the input given to this operation.
+    pub start: usize,
+    /// Byte offset just past the end of the match.
+    pub end: usize,
+    /// 1-based line number the match starts on.
+    pub line_number: usize,
+    #[serde(rename = "match")]
+    pub matched: EditPayload,
+    pub replacement: EditPayload,
+}
+
+/// Whether a [`DiffLineData`] was present on both sides of a hunk
+/// (`Context`), only the old side (`Removed`), or only the new side
+/// (`Added`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineTag {
+    Context,
+    Removed,
+    Added,
+    /// Not a content line: unified diff's `\ No newline at end of file`
+    /// marker, carried as its own tagged (empty-text) line. See
+    /// [`crate::diff::LineTag::NoNewline`].
+    NoNewline,
+}
+
+/// One line of a [`DiffHunkData`]: its tag plus the line text itself
+/// (including the trailing newline, or lack of one, exactly as it appeared
+/// in the file being diffed).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLineData {
+    pub tag: DiffLineTag,
+    pub text: String,
+}
+
+/// One `@@ -a,b +c,d @@` hunk of a unified diff, as structured data: the
+/// four header numbers plus its tagged lines. Built from
+/// [`crate::diff::StructuredHunk`] for `Report::print_json`'s `diff_hunks`
+/// field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunkData {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLineData>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,3 +165,60 @@ pub struct RunEnd {
     pub policy_violation: Option<String>,
     pub exit_code: i32,
 }
+
+/// Ripgrep-compatible (`rg --json`) event schema, produced for
+/// `--format=json-lines` so downstream tooling that already speaks `rg
+/// --json` (including txed's own `--rg-json` consumer) can read txed's
+/// output unchanged.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RgJsonEvent {
+    Begin { data: RgJsonPathData },
+    Match { data: RgJsonMatchData },
+    End { data: RgJsonEndData },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RgJsonPathData {
+    pub path: RgJsonText,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RgJsonText {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RgJsonMatchData {
+    pub path: RgJsonText,
+    pub line_number: usize,
+    pub absolute_offset: usize,
+    pub submatches: Vec<RgJsonSubmatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RgJsonSubmatch {
+    #[serde(rename = "match")]
+    pub matched: EditPayload,
+    pub replacement: EditPayload,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RgJsonEndData {
+    pub path: RgJsonText,
+    pub stats: RgJsonStats,
+}
+
+/// Per-file summary for a `json-lines` `end` event. `bytes` is the total
+/// span of matched (not file) bytes, since txed doesn't otherwise track
+/// per-file read size; `elapsed` is the whole run's duration, since txed
+/// doesn't time individual files.
+#[derive(Debug, Clone, Serialize)]
+pub struct RgJsonStats {
+    pub matches: usize,
+    pub matched_lines: usize,
+    pub bytes: usize,
+    pub elapsed: f64,
+}
@@ -0,0 +1,180 @@
+use crate::error::{Error, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::BTreeMap;
+
+/// A ripgrep-style named file-type registry: maps a short type name (e.g.
+/// `rust`) to the glob patterns that identify it (e.g. `*.rs`). Backed by a
+/// `BTreeMap` so `--type-list` prints types in a stable, lexicographic
+/// order regardless of insertion order.
+#[derive(Debug, Clone)]
+pub struct TypeTable(BTreeMap<String, Vec<String>>);
+
+impl TypeTable {
+    /// The built-in type table. Not as exhaustive as ripgrep's own registry,
+    /// but covers the common cases; `--type-add` extends or overrides it.
+    pub fn builtin() -> Self {
+        let entries: &[(&str, &[&str])] = &[
+            ("c", &["*.c", "*.h"]),
+            ("cpp", &["*.cpp", "*.cc", "*.cxx", "*.hpp", "*.hh"]),
+            ("css", &["*.css"]),
+            ("go", &["*.go"]),
+            ("html", &["*.html", "*.htm"]),
+            ("java", &["*.java"]),
+            ("js", &["*.js", "*.mjs", "*.cjs"]),
+            ("json", &["*.json"]),
+            ("md", &["*.md", "*.markdown"]),
+            ("py", &["*.py"]),
+            ("rust", &["*.rs"]),
+            ("sh", &["*.sh", "*.bash"]),
+            ("toml", &["*.toml"]),
+            ("ts", &["*.ts", "*.tsx"]),
+            ("txt", &["*.txt"]),
+            ("yaml", &["*.yaml", "*.yml"]),
+        ];
+
+        let mut map = BTreeMap::new();
+        for (name, globs) in entries {
+            map.insert((*name).to_string(), globs.iter().map(|g| (*g).to_string()).collect());
+        }
+        Self(map)
+    }
+
+    /// Apply a `--type-add` spec: either `NAME:GLOB` (extend or define a
+    /// type's glob list) or `NAME:include:OTHER` (extend a type with
+    /// another, already-defined type's globs, composing them).
+    pub fn add_spec(&mut self, spec: &str) -> Result<()> {
+        let mut parts = spec.splitn(3, ':');
+        let name = parts
+            .next()
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| Error::Validation(format!("invalid --type-add spec '{}': expected NAME:GLOB", spec)))?;
+
+        let rest = parts
+            .next()
+            .ok_or_else(|| Error::Validation(format!("invalid --type-add spec '{}': expected NAME:GLOB", spec)))?;
+
+        if rest == "include" {
+            let other = parts
+                .next()
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| Error::Validation(format!(
+                    "invalid --type-add spec '{}': expected NAME:include:OTHER", spec
+                )))?;
+            let other_globs = self
+                .0
+                .get(other)
+                .ok_or_else(|| Error::Validation(format!("unknown file type '{}' in --type-add alias", other)))?
+                .clone();
+            self.0.entry(name.to_string()).or_default().extend(other_globs);
+        } else {
+            // The remainder (after the first ':') is the glob itself, so a
+            // glob containing ':' is still captured whole via splitn(3, ..).
+            let glob = if let Some(third) = parts.next() {
+                format!("{}:{}", rest, third)
+            } else {
+                rest.to_string()
+            };
+            self.0.entry(name.to_string()).or_default().push(glob);
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a list of type names to their combined, flattened glob
+    /// patterns. Errors naming the first unknown type encountered.
+    pub fn globs_for(&self, names: &[String]) -> Result<Vec<String>> {
+        let mut globs = Vec::new();
+        for name in names {
+            let entry = self
+                .0
+                .get(name)
+                .ok_or_else(|| Error::Validation(format!("unknown file type '{}' (see --type-list)", name)))?;
+            globs.extend(entry.iter().cloned());
+        }
+        Ok(globs)
+    }
+
+    /// Build a `GlobSet` matching any of the given type names' globs, for
+    /// use as a `--type`/`--type-not` filter over resolved input paths.
+    pub fn build_set(&self, names: &[String]) -> Result<GlobSet> {
+        let mut b = GlobSetBuilder::new();
+        for glob in self.globs_for(names)? {
+            b.add(Glob::new(&glob).map_err(|e| Error::Validation(format!("Invalid glob '{}': {}", glob, e)))?);
+        }
+        b.build().map_err(|e| Error::Validation(format!("Failed to build type glob set: {}", e)))
+    }
+
+    /// Render the resolved table for `--type-list`, one type per line,
+    /// sorted lexicographically by name.
+    pub fn format_list(&self) -> String {
+        let mut out = String::new();
+        for (name, globs) in &self.0 {
+            out.push_str(&format!("{}: {}\n", name, globs.join(", ")));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_resolves_known_type() {
+        let table = TypeTable::builtin();
+        assert_eq!(table.globs_for(&["rust".into()]).unwrap(), vec!["*.rs".to_string()]);
+    }
+
+    #[test]
+    fn unknown_type_errors() {
+        let table = TypeTable::builtin();
+        let err = table.globs_for(&["nope".into()]).unwrap_err();
+        assert!(err.to_string().contains("unknown file type"));
+    }
+
+    #[test]
+    fn type_add_defines_new_type() {
+        let mut table = TypeTable::builtin();
+        table.add_spec("foo:*.foo").unwrap();
+        assert_eq!(table.globs_for(&["foo".into()]).unwrap(), vec!["*.foo".to_string()]);
+    }
+
+    #[test]
+    fn type_add_extends_existing_type() {
+        let mut table = TypeTable::builtin();
+        table.add_spec("rust:*.rs.in").unwrap();
+        assert_eq!(
+            table.globs_for(&["rust".into()]).unwrap(),
+            vec!["*.rs".to_string(), "*.rs.in".to_string()]
+        );
+    }
+
+    #[test]
+    fn type_add_include_alias_composes_globs() {
+        let mut table = TypeTable::builtin();
+        table.add_spec("web:include:html").unwrap();
+        table.add_spec("web:include:css").unwrap();
+        let mut globs = table.globs_for(&["web".into()]).unwrap();
+        globs.sort();
+        let mut expected = vec!["*.html".to_string(), "*.htm".to_string(), "*.css".to_string()];
+        expected.sort();
+        assert_eq!(globs, expected);
+    }
+
+    #[test]
+    fn type_add_include_unknown_other_errors() {
+        let mut table = TypeTable::builtin();
+        let err = table.add_spec("web:include:nope").unwrap_err();
+        assert!(err.to_string().contains("unknown file type"));
+    }
+
+    #[test]
+    fn format_list_is_sorted() {
+        let table = TypeTable::builtin();
+        let rendered = table.format_list();
+        let names: Vec<&str> = rendered.lines().map(|l| l.split(':').next().unwrap()).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::error::{Error, Result};
+use std::path::Path;
+
+/// A parsed `--filter` expression, modeled on Cargo's `cfg(...)` syntax:
+///
+/// ```text
+/// predicate := ident
+///            | ident "=" "string"
+///            | "not" "(" predicate ")"
+///            | ("all" | "any") "(" predicate ("," predicate)* ")"
+/// ```
+///
+/// Supported atoms: `ext = "rs"` (file extension, case-insensitive), `name =
+/// "..."` (exact basename match), `path = "..."` (substring match on the
+/// full path), `hidden` (basename starts with `.`), and `symlink` (path is a
+/// symlink on disk).
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    All(Vec<FilterExpr>),
+    Any(Vec<FilterExpr>),
+    Not(Box<FilterExpr>),
+    Atom { key: String, value: Option<String> },
+}
+
+impl FilterExpr {
+    /// Parse a `--filter` expression, consuming the whole string.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parser = Parser::new(input);
+        let expr = parser.parse_predicate()?;
+        parser.expect_end()?;
+        Ok(expr)
+    }
+
+    /// Evaluate this predicate against a path. Atoms that inspect the
+    /// filesystem (`symlink`) treat a path that doesn't exist as not
+    /// matching, rather than erroring.
+    pub fn eval(&self, path: &Path) -> bool {
+        match self {
+            FilterExpr::All(exprs) => exprs.iter().all(|e| e.eval(path)),
+            FilterExpr::Any(exprs) => exprs.iter().any(|e| e.eval(path)),
+            FilterExpr::Not(inner) => !inner.eval(path),
+            FilterExpr::Atom { key, value } => eval_atom(key, value.as_deref(), path),
+        }
+    }
+}
+
+fn eval_atom(key: &str, value: Option<&str>, path: &Path) -> bool {
+    match (key, value) {
+        ("ext", Some(want)) => path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case(want)),
+        ("name", Some(want)) => path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy() == want),
+        ("path", Some(want)) => path.to_string_lossy().contains(want),
+        ("hidden", None) => path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.')),
+        ("symlink", None) => std::fs::symlink_metadata(path)
+            .map(|m| m.is_symlink())
+            .unwrap_or(false),
+        _ => unreachable!("atom arity already validated at parse time"),
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.char_indices().peekable(),
+            input,
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn expect_char(&mut self, expected: char) -> Result<()> {
+        self.skip_ws();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((i, c)) => Err(Error::Validation(format!(
+                "expected '{}' at offset {} in filter expression, found '{}'",
+                expected, i, c
+            ))),
+            None => Err(Error::Validation(format!(
+                "expected '{}' but filter expression ended",
+                expected
+            ))),
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<()> {
+        self.skip_ws();
+        match self.chars.peek() {
+            None => Ok(()),
+            Some(&(i, c)) => Err(Error::Validation(format!(
+                "unexpected trailing '{}' at offset {} in filter expression",
+                c, i
+            ))),
+        }
+    }
+
+    fn read_ident(&mut self) -> Result<String> {
+        self.skip_ws();
+        let start = match self.chars.peek() {
+            Some(&(i, c)) if c.is_alphabetic() || c == '_' => i,
+            _ => {
+                return Err(Error::Validation(
+                    "expected an identifier in filter expression".into(),
+                ))
+            }
+        };
+        let mut end = start;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                end = i + c.len_utf8();
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        Ok(self.input[start..end].to_string())
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        self.expect_char('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((_, c)) => s.push(c),
+                None => {
+                    return Err(Error::Validation(
+                        "unterminated string literal in filter expression".into(),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn parse_predicate(&mut self) -> Result<FilterExpr> {
+        let ident = self.read_ident()?;
+        match ident.as_str() {
+            "not" => {
+                self.expect_char('(')?;
+                let inner = self.parse_predicate()?;
+                self.expect_char(')')?;
+                Ok(FilterExpr::Not(Box::new(inner)))
+            }
+            "all" | "any" => {
+                self.expect_char('(')?;
+                let mut preds = vec![self.parse_predicate()?];
+                while self.peek_char() == Some(',') {
+                    self.chars.next();
+                    preds.push(self.parse_predicate()?);
+                }
+                self.expect_char(')')?;
+                if ident == "all" {
+                    Ok(FilterExpr::All(preds))
+                } else {
+                    Ok(FilterExpr::Any(preds))
+                }
+            }
+            "ext" | "name" | "path" => {
+                self.expect_char('=')?;
+                let value = self.read_string()?;
+                Ok(FilterExpr::Atom {
+                    key: ident,
+                    value: Some(value),
+                })
+            }
+            "hidden" | "symlink" => Ok(FilterExpr::Atom {
+                key: ident,
+                value: None,
+            }),
+            other => Err(Error::Validation(format!(
+                "unknown filter atom '{}' (expected ext, name, path, hidden, symlink, not, all, or any)",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parses_and_matches_ext() {
+        let expr = FilterExpr::parse("ext = \"rs\"").unwrap();
+        assert!(expr.eval(&PathBuf::from("src/main.rs")));
+        assert!(!expr.eval(&PathBuf::from("src/main.toml")));
+    }
+
+    #[test]
+    fn parses_not() {
+        let expr = FilterExpr::parse("not(ext = \"rs\")").unwrap();
+        assert!(!expr.eval(&PathBuf::from("main.rs")));
+        assert!(expr.eval(&PathBuf::from("main.toml")));
+    }
+
+    #[test]
+    fn parses_any_and_all() {
+        let any = FilterExpr::parse("any(ext = \"rs\", ext = \"toml\")").unwrap();
+        assert!(any.eval(&PathBuf::from("a.rs")));
+        assert!(any.eval(&PathBuf::from("a.toml")));
+        assert!(!any.eval(&PathBuf::from("a.md")));
+
+        let all = FilterExpr::parse("all(ext = \"rs\", not(hidden))").unwrap();
+        assert!(all.eval(&PathBuf::from("src/main.rs")));
+        assert!(!all.eval(&PathBuf::from(".hidden.rs")));
+    }
+
+    #[test]
+    fn matches_hidden_and_path() {
+        let expr = FilterExpr::parse("hidden").unwrap();
+        assert!(expr.eval(&PathBuf::from(".gitignore")));
+        assert!(!expr.eval(&PathBuf::from("gitignore")));
+
+        let expr = FilterExpr::parse("path = \"src/\"").unwrap();
+        assert!(expr.eval(&PathBuf::from("src/main.rs")));
+        assert!(!expr.eval(&PathBuf::from("tests/main.rs")));
+    }
+
+    #[test]
+    fn rejects_unknown_atom() {
+        assert!(FilterExpr::parse("bogus").is_err());
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        assert!(FilterExpr::parse("hidden extra").is_err());
+    }
+}
@@ -1,8 +1,10 @@
 use crate::error::{Error, Result};
-use std::io::{self, BufRead, Read, BufReader};
-use std::path::PathBuf;
+use std::io::{self, BufRead, Read, Write, BufReader};
+use std::path::{Path, PathBuf};
 use crate::rgjson::{stream_rg_json_ndjson, DeinterleavingSink};
 use crate::model::ReplacementRange;
+use crate::replacer::Replacer;
+use serde::Deserialize;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum InputMode {
@@ -17,23 +19,58 @@ pub enum InputMode {
     StdinText,
     /// Read ripgrep JSON from stdin.
     RipgrepJson,
+    /// Read a tool-agnostic NDJSON edit plan from stdin.
+    EditPlan,
+    /// Read a unified diff from stdin and apply its hunks directly.
+    Patch,
+    /// Read a `cargo build`/`cargo clippy --message-format=json` diagnostic
+    /// stream from stdin and apply its machine-applicable suggestions directly.
+    Rustfix,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum InputItem {
     Path(PathBuf),
     StdinText(String),
     RipgrepMatch {
         path: PathBuf,
         matches: Vec<ReplacementRange>,
+        /// One entry per matched line, recording the line's start offset
+        /// and the exact bytes ripgrep reported for it (`lines.text`/
+        /// `lines.bytes`), so the engine can detect a file that's changed
+        /// since `rg --json` ran and report a conflict instead of
+        /// silently applying (or silently not applying) a stale edit.
+        anchors: Vec<RipgrepAnchor>,
+    },
+    /// A targeted edit driven by an external tool's NDJSON plan rather than
+    /// `rg --json`. `ranges` empty means "apply the pattern to the whole
+    /// file"; `replacement`, when set, overrides the CLI/manifest REPLACE
+    /// text for this file only.
+    EditPlan {
+        path: PathBuf,
+        ranges: Vec<ReplacementRange>,
+        replacement: Option<String>,
     },
 }
 
+/// The start offset and expected bytes of a line ripgrep reported a match
+/// on, used to detect whether the file has changed underneath a `--rg-json`
+/// run. See [`InputItem::RipgrepMatch`].
+#[derive(Debug, Clone)]
+pub struct RipgrepAnchor {
+    pub offset: usize,
+    pub expected: Vec<u8>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn resolve_input_mode(
     stdin_paths: bool,
     files0: bool,
     stdin_text: bool,
     rg_json: bool,
+    edit_plan: bool,
+    patch: bool,
+    rustfix: bool,
     files_arg: bool,
     files: &Vec<PathBuf>,
 ) -> InputMode {
@@ -41,6 +78,12 @@ pub fn resolve_input_mode(
         InputMode::StdinText
     } else if rg_json {
         InputMode::RipgrepJson
+    } else if edit_plan {
+        InputMode::EditPlan
+    } else if patch {
+        InputMode::Patch
+    } else if rustfix {
+        InputMode::Rustfix
     } else if files0 {
         InputMode::StdinPathsNul
     } else if stdin_paths {
@@ -67,12 +110,18 @@ pub fn read_paths_from_stdin() -> Result<Vec<PathBuf>> {
 }
 
 /// Read NUL-delimited paths from stdin.
+///
+/// On Unix, paths are built directly from the raw bytes (via
+/// `OsStrExt::from_bytes`), since paths there are arbitrary byte strings
+/// and are not required to be valid UTF-8 (e.g. from `find -print0` over a
+/// filesystem with non-UTF-8 names). Other platforms require valid UTF-8,
+/// since `OsString` there can't be built from arbitrary bytes.
 pub fn read_paths_from_stdin_zero() -> Result<Vec<PathBuf>> {
     let stdin = io::stdin();
     let mut handle = stdin.lock();
     let mut paths = Vec::new();
     let mut buf = Vec::new();
-    
+
     // read_until includes the delimiter
     while handle.read_until(0, &mut buf).map_err(Error::Io)? > 0 {
         // Remove the trailing NUL
@@ -80,15 +129,26 @@ pub fn read_paths_from_stdin_zero() -> Result<Vec<PathBuf>> {
             buf.pop();
         }
         if !buf.is_empty() {
-             let s = String::from_utf8(buf.clone())
-                .map_err(|e| Error::Validation(format!("Invalid UTF-8 in path: {}", e)))?;
-             paths.push(PathBuf::from(s));
+            paths.push(path_from_bytes(&buf)?);
         }
         buf.clear();
     }
     Ok(paths)
 }
 
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> Result<PathBuf> {
+    let s = std::str::from_utf8(bytes)
+        .map_err(|e| Error::Validation(format!("Invalid UTF-8 in path: {}", e)))?;
+    Ok(PathBuf::from(s))
+}
+
 /// Read all text from stdin.
 pub fn read_stdin_text() -> Result<String> {
     let mut buffer = String::new();
@@ -100,50 +160,314 @@ pub fn read_stdin_text() -> Result<String> {
     Ok(buffer)
 }
 
+/// Number of bytes read from stdin per iteration in `stream_stdin_text`.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Apply `replacer` to stdin in fixed-size chunks, writing transformed
+/// output to stdout as it goes, instead of buffering the whole input (the
+/// approach `read_stdin_text` takes, which is unworkable for multi-gigabyte
+/// pipes). Returns whether anything was modified and the total number of
+/// replacements made.
+///
+/// A match can straddle a chunk boundary, so bytes aren't flushed the
+/// moment they're read: each iteration holds back a trailing `window`-byte
+/// tail, since any match starting in that tail could extend past the end of
+/// the chunk read so far. Only the bytes before that tail are searched and
+/// flushed, and a match that starts before the tail but runs into it moves
+/// the cut back to its own start, so it is searched whole on a later pass;
+/// everything after the cut is prepended to the next chunk. For a
+/// literal pattern the window is just the pattern's own length — a literal
+/// match can't be longer than the needle. A regex's longest possible match
+/// is unbounded, so its window instead comes from `--max-match-window`;
+/// widen it if a pattern can match across more than that many bytes.
+///
+/// Because each flushed slice is searched independently, `--limit` is
+/// enforced per chunk rather than as a single global budget across the
+/// whole stream.
+///
+/// The caller is responsible for the streaming-mode `generated_content`
+/// semantics: since the full transformed content is never held in memory,
+/// it can't be attached to the JSON report like the whole-buffer path does.
+pub fn stream_stdin_text(replacer: &Replacer, max_match_window: usize) -> Result<(bool, usize)> {
+    let window = replacer.literal_len().unwrap_or(max_match_window).max(1);
+
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+
+    let mut held: Vec<u8> = Vec::new();
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut modified = false;
+    let mut total_replacements = 0usize;
+
+    loop {
+        let n = reader.read(&mut buf).map_err(Error::Io)?;
+        if n == 0 {
+            break;
+        }
+        held.extend_from_slice(&buf[..n]);
+
+        if held.len() > window {
+            let mut flush_len = held.len() - window;
+            // Don't cut through a match that starts before the tail and runs
+            // into it; flush only up to where it starts.
+            let (_, edits) = replacer.replace_with_edits(&held);
+            if let Some(edit) = edits.iter().find(|e| e.start < flush_len && e.end > flush_len) {
+                flush_len = edit.start;
+            }
+            let (out, replacements) = replacer.replace_with_count(&held[..flush_len]);
+            if replacements > 0 {
+                modified = true;
+                total_replacements += replacements;
+            }
+            writer.write_all(&out).map_err(Error::Io)?;
+            held.drain(..flush_len);
+        }
+    }
+
+    // Nothing left to arrive, so the whole retained tail can be searched.
+    let (out, replacements) = replacer.replace_with_count(&held);
+    if replacements > 0 {
+        modified = true;
+        total_replacements += replacements;
+    }
+    writer.write_all(&out).map_err(Error::Io)?;
+    writer.flush().map_err(Error::Io)?;
+
+    Ok((modified, total_replacements))
+}
+
 /// Read ripgrep JSON output and extract paths and matches.
-/// Uses DeinterleavingSink to group by file.
+///
+/// Uses [`DeinterleavingSink`] to group `match` records by file (ripgrep's
+/// threaded search interleaves output from multiple files on stdout).
+/// `begin`/`context`/`end`/`summary` records are dropped by the sink before
+/// they reach here, and `RgTextOrBytes::to_os_string`/`as_bytes` already
+/// transparently base64-decode any `{"bytes": ...}` path or match payload, so
+/// this loop only has to turn each match's submatches into absolute byte
+/// ranges: `absolute_offset` is the start of `lines.text` in the file, and
+/// `submatch.start`/`end` are relative to that, so the absolute range is
+/// `absolute_offset + start .. absolute_offset + end`. A submatch without an
+/// `absolute_offset` can't be placed in the file without re-reading it, so it
+/// is skipped rather than guessed at.
 pub fn read_rg_json() -> Result<Vec<InputItem>> {
     let stdin = io::stdin();
     let reader = BufReader::new(stdin.lock());
     let mut sink = DeinterleavingSink::new();
-    
+
     stream_rg_json_ndjson(reader, &mut sink).map_err(|e| Error::Validation(format!("Failed to parse rg json: {}", e)))?;
-    
+
     let mut items = Vec::new();
 
     for (path_os, events) in sink.events {
         let path = PathBuf::from(path_os);
         let mut matches = Vec::new();
+        let mut anchors = Vec::new();
 
         for event in events {
-             // For each event (RgData), we extract submatches
-             // If absolute_offset is present, we can calculate absolute ranges
-             if let Some(abs_start) = event.absolute_offset {
-                 for sub in event.submatches {
-                     // sub.start/end are relative to the match text?
-                     // Usually rg submatches are relative to the line content start?
-                     // Let's assume absolute_offset is the line start.
-                     // And sub.start is offset from line start.
-                     let start = (abs_start as usize) + (sub.start as usize);
-                     let end = (abs_start as usize) + (sub.end as usize);
-                     matches.push(ReplacementRange { start, end });
-                 }
-             } else {
-                 // Fallback or warning?
-                 // If no absolute offset, we can't do safe targeted replacement reliably without re-reading file lines.
-                 // For now, skip if we can't determine range.
-             }
+            if let Some(abs_start) = event.absolute_offset {
+                let offset = abs_start as usize;
+                for sub in &event.submatches {
+                    let start = offset + (sub.start as usize);
+                    let end = offset + (sub.end as usize);
+                    matches.push(ReplacementRange { start, end });
+                }
+                if let Some(lines) = &event.lines {
+                    if let Ok(expected) = lines.as_bytes() {
+                        anchors.push(RipgrepAnchor { offset, expected: expected.into_owned() });
+                    }
+                }
+            }
         }
-        
-        // Merge overlapping or adjacent ranges?
-        // Not strictly necessary if the engine handles overlapping replacements, but good practice.
-        // For now, just pass them.
-        
+
         items.push(InputItem::RipgrepMatch {
-            path,
-            matches,
+            path: path.clone(),
+            matches: normalize_ranges(&path, matches)?,
+            anchors,
+        });
+    }
+
+    Ok(items)
+}
+
+/// Sort `ranges` by start and make them disjoint before they reach the
+/// engine, since ripgrep can report the same submatch more than once (e.g.
+/// duplicate `match` lines for a multiline hit) and submatch order on the
+/// wire isn't guaranteed to follow byte order.
+///
+/// Identical ranges (duplicate events for the same submatch) are silently
+/// deduplicated. Two *different* ranges that overlap are a genuine conflict
+/// — applying both would mean splicing the same bytes twice in an
+/// unspecified order — and are rejected with `Error::Validation` naming the
+/// file and the conflicting offsets, rather than silently corrupting the
+/// file. Ranges that merely touch (one's end equals the next's start) are
+/// left as separate, adjacent edits.
+fn normalize_ranges(path: &Path, mut ranges: Vec<ReplacementRange>) -> Result<Vec<ReplacementRange>> {
+    if ranges.len() < 2 {
+        return Ok(ranges);
+    }
+
+    ranges.sort_by_key(|r| (r.start, r.end));
+
+    let mut out: Vec<ReplacementRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        if let Some(prev) = out.last() {
+            if *prev == range {
+                continue;
+            }
+            if range.start < prev.end {
+                return Err(Error::Validation(format!(
+                    "overlapping replacement ranges in {}: {}..{} conflicts with {}..{}",
+                    path.display(),
+                    prev.start,
+                    prev.end,
+                    range.start,
+                    range.end
+                )));
+            }
+        }
+        out.push(range);
+    }
+
+    Ok(out)
+}
+
+/// One line of a generic NDJSON edit plan: `{"path": "...", "ranges":
+/// [{"start": N, "end": M}], "replacement": "..."?}`. `ranges` defaults to
+/// empty (meaning "whole file") and `replacement` to `None` (meaning "use
+/// the CLI/manifest REPLACE text") when omitted.
+#[derive(Debug, Deserialize)]
+struct EditPlanLine {
+    path: PathBuf,
+    #[serde(default)]
+    ranges: Vec<ReplacementRange>,
+    #[serde(default)]
+    replacement: Option<String>,
+}
+
+/// Read a tool-agnostic NDJSON edit plan from stdin, one JSON object per
+/// line. Unlike `--rg-json`, this format isn't tied to ripgrep's schema, so
+/// editors, LSP-style tools, or custom scripts can drive precise byte-range
+/// replacements directly.
+pub fn read_edit_plan() -> Result<Vec<InputItem>> {
+    let stdin = io::stdin();
+    parse_edit_plan(stdin.lock())
+}
+
+/// Parse a tool-agnostic NDJSON edit plan, one `EditPlanLine` per line.
+/// Blank lines are skipped; `ranges` empty means "apply to the whole file"
+/// (see [`InputItem::EditPlan`]); ranges go through [`normalize_ranges`] the
+/// same way `--rg-json` submatches do, so identical ranges are deduplicated
+/// and overlapping different ones are rejected.
+fn parse_edit_plan<R: BufRead>(reader: R) -> Result<Vec<InputItem>> {
+    let mut items = Vec::new();
+
+    for line in reader.lines() {
+        let line = line.map_err(Error::Io)?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let entry: EditPlanLine = serde_json::from_str(line)
+            .map_err(|e| Error::Validation(format!("Failed to parse edit plan line: {}", e)))?;
+        let ranges = normalize_ranges(&entry.path, entry.ranges)?;
+        items.push(InputItem::EditPlan {
+            path: entry.path,
+            ranges,
+            replacement: entry.replacement,
         });
     }
 
     Ok(items)
 }
+
+/// Read a unified diff from stdin (as produced by `--format patch`, `git
+/// diff`, or `diff -u`) and group its hunks by the file they target. Like
+/// non-streaming `--stdin-text`, this reads all of stdin upfront rather than
+/// line-by-line, since a hunk's `@@` header can only be told apart from its
+/// body by also seeing the lines that follow it.
+pub fn read_patch() -> Result<Vec<crate::diff::FilePatch>> {
+    let mut content = String::new();
+    io::stdin().lock().read_to_string(&mut content).map_err(Error::Io)?;
+    crate::diff::parse_patch(&content).map_err(Error::Validation)
+}
+
+/// Read a `cargo build`/`cargo clippy --message-format=json` diagnostic
+/// stream from stdin, grouping every machine-applicable suggestion by the
+/// file it targets. See [`crate::rustfix::parse_rustfix`].
+pub fn read_rustfix() -> Result<Vec<crate::rustfix::RustfixPatch>> {
+    let stdin = io::stdin();
+    crate::rustfix::parse_rustfix(BufReader::new(stdin.lock()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: usize, end: usize) -> ReplacementRange {
+        ReplacementRange { start, end }
+    }
+
+    #[test]
+    fn normalize_ranges_dedups_identical_ranges() {
+        let ranges = normalize_ranges(Path::new("f.rs"), vec![range(0, 3), range(0, 3)]).unwrap();
+        assert_eq!(ranges, vec![range(0, 3)]);
+    }
+
+    #[test]
+    fn normalize_ranges_rejects_overlapping_ranges() {
+        let err = normalize_ranges(Path::new("f.rs"), vec![range(0, 5), range(3, 8)]).unwrap_err();
+        match err {
+            Error::Validation(msg) => {
+                assert!(msg.contains("f.rs"));
+                assert!(msg.contains("0..5"));
+                assert!(msg.contains("3..8"));
+            }
+            other => panic!("expected Error::Validation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn normalize_ranges_allows_touching_ranges() {
+        let ranges = normalize_ranges(Path::new("f.rs"), vec![range(0, 3), range(3, 6)]).unwrap();
+        assert_eq!(ranges, vec![range(0, 3), range(3, 6)]);
+    }
+
+    #[test]
+    fn parse_edit_plan_basic_parse_and_apply() {
+        let input = r#"{"path":"a.txt","ranges":[{"start":0,"end":3}]}"#;
+        let items = parse_edit_plan(input.as_bytes()).unwrap();
+        assert_eq!(items.len(), 1);
+        match &items[0] {
+            InputItem::EditPlan { path, ranges, replacement } => {
+                assert_eq!(path, &PathBuf::from("a.txt"));
+                assert_eq!(ranges, &vec![range(0, 3)]);
+                assert_eq!(replacement, &None);
+            }
+            other => panic!("expected InputItem::EditPlan, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_edit_plan_honors_per_line_replacement_override() {
+        let input = r#"{"path":"a.txt","ranges":[{"start":0,"end":3}],"replacement":"bar"}"#;
+        let items = parse_edit_plan(input.as_bytes()).unwrap();
+        match &items[0] {
+            InputItem::EditPlan { replacement, .. } => {
+                assert_eq!(replacement.as_deref(), Some("bar"));
+            }
+            other => panic!("expected InputItem::EditPlan, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_edit_plan_omitted_ranges_means_whole_file() {
+        let input = r#"{"path":"a.txt"}"#;
+        let items = parse_edit_plan(input.as_bytes()).unwrap();
+        match &items[0] {
+            InputItem::EditPlan { ranges, .. } => assert!(ranges.is_empty()),
+            other => panic!("expected InputItem::EditPlan, got {other:?}"),
+        }
+    }
+}
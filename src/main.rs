@@ -1,30 +1,60 @@
 use anyhow::{Context, Result, bail};
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use std::fs;
 use std::io::IsTerminal;
+use std::time::Duration;
 
 use crate::cli::{Cli, Commands, OutputFormat, PermissionsMode as CliPermissionsMode, DefaultArgs};
+use crate::filter::FilterExpr;
 use crate::input::{InputItem, InputMode};
 use crate::model::{Operation, Pipeline, LineRange, PermissionsMode};
+use crate::reporter::{FileResult, Report};
 
+mod cache;
 mod cli;
+mod diff;
+mod encoding;
 mod engine;
 mod error;
 mod events;
 mod exit_codes;
+mod filetype;
+mod filter;
 mod input;
 mod model;
 mod policy;
+mod recipes;
 mod replacer;
 mod reporter;
 mod rgjson;
+mod rustfix;
 mod transaction;
+mod walk;
+mod watch;
+mod when;
 mod write;
 
-fn parse_range(s: &str) -> Option<LineRange> {
+/// The path a `--filter` predicate should be evaluated against, or `None`
+/// for input items (like `--stdin-text`) that aren't tied to a file path.
+fn input_item_path(item: &InputItem) -> Option<&std::path::Path> {
+    match item {
+        InputItem::Path(p) => Some(p.as_path()),
+        InputItem::RipgrepMatch { path, .. } => Some(path.as_path()),
+        InputItem::EditPlan { path, .. } => Some(path.as_path()),
+        InputItem::StdinText(_) => None,
+    }
+}
+
+/// Parse a single `START[:END]` token. `START`/`END` may be negative to
+/// count back from the last line (resolved at execution time, once the
+/// file's line count is known). A bare number (no `:`) means that specific
+/// line only; `START:` is open-ended through end of file.
+fn parse_range_token(s: &str) -> Option<LineRange> {
     let parts: Vec<&str> = s.split(':').collect();
-    if parts.is_empty() { return None; }
-    
+    if parts.is_empty() || parts[0].is_empty() {
+        return None;
+    }
+
     let start = parts[0].parse().ok()?;
     let end = if parts.len() > 1 {
         if parts[1].is_empty() {
@@ -36,10 +66,34 @@ fn parse_range(s: &str) -> Option<LineRange> {
         // Single number (e.g. "40") means that specific line only (40..40)
         Some(start)
     };
-    
+
     Some(LineRange { start, end })
 }
 
+/// Parse a comma-separated list of `START[:END]` tokens (e.g. `1:10,25,40:`)
+/// into the ranges a `--range` flag selects, in the order given; ranges that
+/// overlap are kept as they are, neither merged nor rejected.
+fn parse_ranges(s: &str) -> Option<Vec<LineRange>> {
+    let mut ranges = Vec::new();
+    for token in s.split(',') {
+        let token = token.trim();
+        if token.is_empty() {
+            continue;
+        }
+        ranges.push(parse_range_token(token)?);
+    }
+    if ranges.is_empty() { None } else { Some(ranges) }
+}
+
+/// Parse repeated `--redact PATTERN=PLACEHOLDER` flags into (pattern, placeholder) pairs.
+/// Entries without an `=` are dropped rather than erroring, since they can't express a rule.
+fn parse_redactions(raw: &[String]) -> Vec<(String, String)> {
+    raw.iter()
+        .filter_map(|s| s.split_once('='))
+        .map(|(pattern, placeholder)| (pattern.to_string(), placeholder.to_string()))
+        .collect()
+}
+
 fn resolve_permissions(args: &DefaultArgs) -> Result<Option<PermissionsMode>> {
     if let Some(ref m_str) = args.mode {
         let m = u32::from_str_radix(m_str, 8).context("Invalid octal mode")?;
@@ -76,6 +130,12 @@ fn try_main() -> Result<i32> {
             println!("{}", serde_json::to_string_pretty(&schema)?);
             return Ok(exit_codes::SUCCESS);
         }
+        Some(Commands::Completions { shell }) => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+            return Ok(exit_codes::SUCCESS);
+        }
         Some(Commands::Apply(args)) => {
             // Manifest is required for apply subcommand
             let manifest_path = Some(args.manifest);
@@ -89,6 +149,48 @@ fn try_main() -> Result<i32> {
             };
             (manifest_path, None, None, vec![], default_args)
         }
+        Some(Commands::Use(args)) => {
+            let path = recipes::RecipeFile::default_path()
+                .context("could not determine recipe config path (no $HOME or $XDG_CONFIG_HOME)")?;
+            let recipe_file = recipes::RecipeFile::load(&path)
+                .with_context(|| format!("loading recipe config from {:?}", path))?;
+            let recipe = recipe_file
+                .resolve(&args.recipe)
+                .with_context(|| format!("resolving recipe '{}'", args.recipe))?
+                .clone();
+
+            let mut default_args = cli.args;
+            default_args.find = Some(recipe.find);
+            default_args.replace = Some(recipe.with);
+            default_args.files = args.files;
+            default_args.regex = recipe.regex;
+            default_args.ignore_case = recipe.ignore_case;
+            default_args.smart_case = recipe.smart_case;
+            default_args.word_regexp = recipe.word_regexp;
+            default_args.multiline = recipe.multiline;
+            default_args.dot_matches_newline = recipe.dot_matches_newline;
+            default_args.no_unicode = recipe.no_unicode;
+            default_args.limit = recipe.limit;
+            default_args.range = recipe.range;
+            default_args.expand = recipe.expand;
+            if args.dry_run {
+                default_args.dry_run = true;
+            }
+            if args.validate_only {
+                default_args.validate_only = true;
+            }
+            if args.json {
+                default_args.json = true;
+            }
+
+            (
+                None,
+                default_args.find.clone(),
+                default_args.replace.clone(),
+                default_args.files.clone(),
+                default_args,
+            )
+        }
         None => {
             // Default command behavior: stedi [OPTIONS] FIND REPLACE [FILES...]
             let default_args = cli.args;
@@ -97,7 +199,24 @@ fn try_main() -> Result<i32> {
     };
     
     // Determine the actual args to use, preferring manifest-specific overrides
-    let args = default_args;
+    let mut args = default_args;
+
+    // `--backup` is a convenience for `--backup-suffix=~`; an explicit
+    // `--backup-suffix` always wins.
+    if args.backup && args.backup_suffix.is_none() {
+        args.backup_suffix = Some("~".to_string());
+    }
+
+    // Build the file-type table (built-ins plus any --type-add specs) up
+    // front, since --type-list needs it even when no FIND/REPLACE is given.
+    let mut type_table = filetype::TypeTable::builtin();
+    for spec in &args.type_add {
+        type_table.add_spec(spec)?;
+    }
+    if args.type_list {
+        print!("{}", type_table.format_list());
+        return Ok(exit_codes::SUCCESS);
+    }
 
     // Resolve input mode
     let mode = input::resolve_input_mode(
@@ -105,15 +224,51 @@ fn try_main() -> Result<i32> {
         args.files0,
         args.stdin_text,
         args.rg_json,
+        args.edit_plan,
+        args.patch,
+        args.rustfix,
         args.files_arg,
         &files,
     );
 
+    // `--patch` doesn't go through the `InputItem` pipeline at all (see
+    // `engine::execute_patch`); read its diffs separately.
+    let patches: Vec<crate::diff::FilePatch> = if mode == InputMode::Patch {
+        input::read_patch()?
+    } else {
+        Vec::new()
+    };
+
+    // `--rustfix` doesn't go through the `InputItem` pipeline either (see
+    // `engine::execute_rustfix`); read its suggestions separately.
+    let rustfix_patches: Vec<crate::rustfix::RustfixPatch> = if mode == InputMode::Rustfix {
+        input::read_rustfix()?
+    } else {
+        Vec::new()
+    };
+
+    // Directory-walk failures (permission denied on a subdir, etc.) are
+    // reported as file-level errors rather than aborting the run; see the
+    // merge into `report` below.
+    let mut walk_errors: Vec<walk::WalkError> = Vec::new();
+
     // 1. Collect inputs
     let mut inputs: Vec<InputItem> = match mode {
         InputMode::Auto(ref paths) => {
             if !paths.is_empty() {
-                 paths.iter().map(|p| InputItem::Path(p.clone())).collect()
+                if args.recursive {
+                    let walk_opts = walk::WalkOptions {
+                        hidden: args.hidden,
+                        no_ignore: args.no_ignore,
+                        max_depth: args.max_depth,
+                        symlinks: args.symlinks.clone().map(Into::into).unwrap_or_default(),
+                    };
+                    let (files, errors) = walk::expand_paths(paths, &walk_opts);
+                    walk_errors = errors;
+                    files.into_iter().map(InputItem::Path).collect()
+                } else {
+                    paths.iter().map(|p| InputItem::Path(p.clone())).collect()
+                }
             } else if !std::io::stdin().is_terminal() {
                 input::read_paths_from_stdin()?.into_iter().map(InputItem::Path).collect()
             } else {
@@ -131,10 +286,47 @@ fn try_main() -> Result<i32> {
         }
                         InputMode::RipgrepJson => {
                              input::read_rg_json()?
-                        }    };
+                        }
+        InputMode::EditPlan => {
+             input::read_edit_plan()?
+        }
+        // Handled separately above via `patches`/`rustfix_patches`.
+        InputMode::Patch => Vec::new(),
+        InputMode::Rustfix => Vec::new(),
+    };
+
+    // Drop items that don't match the selected --type/--type-not file
+    // types before they reach the engine. Items with no associated path
+    // (e.g. --stdin-text) are never filtered, since types only inspect
+    // paths.
+    if !args.file_type.is_empty() {
+        let set = type_table.build_set(&args.file_type)?;
+        inputs.retain(|item| match input_item_path(item) {
+            Some(path) => set.is_match(path),
+            None => true,
+        });
+    }
+    if !args.type_not.is_empty() {
+        let set = type_table.build_set(&args.type_not)?;
+        inputs.retain(|item| match input_item_path(item) {
+            Some(path) => !set.is_match(path),
+            None => true,
+        });
+    }
+
+    // Drop items that don't satisfy --filter before they reach the engine.
+    // Items with no associated path (e.g. --stdin-text) are never filtered,
+    // since the expression language only inspects paths.
+    if let Some(expr) = &args.filter {
+        let expr = FilterExpr::parse(expr).context("parsing --filter expression")?;
+        inputs.retain(|item| match input_item_path(item) {
+            Some(path) => expr.eval(path),
+            None => true,
+        });
+    }
 
     // 2. Build Pipeline
-    let pipeline = if let Some(path) = &manifest_path {
+    let mut pipeline = if let Some(path) = &manifest_path {
         let content = fs::read_to_string(path).context(format!("reading manifest from {:?}", path))?;
         let mut p: Pipeline = serde_json::from_str(&content).context("parsing manifest")?;
 
@@ -145,10 +337,15 @@ fn try_main() -> Result<i32> {
         if args.require_match { p.require_match = true; }
         if args.expect.is_some() { p.expect = args.expect; }
         if args.fail_on_change { p.fail_on_change = true; }
+        if !args.allow_write.is_empty() { p.allow_write = args.allow_write; }
+        if !args.deny_write.is_empty() { p.deny_write = args.deny_write; }
+        if args.fail_on_blocked { p.fail_on_blocked = true; }
         if let Some(t) = &args.transaction { p.transaction = t.clone().into(); }
         if let Some(s) = &args.symlinks { p.symlinks = s.clone().into(); }
         if let Some(b) = &args.binary { p.binary = b.clone().into(); }
-        
+        if let Some(n) = &args.newline_style { p.newline_style = n.clone().into(); }
+        if let Some(w) = &args.write_strategy { p.write_strategy = w.clone().into(); }
+
         // Resolve permissions override
         if let Some(perms) = resolve_permissions(&args)? {
             p.permissions = perms;
@@ -156,15 +353,68 @@ fn try_main() -> Result<i32> {
 
         if !args.glob_include.is_empty() { p.glob_include = Some(args.glob_include); }
         if !args.glob_exclude.is_empty() { p.glob_exclude = Some(args.glob_exclude); }
-        
+        if !args.redact.is_empty() { p.diff_redactions = parse_redactions(&args.redact); }
+        if let Some(c) = args.context { p.diff_context = c; }
+        if args.when.is_some() { p.when = args.when.clone(); }
+        if args.no_mmap { p.no_mmap = true; }
+        if args.mmap_min_size.is_some() { p.mmap_min_size = args.mmap_min_size; }
+        if args.preserve_ownership { p.preserve_ownership = true; }
+        if args.preserve_timestamps { p.preserve_timestamps = true; }
+        if args.preserve_xattrs { p.preserve_xattrs = true; }
+        if args.durable { p.durable = true; }
+        if args.emit_edits { p.emit_edits = true; }
+        if args.backup_suffix.is_some() { p.backup_suffix = args.backup_suffix.clone(); }
+        if args.threads.is_some() { p.threads = args.threads; }
+        if let Some(path) = &args.cache_file { p.cache_path = Some(path.display().to_string()); }
+
         p
+    } else if mode == InputMode::Patch || mode == InputMode::Rustfix {
+        // `--patch`/`--rustfix`: no FIND/REPLACE operation at all, since each
+        // hunk/suggestion already carries its own replacement text.
+        let permissions = resolve_permissions(&args)?.unwrap_or(PermissionsMode::Preserve);
+
+        Pipeline {
+            files: vec![],
+            operations: vec![],
+            dry_run: args.dry_run,
+            no_write: args.no_write,
+            require_match: args.require_match,
+            expect: args.expect,
+            fail_on_change: args.fail_on_change,
+            allow_write: args.allow_write,
+            deny_write: args.deny_write,
+            fail_on_blocked: args.fail_on_blocked,
+            transaction: args.transaction.clone().map(Into::into).unwrap_or_default(),
+            symlinks: args.symlinks.clone().map(Into::into).unwrap_or_default(),
+            binary: args.binary.clone().map(Into::into).unwrap_or_default(),
+            encoding: args.encoding.clone().map(Into::into).unwrap_or_default(),
+            newline_style: args.newline_style.clone().map(Into::into).unwrap_or_default(),
+            write_strategy: args.write_strategy.clone().map(Into::into).unwrap_or_default(),
+            permissions,
+            validate_only: args.validate_only,
+            glob_include: if args.glob_include.is_empty() { None } else { Some(args.glob_include) },
+            glob_exclude: if args.glob_exclude.is_empty() { None } else { Some(args.glob_exclude) },
+            diff_redactions: parse_redactions(&args.redact),
+            diff_context: args.context.unwrap_or(3),
+            when: args.when,
+            no_mmap: args.no_mmap,
+            mmap_min_size: args.mmap_min_size,
+            preserve_ownership: args.preserve_ownership,
+            preserve_timestamps: args.preserve_timestamps,
+            preserve_xattrs: args.preserve_xattrs,
+            durable: args.durable,
+            emit_edits: args.emit_edits,
+            backup_suffix: args.backup_suffix,
+            threads: args.threads,
+            cache_path: args.cache_file.as_ref().map(|p| p.display().to_string()),
+        }
     } else {
         // Construct from CLI args (for default command)
         let find = find.context("FIND pattern is required unless --manifest is used")?;
         let replace = replace.context("REPLACE pattern is required unless --manifest is used")?;
         
-        let range = if let Some(r) = &args.range {
-            parse_range(r)
+        let ranges = if let Some(r) = &args.range {
+            parse_ranges(r)
         } else {
             None
         };
@@ -182,7 +432,7 @@ fn try_main() -> Result<i32> {
             dot_matches_newline: args.dot_matches_newline,
             no_unicode: args.no_unicode,
             limit: args.limit.unwrap_or(0),
-            range,
+            ranges,
             expand: args.expand,
             validation_mode,
         };
@@ -198,13 +448,32 @@ fn try_main() -> Result<i32> {
             require_match: args.require_match,
             expect: args.expect,
             fail_on_change: args.fail_on_change,
+            allow_write: args.allow_write,
+            deny_write: args.deny_write,
+            fail_on_blocked: args.fail_on_blocked,
             transaction: args.transaction.clone().map(Into::into).unwrap_or_default(),
             symlinks: args.symlinks.clone().map(Into::into).unwrap_or_default(),
             binary: args.binary.clone().map(Into::into).unwrap_or_default(),
+            encoding: args.encoding.clone().map(Into::into).unwrap_or_default(),
+            newline_style: args.newline_style.clone().map(Into::into).unwrap_or_default(),
+            write_strategy: args.write_strategy.clone().map(Into::into).unwrap_or_default(),
             permissions, 
             validate_only: args.validate_only,
             glob_include: if args.glob_include.is_empty() { None } else { Some(args.glob_include) },
             glob_exclude: if args.glob_exclude.is_empty() { None } else { Some(args.glob_exclude) },
+            diff_redactions: parse_redactions(&args.redact),
+            diff_context: args.context.unwrap_or(3),
+            when: args.when,
+            no_mmap: args.no_mmap,
+            mmap_min_size: args.mmap_min_size,
+            preserve_ownership: args.preserve_ownership,
+            preserve_timestamps: args.preserve_timestamps,
+            preserve_xattrs: args.preserve_xattrs,
+            durable: args.durable,
+            emit_edits: args.emit_edits,
+            backup_suffix: args.backup_suffix,
+            threads: args.threads,
+            cache_path: args.cache_file.as_ref().map(|p| p.display().to_string()),
         }
     };
 
@@ -215,11 +484,14 @@ fn try_main() -> Result<i32> {
         }
     }
 
-    // 3. Execute
+    // `--format=json-lines` needs per-replacement detail to emit ripgrep-style
+    // `match` events, so force it on the same way `validate_only` forces `dry_run`.
+    if args.format == Some(OutputFormat::JsonLines) {
+        pipeline.emit_edits = true;
+    }
+
     let pipeline_for_report = pipeline.clone();
-    let report = engine::execute(pipeline, inputs)?;
 
-    // 4. Report
     let format = args.format.unwrap_or_else(|| {
         if args.json {
             OutputFormat::Json
@@ -239,14 +511,98 @@ fn try_main() -> Result<i32> {
         InputMode::StdinPathsNul => "files0",
         InputMode::StdinText => "stdin-text",
         InputMode::RipgrepJson => "rg-json",
+        InputMode::EditPlan => "edit-plan",
+        InputMode::Patch => "patch",
+        InputMode::Rustfix => "rustfix",
     };
-    
-    match format {
+
+    let print_report = |report: &Report| match format {
         OutputFormat::Json => report.print_json(&pipeline_for_report, env!("CARGO_PKG_VERSION"), mode_str, input_mode_str),
         OutputFormat::Agent => report.print_agent(),
+        OutputFormat::JsonLines => report.print_json_lines(),
         OutputFormat::Diff => if args.quiet { report.print_errors_only() } else { report.print_human() },
         OutputFormat::Summary => if args.quiet { report.print_errors_only() } else { report.print_summary() },
+        OutputFormat::Patch => if args.quiet { report.print_errors_only() } else { report.print_patch(&pipeline_for_report.diff_redactions) },
+        OutputFormat::Shell => report.print_shell(),
+        OutputFormat::Files0 => report.print_files0(),
+    };
+
+    // `--watch` takes over the whole run: it keeps re-invoking `execute` on
+    // the same resolved inputs, reusing every gating/policy check as-is, and
+    // prints a report per cycle instead of once. It only makes sense for
+    // file-path inputs, since there's nothing on disk to watch otherwise.
+    if args.watch {
+        if !matches!(mode, InputMode::Auto(_)) {
+            bail!("--watch only supports file-path inputs, not stdin-based input modes");
+        }
+        if args.stream {
+            bail!("--watch does not support --stream: each cycle re-invokes the normal whole-buffer execute path");
+        }
+        let debounce = Duration::from_millis(args.watch_debounce_ms);
+        watch::run(&pipeline, inputs, debounce, print_report)?;
+        return Ok(exit_codes::SUCCESS);
     }
 
+    // Directory-walk failures are surfaced as file-level errors rather than
+    // aborting the run; they didn't go through the normal per-file pipeline
+    // so there's no diff/replacement count to report, just the failure.
+    // Built up front (rather than merged into the report after `execute`
+    // returns) so a walk failure can reach `engine::execute` and block its
+    // `--transaction all` commit instead of only affecting the exit code
+    // after every other file's write has already been committed.
+    let walk_error_results: Vec<FileResult> = walk_errors
+        .into_iter()
+        .map(|werr| FileResult {
+            path: werr.path,
+            modified: false,
+            replacements: 0,
+            error: Some(werr.message),
+            skipped: None,
+            diff: None,
+            diff_hunks: None,
+            io: None,
+            generated_content: None,
+            edits: None,
+            backup_path: None,
+        })
+        .collect();
+
+    // 3. Execute
+    let started_at = std::time::Instant::now();
+    let mut report = if inputs.is_empty() && !walk_error_results.is_empty() {
+        // Every input was a directory-walk failure (e.g. `--recursive` hit a
+        // permission-denied subdirectory and found no readable files under
+        // it); there's nothing left to feed the pipeline, but there's still
+        // something to report.
+        let mut report = Report::new(pipeline.dry_run || pipeline.validate_only, pipeline.validate_only);
+        for result in walk_error_results {
+            report.add_result(result);
+        }
+        report
+    } else if args.stream && matches!(mode, InputMode::StdinText) {
+        engine::execute_stdin_streaming(&pipeline, args.max_match_window)?
+    } else if args.stream && matches!(mode, InputMode::Auto(_)) {
+        let paths: Vec<std::path::PathBuf> = inputs
+            .into_iter()
+            .map(|item| match item {
+                InputItem::Path(p) => p,
+                other => unreachable!("InputMode::Auto only ever produces InputItem::Path, got {:?}", other),
+            })
+            .collect();
+        engine::execute_file_streaming(&pipeline, paths, walk_error_results)?
+    } else if args.stream {
+        bail!("--stream only supports --stdin-text or file-path inputs");
+    } else if matches!(mode, InputMode::Patch) {
+        engine::execute_patch(&pipeline, patches)?
+    } else if matches!(mode, InputMode::Rustfix) {
+        engine::execute_rustfix(&pipeline, rustfix_patches)?
+    } else {
+        engine::execute(pipeline, inputs, walk_error_results)?
+    };
+    report.duration_ms = started_at.elapsed().as_millis() as u64;
+
+    // 4. Report
+    print_report(&report);
+
     Ok(report.exit_code())
 }
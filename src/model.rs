@@ -1,10 +1,15 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// A single line-range bound. `start`/`end` are 1-based line numbers, or
+/// negative to count back from the last line (`-1` is the last line, `-5`
+/// the fifth-from-last), resolved against each file's actual line count at
+/// execution time rather than at parse time. `end: None` means "through
+/// end of file".
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LineRange {
-    pub start: usize,
-    pub end: Option<usize>,
+    pub start: i64,
+    pub end: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord)]
@@ -66,6 +71,152 @@ impl Default for PermissionsMode {
     }
 }
 
+/// How to decode a file's on-disk bytes to the UTF-8 text the `Replacer`
+/// matches against, and how to re-encode the replaced result back to
+/// bytes. See [`crate::encoding`] for the actual transcoding.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextEncoding {
+    /// Sniff a UTF-8/UTF-16LE/UTF-16BE BOM; no BOM falls back to UTF-8.
+    Auto,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Single-byte Western European text (ISO-8859-1/"Latin-1", decoded
+    /// via `encoding_rs`'s Windows-1252 superset per the Encoding
+    /// Standard). Every byte maps to some code point, so unlike the
+    /// others this never fails to decode.
+    Latin1,
+}
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+/// Line-ending style enforced on write, applied after all replacement
+/// operations so matches and replacement text both see the file's original
+/// line endings. See [`crate::engine::normalize_newlines`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NewlineStyle {
+    /// Keep the file's existing dominant style (majority of CRLF vs LF).
+    Auto,
+    /// The host platform's convention (`\r\n` on Windows, `\n` elsewhere).
+    Native,
+    /// Rewrite every `\r\n` to `\n`.
+    Unix,
+    /// Rewrite every `\n` to `\r\n`.
+    Windows,
+}
+
+impl Default for NewlineStyle {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl From<crate::cli::NewlineStyle> for NewlineStyle {
+    fn from(item: crate::cli::NewlineStyle) -> Self {
+        match item {
+            crate::cli::NewlineStyle::Auto => NewlineStyle::Auto,
+            crate::cli::NewlineStyle::Native => NewlineStyle::Native,
+            crate::cli::NewlineStyle::Unix => NewlineStyle::Unix,
+            crate::cli::NewlineStyle::Windows => NewlineStyle::Windows,
+        }
+    }
+}
+
+/// How a modified file's new content is committed to disk. See
+/// [`crate::write::StagedEntry`] (and, for the zero-copy scanning angle,
+/// [`crate::write::read_file`]'s doc comment on `WriteStrategy::Mmap`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WriteStrategy {
+    /// Write to a temp file in the target directory, then rename it into
+    /// place. Requires write permission on the directory.
+    Atomic,
+    /// Truncate and rewrite the target file directly, with no rename. Only
+    /// needs write permission on the file itself, not its directory, at the
+    /// cost of a reader being able to observe a half-written file mid-write.
+    /// Not compatible with `--transaction all`, which needs a temp file per
+    /// entry to stage its all-or-nothing swap.
+    InPlace,
+    /// Always memory-map the input for scanning, regardless of
+    /// `--mmap-min-size`. The mapping is always dropped before the write is
+    /// committed, on every platform, so the write itself is still atomic.
+    Mmap,
+}
+
+impl Default for WriteStrategy {
+    fn default() -> Self {
+        Self::Atomic
+    }
+}
+
+impl From<crate::cli::WriteStrategy> for WriteStrategy {
+    fn from(item: crate::cli::WriteStrategy) -> Self {
+        match item {
+            crate::cli::WriteStrategy::Atomic => WriteStrategy::Atomic,
+            crate::cli::WriteStrategy::InPlace => WriteStrategy::InPlace,
+            crate::cli::WriteStrategy::Mmap => WriteStrategy::Mmap,
+        }
+    }
+}
+
+/// How strictly replacement-string capture references (`$1`, `${name}`) are checked.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationMode {
+    /// Reject ambiguous or unknown capture references with an error.
+    Strict,
+    /// Rewrite ambiguous references and warn on unknown ones, but proceed.
+    Warn,
+    /// Perform no validation at all.
+    None,
+}
+
+impl Default for ValidationMode {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl From<crate::cli::ValidationMode> for ValidationMode {
+    fn from(item: crate::cli::ValidationMode) -> Self {
+        match item {
+            crate::cli::ValidationMode::Strict => ValidationMode::Strict,
+            crate::cli::ValidationMode::Warn => ValidationMode::Warn,
+            crate::cli::ValidationMode::None => ValidationMode::None,
+        }
+    }
+}
+
+/// Case transform applied by `Operation::Transform`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseKind {
+    Upper,
+    Lower,
+    TitleCase,
+}
+
+/// Where `Operation::InsertLine` places its new line relative to each line
+/// matching `anchor`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertPosition {
+    Before,
+    After,
+}
+
+impl Default for InsertPosition {
+    fn default() -> Self {
+        Self::After
+    }
+}
+
 /// A single text transformation operation.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -100,12 +251,16 @@ pub enum Operation {
         /// Maximum number of replacements per file (0 = unlimited).
         #[serde(default)]
         limit: usize,
-        /// Only apply replacements in a line range (1-based).
+        /// Only apply replacements within one or more line ranges
+        /// (1-based; a later range may overlap an earlier one).
         #[serde(default)]
-        range: Option<LineRange>,
+        ranges: Option<Vec<LineRange>>,
         /// Enable regex capture expansion (e.g. $1, $name).
         #[serde(default)]
         expand: bool,
+        /// How strictly to validate capture references in `with`.
+        #[serde(default)]
+        validation_mode: ValidationMode,
     },
     /// Delete occurrences of a pattern.
     Delete {
@@ -135,11 +290,35 @@ pub enum Operation {
         /// Maximum number of replacements per file (0 = unlimited).
         #[serde(default)]
         limit: usize,
-        /// Only apply replacements in a line range (1-based).
+        /// Only apply replacements within one or more line ranges
+        /// (1-based; a later range may overlap an earlier one).
+        #[serde(default)]
+        ranges: Option<Vec<LineRange>>,
+    },
+    /// Change the case of every line within `range` (the whole file if
+    /// `None`). Counts one replacement per line actually changed, so it
+    /// composes with `--expect`/`--require-match` like any other operation.
+    Transform {
+        kind: CaseKind,
+        /// Only transform lines within one or more line ranges (same
+        /// 1-based/negative-index convention as `Replace`'s `ranges`).
         #[serde(default)]
-        range: Option<LineRange>,
+        range: Option<Vec<LineRange>>,
+    },
+    /// Insert a new line of `text` immediately before/after every existing
+    /// line containing `anchor` (a literal substring match). Counts one
+    /// replacement per line inserted.
+    InsertLine {
+        anchor: String,
+        text: String,
+        #[serde(default)]
+        position: InsertPosition,
+    },
+    /// Delete every whole line matching `pattern` (a regex). Counts one
+    /// replacement per line removed.
+    DeleteMatching {
+        pattern: String,
     },
-    // Future operations: Insert, RegexReplace, etc.
 }
 
 /// A complete transformation pipeline.
@@ -167,6 +346,20 @@ pub struct Pipeline {
     #[serde(default)]
     pub fail_on_change: bool,
 
+    /// Directories writes are confined to. A file is allowed if its fully
+    /// resolved (symlink- and `..`-free) absolute path is contained in any
+    /// of these. Empty (the default) means no restriction.
+    #[serde(default)]
+    pub allow_write: Vec<String>,
+    /// Directories excluded from writes, checked the same way as
+    /// `allow_write` and taking precedence over it.
+    #[serde(default)]
+    pub deny_write: Vec<String>,
+    /// Exit non-zero if any file was rejected for being outside
+    /// `allow_write`/inside `deny_write`.
+    #[serde(default)]
+    pub fail_on_blocked: bool,
+
     // Transaction model
     #[serde(default)]
     pub transaction: Transaction,
@@ -178,6 +371,12 @@ pub struct Pipeline {
     pub binary: BinaryFileMode,
     #[serde(default)]
     pub permissions: PermissionsMode,
+    /// How to decode/re-encode a file's bytes when it isn't UTF-8.
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// Line-ending style enforced on write.
+    #[serde(default)]
+    pub newline_style: NewlineStyle,
 
     /// Validate manifest and semantic checks without running.
     #[serde(default)]
@@ -189,6 +388,87 @@ pub struct Pipeline {
     /// Glob patterns to exclude.
     #[serde(default)]
     pub glob_exclude: Option<Vec<String>>,
+
+    /// Substitution rules (pattern, placeholder) applied to dry-run diff
+    /// previews before rendering, so volatile text (temp paths, timestamps)
+    /// doesn't make the output non-reproducible.
+    #[serde(default)]
+    pub diff_redactions: Vec<(String, String)>,
+
+    /// Unchanged context lines kept around each hunk in a dry-run diff
+    /// preview, mirroring `diff -u`'s `-U`/`--unified` option.
+    #[serde(default = "default_diff_context")]
+    pub diff_context: usize,
+
+    /// A `--when` expression gating whether each file is edited, evaluated
+    /// against its path, content, and size after it's read. `None` means
+    /// every file that survives glob/type filtering is edited.
+    #[serde(default)]
+    pub when: Option<String>,
+
+    /// Disable the memory-mapped read fast path for large files, always
+    /// using a buffered `fs::read` instead.
+    #[serde(default)]
+    pub no_mmap: bool,
+
+    /// Files at or above this size (in bytes) are memory-mapped rather than
+    /// read with a buffered `fs::read`. Defaults to
+    /// [`crate::write::MMAP_MIN_SIZE`] when unset. Ignored if `no_mmap` is
+    /// set.
+    #[serde(default)]
+    pub mmap_min_size: Option<u64>,
+
+    /// How a modified file's new content is committed to disk.
+    #[serde(default)]
+    pub write_strategy: WriteStrategy,
+
+    /// Best-effort: restore owner/group across the atomic rename (Unix only).
+    #[serde(default)]
+    pub preserve_ownership: bool,
+    /// Best-effort: restore mtime/atime across the atomic rename.
+    #[serde(default)]
+    pub preserve_timestamps: bool,
+    /// Best-effort: copy extended attributes across the atomic rename (Unix only).
+    #[serde(default)]
+    pub preserve_xattrs: bool,
+
+    /// Fsync each written file (and, for `transaction=all`, each touched
+    /// parent directory) so writes survive a crash immediately after commit.
+    #[serde(default)]
+    pub durable: bool,
+
+    /// Report per-replacement detail (byte span, line number, matched and
+    /// substituted text) on each `FileEvent::Success`. Off by default since
+    /// computing and serializing it isn't free.
+    #[serde(default)]
+    pub emit_edits: bool,
+
+    /// If set, preserve a modified file's pre-edit bytes under a sibling
+    /// path formed by appending this suffix (e.g. `.bak`) before its
+    /// replacement content is committed in its place.
+    #[serde(default)]
+    pub backup_suffix: Option<String>,
+
+    /// Worker thread count for the parallel file executor. `None` (the
+    /// default) uses all available parallelism; only meaningful when built
+    /// with the `parallel` feature, since the serial fallback has no pool to
+    /// size.
+    #[serde(default)]
+    pub threads: Option<usize>,
+
+    /// Path to a persistent cache file mapping each processed path to a hash
+    /// of its last-seen content, keyed to a hash of the active operation
+    /// set. A file whose current content hashes the same as its cached
+    /// entry is reported `skipped: Some("unchanged (cache)")` without
+    /// running the replacers. `None` (the default) disables caching
+    /// entirely; always disabled under `dry_run`/`validate_only`, since
+    /// neither is a real run the cache should remember.
+    #[serde(default)]
+    pub cache_path: Option<String>,
+}
+
+fn default_diff_context() -> usize {
+    3
 }
 
 impl Pipeline {
@@ -208,21 +488,41 @@ impl Pipeline {
                 dot_matches_newline: false,
                 no_unicode: false,
                 limit: 0,
-                range: None,
+                ranges: None,
                 expand: false,
+                validation_mode: ValidationMode::default(),
             }],
             dry_run: false,
             no_write: false,
             require_match: false,
             expect: None,
             fail_on_change: false,
+            allow_write: Vec::new(),
+            deny_write: Vec::new(),
+            fail_on_blocked: false,
             transaction: Transaction::default(),
             symlinks: Symlinks::default(),
             binary: BinaryFileMode::default(),
             permissions: PermissionsMode::default(),
+            encoding: TextEncoding::default(),
+            newline_style: NewlineStyle::default(),
             validate_only: false,
             glob_include: None,
             glob_exclude: None,
+            diff_redactions: Vec::new(),
+            diff_context: default_diff_context(),
+            when: None,
+            no_mmap: false,
+            mmap_min_size: None,
+            write_strategy: WriteStrategy::default(),
+            preserve_ownership: false,
+            preserve_timestamps: false,
+            preserve_xattrs: false,
+            durable: false,
+            emit_edits: false,
+            backup_suffix: None,
+            threads: None,
+            cache_path: None,
         }
     }
 }
@@ -237,13 +537,32 @@ impl Default for Pipeline {
             require_match: false,
             expect: None,
             fail_on_change: false,
+            allow_write: Vec::new(),
+            deny_write: Vec::new(),
+            fail_on_blocked: false,
             transaction: Transaction::default(),
             symlinks: Symlinks::default(),
             binary: BinaryFileMode::default(),
             permissions: PermissionsMode::default(),
+            encoding: TextEncoding::default(),
+            newline_style: NewlineStyle::default(),
             validate_only: false,
             glob_include: None,
             glob_exclude: None,
+            diff_redactions: Vec::new(),
+            diff_context: default_diff_context(),
+            when: None,
+            no_mmap: false,
+            mmap_min_size: None,
+            write_strategy: WriteStrategy::default(),
+            preserve_ownership: false,
+            preserve_timestamps: false,
+            preserve_xattrs: false,
+            durable: false,
+            emit_edits: false,
+            backup_suffix: None,
+            threads: None,
+            cache_path: None,
         }
     }
 }
@@ -275,3 +594,15 @@ impl From<crate::cli::BinaryFileMode> for BinaryFileMode {
         }
     }
 }
+
+impl From<crate::cli::TextEncoding> for TextEncoding {
+    fn from(item: crate::cli::TextEncoding) -> Self {
+        match item {
+            crate::cli::TextEncoding::Auto => TextEncoding::Auto,
+            crate::cli::TextEncoding::Utf8 => TextEncoding::Utf8,
+            crate::cli::TextEncoding::Utf16le => TextEncoding::Utf16Le,
+            crate::cli::TextEncoding::Utf16be => TextEncoding::Utf16Be,
+            crate::cli::TextEncoding::Latin1 => TextEncoding::Latin1,
+        }
+    }
+}
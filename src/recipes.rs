@@ -0,0 +1,179 @@
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A single named transformation: a find/replace pair plus the usual
+/// `Operation::Replace` flags, so it can be materialized into the same
+/// pipeline the default `txed FIND REPLACE` command builds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Recipe {
+    pub find: String,
+    pub with: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub ignore_case: bool,
+    #[serde(default)]
+    pub smart_case: bool,
+    #[serde(default)]
+    pub word_regexp: bool,
+    #[serde(default)]
+    pub multiline: bool,
+    #[serde(default)]
+    pub dot_matches_newline: bool,
+    #[serde(default)]
+    pub no_unicode: bool,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub range: Option<String>,
+    #[serde(default)]
+    pub expand: bool,
+}
+
+/// `~/.config/txed/recipes.toml`: named recipes plus simple string
+/// aliases (an alias name resolves to a recipe name one hop deep) so
+/// teams can share a library of transformations instead of re-typing
+/// long regex invocations.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct RecipeFile {
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    pub recipes: BTreeMap<String, Recipe>,
+}
+
+impl RecipeFile {
+    /// Default recipe config location: `$XDG_CONFIG_HOME/txed/recipes.toml`,
+    /// falling back to `~/.config/txed/recipes.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("txed").join("recipes.toml"))
+    }
+
+    /// Load and parse a recipe config file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content)
+            .map_err(|e| Error::Validation(format!("parsing recipe config {:?}: {}", path, e)))
+    }
+
+    /// Resolve `name` through `aliases` (one hop) and look it up in `recipes`.
+    pub fn resolve(&self, name: &str) -> Result<&Recipe> {
+        let target = self.aliases.get(name).map(String::as_str).unwrap_or(name);
+        self.recipes
+            .get(target)
+            .ok_or_else(|| Error::Validation(format!("no recipe named '{}' (or alias '{}')", target, name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(find: &str, with: &str) -> Recipe {
+        Recipe {
+            find: find.into(),
+            with: with.into(),
+            regex: false,
+            ignore_case: false,
+            smart_case: false,
+            word_regexp: false,
+            multiline: false,
+            dot_matches_newline: false,
+            no_unicode: false,
+            limit: None,
+            range: None,
+            expand: false,
+        }
+    }
+
+    #[test]
+    fn resolve_finds_recipe_directly_by_name() {
+        let mut recipes = BTreeMap::new();
+        recipes.insert("fix-imports".to_string(), recipe("foo", "bar"));
+        let file = RecipeFile { aliases: BTreeMap::new(), recipes };
+
+        let found = file.resolve("fix-imports").unwrap();
+        assert_eq!(found.find, "foo");
+    }
+
+    #[test]
+    fn resolve_follows_alias_one_hop() {
+        let mut recipes = BTreeMap::new();
+        recipes.insert("fix-imports".to_string(), recipe("foo", "bar"));
+        let mut aliases = BTreeMap::new();
+        aliases.insert("fi".to_string(), "fix-imports".to_string());
+        let file = RecipeFile { aliases, recipes };
+
+        let found = file.resolve("fi").unwrap();
+        assert_eq!(found.find, "foo");
+    }
+
+    #[test]
+    fn resolve_unknown_name_is_validation_error() {
+        let file = RecipeFile::default();
+        let err = file.resolve("does-not-exist").unwrap_err();
+        match err {
+            Error::Validation(msg) => assert!(msg.contains("does-not-exist")),
+            other => panic!("expected Error::Validation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn resolve_alias_to_missing_recipe_names_the_recipe_and_the_alias() {
+        let mut aliases = BTreeMap::new();
+        aliases.insert("fi".to_string(), "fix-imports".to_string());
+        let file = RecipeFile { aliases, recipes: BTreeMap::new() };
+
+        let err = file.resolve("fi").unwrap_err();
+        match err {
+            Error::Validation(msg) => {
+                assert!(msg.contains("fix-imports"));
+                assert!(msg.contains("fi"));
+            }
+            other => panic!("expected Error::Validation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn load_parses_recipes_and_aliases_from_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recipes.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [aliases]
+            fi = "fix-imports"
+
+            [recipes.fix-imports]
+            find = "foo"
+            with = "bar"
+            regex = true
+            "#,
+        )
+        .unwrap();
+
+        let file = RecipeFile::load(&path).unwrap();
+        let found = file.resolve("fi").unwrap();
+        assert_eq!(found.find, "foo");
+        assert_eq!(found.with, "bar");
+        assert!(found.regex);
+    }
+
+    #[test]
+    fn load_malformed_toml_is_validation_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recipes.toml");
+        std::fs::write(&path, "this is not [valid toml").unwrap();
+
+        let err = RecipeFile::load(&path).unwrap_err();
+        match err {
+            Error::Validation(msg) => assert!(msg.contains("recipes.toml")),
+            other => panic!("expected Error::Validation, got {other:?}"),
+        }
+    }
+}
@@ -1,24 +1,69 @@
 use crate::error::{Error, Result};
-use crate::model::{LineRange, ReplacementRange};
+use crate::events::{EditPayload, EditRecord};
+use crate::model::{LineRange, ReplacementRange, ValidationMode};
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
 use regex::bytes::{Regex, RegexBuilder, NoExpand};
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::{Read, Write};
 use memchr::memmem;
 
+mod prefilter;
+mod smart_case;
 mod validate;
+pub use validate::CaptureRef;
+
+/// A mandatory literal substring of a `Matcher::Regex` pattern, recovered by
+/// [`prefilter::required_literal`], used to jump straight to candidate
+/// offsets via `memmem` instead of invoking the regex engine across the
+/// whole text. Only ever built for a single compiled pattern — a multi-rule
+/// alternation (`Matcher::MultiRegex`) rarely has one literal common to
+/// every rule, so [`Replacer::new_multi`] doesn't bother.
+struct PrefilterFinder {
+    finder: memmem::Finder<'static>,
+}
 
 enum Matcher {
     Regex(Regex),
     Literal(Vec<u8>),
+    /// One left-to-right scan over many literal needles at once, built via
+    /// [`Replacer::new_multi`]. Matches are leftmost-longest so overlapping
+    /// needles (`"foo"` and `"foobar"`) don't race based on rule order.
+    MultiLiteral(AhoCorasick),
+    /// One left-to-right scan over many regex rules at once, built via
+    /// [`Replacer::new_multi`] as a single `(r0)|(r1)|...` alternation so
+    /// rules never compete for the same match the way N sequential passes
+    /// would.
+    MultiRegex(Regex),
 }
 
 pub struct Replacer {
     matcher: Matcher,
     replacement: Vec<u8>,
+    /// Per-rule replacement text, indexed by pattern ID (`MultiLiteral`) or
+    /// by rule position (`MultiRegex`, via `rule_groups`). Empty for the
+    /// single-rule matchers, which just use `replacement` directly.
+    replacements: Vec<Vec<u8>>,
+    /// `MultiRegex` only: each rule's own top-level capture group index
+    /// within the combined alternation, in rule order. A rule's pattern may
+    /// contain any number of its own nested groups, so these indices aren't
+    /// contiguous — they're recovered from the `__txed_rule_N` group names
+    /// `new_multi` gives each rule's wrapper group.
+    rule_groups: Vec<usize>,
     max_replacements: usize,
-    range: Option<LineRange>,
+    ranges: Option<Vec<LineRange>>,
     allowed_ranges: Option<Vec<ReplacementRange>>,
     expand: bool,
-    // TODO: track validation mode (strict, warn, none)
+    /// Set only for a plain `Matcher::Regex` whose pattern has a required
+    /// literal worth memmem-searching for. See [`PrefilterFinder`].
+    prefilter: Option<PrefilterFinder>,
+    /// Set only for a `Matcher::Literal` built with `--word-regexp`; the
+    /// inner bool selects Unicode-aware vs. ASCII-only word-character
+    /// classification for [`literal_match_is_word_bounded`]. `None` means
+    /// no boundary check applies (word boundaries are otherwise handled by
+    /// wrapping the pattern in `\b...\b` and going through `Matcher::Regex`
+    /// instead — see `use_literal_matcher` in [`Self::new`]).
+    literal_word_boundary: Option<bool>,
 }
 
 impl Replacer {
@@ -35,39 +80,39 @@ impl Replacer {
         single_line: bool,
         dot_matches_newline: bool,
         no_unicode: bool,
-        _crlf: bool,
+        crlf: bool,
         max_replacements: usize,
-        range: Option<LineRange>,
+        ranges: Option<Vec<LineRange>>,
         allowed_ranges: Option<Vec<ReplacementRange>>,
         expand: bool,
+        validation_mode: ValidationMode,
     ) -> Result<Self> {
-        // 1. Validate replacement pattern for capture group references
-        if !expand {
-             // If we don't expand, we don't strictly need to validate $1, but it might be nice to warn?
-             // Actually, the original code called validate::validate_replacement which checks for $N validity.
-             // If expand is false, $1 is literal "$1", so valid.
-             // If expand is true, $1 must be valid.
-             // We should probably only validate if expand is true.
-        } else {
-            validate::validate_replacement(replacement)?;
-        }
-
         // Determine if we can use efficient literal matcher
         // We can use Literal matcher only if:
         // - fixed_strings is requested (or pattern is literal) -> handled by caller passing fixed_strings
-        // - NO regex flags that affect matching (ignore_case, smart_case, word_regexp, multiline etc)
+        // - NO regex flags that affect matching (ignore_case, smart_case, multiline etc)
         // - NO expansion (if expand is true, we need regex engine to resolve captures, UNLESS replacement has no $ signs)
         // Note: multiline/dot_matches_newline don't apply to literal strings unless we search line by line?
         // memmem works on bytes, ignores lines.
-        // word_regexp requires checking boundaries -> complex for memmem, use regex.
+        // word_regexp is fine too, as long as the needle's own edges are word
+        // characters: literal_match_is_word_bounded can then check memmem's
+        // candidates manually instead of falling back to the regex engine.
         // ignore_case -> complex for memmem, use regex.
-        
-        let use_literal_matcher = fixed_strings 
-            && !ignore_case 
-            && !smart_case 
-            && !word_regexp
+
+        let use_literal_matcher = fixed_strings
+            && !ignore_case
+            && !smart_case
+            && (!word_regexp || literal_has_word_edges(pattern, !no_unicode))
             && (!expand || !replacement.contains("$")); // If expansion requested but no $ involved, literal is fine
 
+        let literal_word_boundary = if use_literal_matcher && word_regexp {
+            Some(!no_unicode)
+        } else {
+            None
+        };
+
+        let mut prefilter = None;
+
         let matcher = if use_literal_matcher {
             Matcher::Literal(pattern.as_bytes().to_vec())
         } else {
@@ -91,19 +136,55 @@ impl Replacer {
             if ignore_case {
                 builder.case_insensitive(true);
             } else if smart_case {
-                let is_lowercase = pattern.chars().all(|c| !c.is_uppercase());
-                builder.case_insensitive(is_lowercase);
+                builder.case_insensitive(smart_case::is_lowercase_literal(&pattern));
             } else {
                 builder.case_insensitive(false);
             }
 
             builder.multi_line(multiline && !single_line);
             builder.dot_matches_new_line(dot_matches_newline);
-            
+            // CRLF mode makes `$` (in multi-line mode) match before `\r\n`
+            // rather than between the `\r` and the `\n`, and makes `.`
+            // exclude `\r` the same way it already excludes `\n` — without
+            // it, a CRLF file's `\r` silently becomes part of "the line" as
+            // far as these anchors are concerned.
+            builder.crlf(crlf);
+
             let regex = builder.build().map_err(Error::Regex)?;
+
+            // memmem is case-sensitive, so it can't stand in for a
+            // case-insensitive regex.
+            if !dot_matches_newline && !ignore_case && !(smart_case && smart_case::is_lowercase_literal(&pattern)) {
+                prefilter = prefilter::required_literal(&pattern).map(|needle| PrefilterFinder {
+                    finder: memmem::Finder::new(&needle).into_owned(),
+                });
+            }
+
             Matcher::Regex(regex)
         };
 
+        // Validate capture group references in the replacement text, if we're
+        // going to expand them. Only a compiled regex has capture metadata;
+        // the literal matcher has no groups at all, so $1/${name} there are
+        // always unknown.
+        if expand {
+            let known_names: Option<HashSet<&str>> = match &matcher {
+                Matcher::Regex(re) => Some(re.capture_names().flatten().collect()),
+                Matcher::Literal(_) => None,
+            };
+            let captures_len = match &matcher {
+                Matcher::Regex(re) => re.captures_len(),
+                Matcher::Literal(_) => 0,
+            };
+            validate::validate_replacement(replacement, validation_mode, |cap_ref| match cap_ref {
+                CaptureRef::Numbered(n) => n < captures_len,
+                CaptureRef::Named(name) => known_names
+                    .as_ref()
+                    .map(|names| names.contains(name))
+                    .unwrap_or(false),
+            })?;
+        }
+
         let replacement_bytes = replacement.as_bytes().to_vec();
 
         let mut allowed_ranges = allowed_ranges;
@@ -114,50 +195,205 @@ impl Replacer {
         Ok(Self {
             matcher,
             replacement: replacement_bytes,
+            replacements: Vec::new(),
+            rule_groups: Vec::new(),
+            max_replacements,
+            ranges,
+            allowed_ranges,
+            expand,
+            prefilter,
+            literal_word_boundary,
+        })
+    }
+
+    /// Like [`Self::new`], but applies a whole set of find/replace rules in
+    /// one left-to-right scan instead of requiring one pass per rule. All
+    /// rules share the same matching flags (case handling, word boundaries,
+    /// multi-line mode, `expand`) the way a single `--find`/`--replace`
+    /// pair would — there's no per-rule override, only per-rule pattern and
+    /// replacement text.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_multi(
+        rules: &[(String, String)],
+        fixed_strings: bool,
+        ignore_case: bool,
+        smart_case: bool,
+        word_regexp: bool,
+        multiline: bool,
+        single_line: bool,
+        dot_matches_newline: bool,
+        no_unicode: bool,
+        max_replacements: usize,
+        ranges: Option<Vec<LineRange>>,
+        allowed_ranges: Option<Vec<ReplacementRange>>,
+        expand: bool,
+        validation_mode: ValidationMode,
+    ) -> Result<Self> {
+        let use_literal_matcher = fixed_strings
+            && !ignore_case
+            && !smart_case
+            && !word_regexp
+            && (!expand || rules.iter().all(|(_, with)| !with.contains('$')));
+
+        let replacements: Vec<Vec<u8>> = rules.iter().map(|(_, with)| with.as_bytes().to_vec()).collect();
+
+        let mut rule_groups = Vec::new();
+
+        let matcher = if use_literal_matcher {
+            let patterns: Vec<&[u8]> = rules.iter().map(|(find, _)| find.as_bytes()).collect();
+            let ac = AhoCorasickBuilder::new()
+                .match_kind(MatchKind::LeftmostLongest)
+                .ascii_case_insensitive(false)
+                .build(&patterns)
+                .map_err(|e| Error::Validation(format!("invalid multi-pattern rule set: {}", e)))?;
+            Matcher::MultiLiteral(ac)
+        } else {
+            // Each rule gets its own uniquely-named wrapper group
+            // (`__txed_rule_N`) so a match can be traced back to the rule
+            // that produced it, regardless of how many of its own nested
+            // capture groups that rule's pattern contains.
+            let alternation = rules
+                .iter()
+                .enumerate()
+                .map(|(i, (find, _))| {
+                    let pattern = if fixed_strings {
+                        regex::escape(find)
+                    } else {
+                        find.clone()
+                    };
+                    let body = if word_regexp {
+                        format!(r"\b{}\b", pattern)
+                    } else {
+                        pattern
+                    };
+                    format!("(?P<__txed_rule_{}>{})", i, body)
+                })
+                .collect::<Vec<_>>()
+                .join("|");
+
+            let mut builder = RegexBuilder::new(&alternation);
+            builder.unicode(!no_unicode);
+
+            if ignore_case {
+                builder.case_insensitive(true);
+            } else if smart_case {
+                builder.case_insensitive(smart_case::is_lowercase_literal(&alternation));
+            } else {
+                builder.case_insensitive(false);
+            }
+
+            builder.multi_line(multiline && !single_line);
+            builder.dot_matches_new_line(dot_matches_newline);
+
+            let regex = builder.build().map_err(Error::Regex)?;
+
+            rule_groups = vec![0usize; rules.len()];
+            for (group_idx, name) in regex.capture_names().enumerate() {
+                if let Some(rule_idx) = name.and_then(|n| n.strip_prefix("__txed_rule_")).and_then(|n| n.parse::<usize>().ok()) {
+                    rule_groups[rule_idx] = group_idx;
+                }
+            }
+
+            Matcher::MultiRegex(regex)
+        };
+
+        if expand {
+            if let Matcher::MultiRegex(re) = &matcher {
+                let known_names: HashSet<&str> = re.capture_names().flatten().collect();
+                let captures_len = re.captures_len();
+                for (_, with) in rules {
+                    validate::validate_replacement(with, validation_mode, |cap_ref| match cap_ref {
+                        CaptureRef::Numbered(n) => n < captures_len,
+                        CaptureRef::Named(name) => known_names.contains(name),
+                    })?;
+                }
+            }
+        }
+
+        Ok(Self {
+            matcher,
+            replacement: Vec::new(),
+            replacements,
+            rule_groups,
             max_replacements,
-            range,
+            ranges,
             allowed_ranges,
             expand,
+            prefilter: None,
+            literal_word_boundary: None,
         })
     }
 
+    /// Length of the pattern, for a literal matcher — the minimal overlap
+    /// window a streaming caller needs to hold back across a chunk boundary,
+    /// since a literal match can't be longer than the needle itself. `None`
+    /// for a regex matcher, whose longest possible match is unbounded.
+    pub fn literal_len(&self) -> Option<usize> {
+        match &self.matcher {
+            Matcher::Literal(needle) => Some(needle.len()),
+            Matcher::Regex(_) | Matcher::MultiLiteral(_) | Matcher::MultiRegex(_) => None,
+        }
+    }
+
     /// Count the number of matches in the given text.
     pub fn count_matches(&self, text: &[u8]) -> usize {
-        if self.range.is_some() || self.allowed_ranges.is_some() {
-             // If range filters are set, we must iterate to check bounds
+        if self.ranges.is_some() || self.allowed_ranges.is_some() || self.prefilter.is_some() {
+             // If range filters (or a prefilter, which needs line starts
+             // too) are in play, we must iterate to check bounds.
              let mut count = 0;
-             let line_offsets = if self.range.is_some() {
-                 Some(build_line_offsets(text))
+             let line_index = if self.ranges.is_some() || self.prefilter.is_some() {
+                 let offsets = build_line_offsets(text);
+                 let total = total_line_count(text.len(), &offsets);
+                 Some((offsets, total))
              } else {
                  None
              };
-             
+
              let mut allowed_cursor = 0;
 
              match &self.matcher {
                 Matcher::Regex(re) => {
-                    for m in re.find_iter(text) {
-                        if let Some(range) = &self.range {
-                            if !is_in_range(m.start(), range, line_offsets.as_ref().unwrap()) {
-                                continue;
+                    let mut visit = |whole: regex::bytes::Match| {
+                        if let Some(ranges) = &self.ranges {
+                            let (offsets, total) = line_index.as_ref().unwrap();
+                            if !is_in_ranges(whole.start(), ranges, offsets, *total) {
+                                return;
                             }
                         }
                         if let Some(allowed) = &self.allowed_ranges {
-                            if !check_allowed_range_optimized(m.start(), m.end(), allowed, &mut allowed_cursor) {
-                                continue;
+                            if !check_allowed_range_optimized(whole.start(), whole.end(), allowed, &mut allowed_cursor) {
+                                return;
                             }
                         }
                         count += 1;
+                    };
+
+                    if let Some(prefilter) = &self.prefilter {
+                        let (offsets, _) = line_index.as_ref().unwrap();
+                        each_prefiltered_match(re, text, prefilter, offsets, |caps| {
+                            visit(caps.get(0).unwrap());
+                            true
+                        });
+                    } else {
+                        for m in re.find_iter(text) {
+                            visit(m);
+                        }
                     }
                 },
                 Matcher::Literal(needle) => {
                      for m in memmem::find_iter(text, needle) {
-                        if let Some(range) = &self.range {
-                            if !is_in_range(m, range, line_offsets.as_ref().unwrap()) {
+                        let end = m + needle.len();
+                        if let Some(unicode) = self.literal_word_boundary {
+                            if !literal_match_is_word_bounded(text, m, end, unicode) {
+                                continue;
+                            }
+                        }
+                        if let Some(ranges) = &self.ranges {
+                            let (offsets, total) = line_index.as_ref().unwrap();
+                            if !is_in_ranges(m, ranges, offsets, *total) {
                                 continue;
                             }
                         }
-                        let end = m + needle.len();
                         if let Some(allowed) = &self.allowed_ranges {
                             if !check_allowed_range_optimized(m, end, allowed, &mut allowed_cursor) {
                                 continue;
@@ -166,20 +402,66 @@ impl Replacer {
                         count += 1;
                      }
                 }
+                Matcher::MultiLiteral(ac) => {
+                    for m in ac.find_iter(text) {
+                        if let Some(ranges) = &self.ranges {
+                            let (offsets, total) = line_index.as_ref().unwrap();
+                            if !is_in_ranges(m.start(), ranges, offsets, *total) {
+                                continue;
+                            }
+                        }
+                        if let Some(allowed) = &self.allowed_ranges {
+                            if !check_allowed_range_optimized(m.start(), m.end(), allowed, &mut allowed_cursor) {
+                                continue;
+                            }
+                        }
+                        count += 1;
+                    }
+                }
+                Matcher::MultiRegex(re) => {
+                    for m in re.find_iter(text) {
+                        if let Some(ranges) = &self.ranges {
+                            let (offsets, total) = line_index.as_ref().unwrap();
+                            if !is_in_ranges(m.start(), ranges, offsets, *total) {
+                                continue;
+                            }
+                        }
+                        if let Some(allowed) = &self.allowed_ranges {
+                            if !check_allowed_range_optimized(m.start(), m.end(), allowed, &mut allowed_cursor) {
+                                continue;
+                            }
+                        }
+                        count += 1;
+                    }
+                }
              }
              return count;
         }
 
         match &self.matcher {
             Matcher::Regex(re) => re.find_iter(text).count(),
-            Matcher::Literal(needle) => memmem::find_iter(text, needle).count(),
+            Matcher::Literal(needle) => match self.literal_word_boundary {
+                Some(unicode) => memmem::find_iter(text, needle)
+                    .filter(|&m| literal_match_is_word_bounded(text, m, m + needle.len(), unicode))
+                    .count(),
+                None => memmem::find_iter(text, needle).count(),
+            },
+            Matcher::MultiLiteral(ac) => ac.find_iter(text).count(),
+            Matcher::MultiRegex(re) => re.find_iter(text).count(),
         }
     }
 
     /// Replace matches in text and return the replaced text along with the number of replacements performed.
     pub fn replace_with_count<'a>(&self, text: &'a [u8]) -> (Cow<'a, [u8]>, usize) {
-        // If no range filter and regex replacement, use regex methods for speed
-        if self.range.is_none() && self.allowed_ranges.is_none() {
+        // If no range filter and regex replacement, use regex methods for speed.
+        // A prefilter steers clear of this path even with no ranges set,
+        // since it needs the per-match loop below to drive `captures_at`
+        // itself instead of handing the whole text to `replace_all`. So
+        // does a case-fold directive (`\U`/`\L`/`\u`/`\l`/`\E`) in the
+        // replacement text, since `replace_all`/`replacen` only know how to
+        // expand `$group` references, not our own fold state machine.
+        let use_case_fold = self.expand && replacement_has_case_fold(&self.replacement);
+        if !use_case_fold && self.ranges.is_none() && self.allowed_ranges.is_none() && self.prefilter.is_none() {
             if let Matcher::Regex(re) = &self.matcher {
                  let matches_count = self.count_matches(text);
                  if matches_count == 0 {
@@ -216,47 +498,69 @@ impl Replacer {
         let mut new_data = Vec::with_capacity(text.len());
         let mut last_match_end = 0;
         let mut count = 0;
-        
-        let line_offsets = if self.range.is_some() {
-            Some(build_line_offsets(text))
+
+        let line_index = if self.ranges.is_some() || self.prefilter.is_some() {
+            let offsets = build_line_offsets(text);
+            let total = total_line_count(text.len(), &offsets);
+            Some((offsets, total))
         } else {
             None
         };
-        
+
         let mut allowed_cursor = 0;
 
+        // self.replacement is always valid UTF-8: it's built from the
+        // `replacement: &str` argument to `Replacer::new` and never
+        // mutated. Computed once, outside the per-match loop below.
+        let case_fold_template = use_case_fold.then(|| std::str::from_utf8(&self.replacement).unwrap());
+
         match &self.matcher {
             Matcher::Regex(re) => {
-                 for m in re.captures_iter(text) {
+                 let mut visit = |m: regex::bytes::Captures| -> bool {
                     if self.max_replacements > 0 && count >= self.max_replacements {
-                        break;
+                        return false;
                     }
-                    
+
                     let match_start = m.get(0).unwrap().start();
                     let match_end = m.get(0).unwrap().end();
-                    
-                    if let Some(range) = &self.range {
-                        if !is_in_range(match_start, range, line_offsets.as_ref().unwrap()) {
-                            continue;
+
+                    if let Some(ranges) = &self.ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(match_start, ranges, offsets, *total) {
+                            return true;
                         }
                     }
 
                     if let Some(allowed) = &self.allowed_ranges {
                         if !check_allowed_range_optimized(match_start, match_end, allowed, &mut allowed_cursor) {
-                            continue;
+                            return true;
                         }
                     }
 
                     new_data.extend_from_slice(&text[last_match_end..match_start]);
-                    
-                    if self.expand {
+
+                    if let Some(template) = case_fold_template {
+                        expand_with_case_folding(template, &m, &mut new_data);
+                    } else if self.expand {
                         m.expand(&self.replacement, &mut new_data);
                     } else {
                         new_data.extend_from_slice(&self.replacement);
                     }
-                    
+
                     last_match_end = match_end;
                     count += 1;
+                    true
+                 };
+
+                 if let Some(prefilter) = &self.prefilter {
+                     let (offsets, _) = line_index.as_ref().unwrap();
+                     each_prefiltered_match(re, text, prefilter, offsets, visit);
+                 } else {
+                     for m in re.captures_iter(text) {
+                         if !visit(m) {
+                             break;
+                         }
+                     }
                  }
             },
             Matcher::Literal(needle) => {
@@ -264,14 +568,21 @@ impl Replacer {
                     if self.max_replacements > 0 && count >= self.max_replacements {
                         break;
                     }
-                    
-                    if let Some(range) = &self.range {
-                         if !is_in_range(m, range, line_offsets.as_ref().unwrap()) {
+
+                    let end = m + needle.len();
+                    if let Some(unicode) = self.literal_word_boundary {
+                        if !literal_match_is_word_bounded(text, m, end, unicode) {
+                            continue;
+                        }
+                    }
+
+                    if let Some(ranges) = &self.ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(m, ranges, offsets, *total) {
                             continue;
                         }
                     }
 
-                    let end = m + needle.len();
                     if let Some(allowed) = &self.allowed_ranges {
                         if !check_allowed_range_optimized(m, end, allowed, &mut allowed_cursor) {
                             continue;
@@ -284,6 +595,66 @@ impl Replacer {
                     count += 1;
                 }
             }
+            Matcher::MultiLiteral(ac) => {
+                for m in ac.find_iter(text) {
+                    if self.max_replacements > 0 && count >= self.max_replacements {
+                        break;
+                    }
+
+                    if let Some(ranges) = &self.ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(m.start(), ranges, offsets, *total) {
+                            continue;
+                        }
+                    }
+
+                    if let Some(allowed) = &self.allowed_ranges {
+                        if !check_allowed_range_optimized(m.start(), m.end(), allowed, &mut allowed_cursor) {
+                            continue;
+                        }
+                    }
+
+                    new_data.extend_from_slice(&text[last_match_end..m.start()]);
+                    new_data.extend_from_slice(&self.replacements[m.pattern().as_usize()]);
+                    last_match_end = m.end();
+                    count += 1;
+                }
+            }
+            Matcher::MultiRegex(re) => {
+                for m in re.captures_iter(text) {
+                    if self.max_replacements > 0 && count >= self.max_replacements {
+                        break;
+                    }
+
+                    let match_start = m.get(0).unwrap().start();
+                    let match_end = m.get(0).unwrap().end();
+
+                    if let Some(ranges) = &self.ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(match_start, ranges, offsets, *total) {
+                            continue;
+                        }
+                    }
+
+                    if let Some(allowed) = &self.allowed_ranges {
+                        if !check_allowed_range_optimized(match_start, match_end, allowed, &mut allowed_cursor) {
+                            continue;
+                        }
+                    }
+
+                    new_data.extend_from_slice(&text[last_match_end..match_start]);
+
+                    let rule = multi_regex_rule_index(&m, &self.rule_groups).expect("alternation always matches exactly one rule group");
+                    if self.expand {
+                        m.expand(&self.replacements[rule], &mut new_data);
+                    } else {
+                        new_data.extend_from_slice(&self.replacements[rule]);
+                    }
+
+                    last_match_end = match_end;
+                    count += 1;
+                }
+            }
         }
 
         if count == 0 {
@@ -293,85 +664,833 @@ impl Replacer {
         new_data.extend_from_slice(&text[last_match_end..]);
         (Cow::Owned(new_data), count)
     }
-}
 
-/// Precompute line start offsets.
-/// Returns a vector where index i is the byte offset of the start of line i+1.
-fn build_line_offsets(text: &[u8]) -> Vec<usize> {
-    let mut offsets = Vec::new();
-    offsets.push(0);
-    for (i, &b) in text.iter().enumerate() {
-        if b == b'\n' {
-            offsets.push(i + 1);
-        }
-    }
-    offsets
-}
+    /// Like [`Self::replace_with_count`], but also returns one [`EditRecord`]
+    /// per applied replacement with its byte span, line number, and matched
+    /// and substituted text. Always takes the per-match loop, since the
+    /// batched regex fast path in `replace_with_count` has no way to report
+    /// individual match spans.
+    pub fn replace_with_edits<'a>(&self, text: &'a [u8]) -> (Cow<'a, [u8]>, Vec<EditRecord>) {
+        let mut new_data = Vec::with_capacity(text.len());
+        let mut last_match_end = 0;
+        let mut edits = Vec::new();
 
-/// Check if a byte offset is within the allowed line range.
-fn is_in_range(byte_offset: usize, range: &LineRange, line_offsets: &[usize]) -> bool {
-    // Find line number for byte_offset using binary search
-    // line_offsets[i] <= byte_offset < line_offsets[i+1]
-    
-    let line_idx = match line_offsets.binary_search(&byte_offset) {
-        Ok(i) => i, // Exact match means start of line i+1 (0-based idx i)
-        Err(i) => i - 1, // Insertion point is i, so it belongs to line i-1 (0-based)
-    };
-    
-    let line_number = line_idx + 1; // 1-based line number
+        let line_offsets = build_line_offsets(text);
+        let total_lines = total_line_count(text.len(), &line_offsets);
+        let mut allowed_cursor = 0;
+        let case_fold_template = (self.expand && replacement_has_case_fold(&self.replacement))
+            .then(|| std::str::from_utf8(&self.replacement).unwrap());
 
-    if line_number < range.start {
-        return false;
-    }
-    if let Some(end) = range.end {
-        if line_number > end {
-            return false;
-        }
-    }
-    true
-}
+        match &self.matcher {
+            Matcher::Regex(re) => {
+                let mut visit = |m: regex::bytes::Captures| -> bool {
+                    if self.max_replacements > 0 && edits.len() >= self.max_replacements {
+                        return false;
+                    }
 
-/// Optimized check for allowed ranges using a cursor.
-/// Assumes matches are processed in order and allowed ranges are sorted by start.
-fn check_allowed_range_optimized(start: usize, end: usize, allowed: &[ReplacementRange], cursor: &mut usize) -> bool {
-    // Fast forward cursor: skip ranges that end before the match starts.
-    while *cursor < allowed.len() && allowed[*cursor].end <= start {
-        *cursor += 1;
-    }
+                    let whole = m.get(0).unwrap();
+                    let match_start = whole.start();
+                    let match_end = whole.end();
 
-    if *cursor >= allowed.len() {
-        return false;
-    }
+                    if let Some(ranges) = &self.ranges {
+                        if !is_in_ranges(match_start, ranges, &line_offsets, total_lines) {
+                            return true;
+                        }
+                    }
+                    if let Some(allowed) = &self.allowed_ranges {
+                        if !check_allowed_range_optimized(match_start, match_end, allowed, &mut allowed_cursor) {
+                            return true;
+                        }
+                    }
 
-    let r = &allowed[*cursor];
-    // Check intersection: start < r.end && r.start < end
-    // We know r.end > start (from loop).
-    // So we just need r.start < end.
-    if r.start < end {
-        return true;
-    }
+                    new_data.extend_from_slice(&text[last_match_end..match_start]);
 
-    // No overlap.
-    // Since allowed ranges are sorted by start, any subsequent range r' will have r'.start >= r.start >= end.
-    // So no future overlap is possible for this match.
-    false
-}
+                    let substituted_start = new_data.len();
+                    if let Some(template) = case_fold_template {
+                        expand_with_case_folding(template, &m, &mut new_data);
+                    } else if self.expand {
+                        m.expand(&self.replacement, &mut new_data);
+                    } else {
+                        new_data.extend_from_slice(&self.replacement);
+                    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+                    edits.push(EditRecord {
+                        start: match_start,
+                        end: match_end,
+                        line_number: line_number_for(match_start, &line_offsets),
+                        matched: EditPayload::from_bytes(whole.as_bytes()),
+                        replacement: EditPayload::from_bytes(&new_data[substituted_start..]),
+                    });
 
-    #[test]
-    fn test_basic_replacement() {
-        let replacer = Replacer::new(
-            "foo",
-            "bar",
-            false, // fixed_strings (treated as regex since false? No, depends on caller logic. Here false means regex? Wait. engine.rs sets it. 
-                   // new() takes fixed_strings directly. If false, it tries regex parse. "foo" is valid regex.)
-            false, // ignore_case
-            false, // smart_case
-            true,  // case_sensitive
-            false, // word_regexp
+                    last_match_end = match_end;
+                    true
+                };
+
+                if let Some(prefilter) = &self.prefilter {
+                    each_prefiltered_match(re, text, prefilter, &line_offsets, visit);
+                } else {
+                    for m in re.captures_iter(text) {
+                        if !visit(m) {
+                            break;
+                        }
+                    }
+                }
+            }
+            Matcher::Literal(needle) => {
+                for m in memmem::find_iter(text, needle) {
+                    if self.max_replacements > 0 && edits.len() >= self.max_replacements {
+                        break;
+                    }
+
+                    let end = m + needle.len();
+                    if let Some(unicode) = self.literal_word_boundary {
+                        if !literal_match_is_word_bounded(text, m, end, unicode) {
+                            continue;
+                        }
+                    }
+
+                    if let Some(ranges) = &self.ranges {
+                        if !is_in_ranges(m, ranges, &line_offsets, total_lines) {
+                            continue;
+                        }
+                    }
+
+                    if let Some(allowed) = &self.allowed_ranges {
+                        if !check_allowed_range_optimized(m, end, allowed, &mut allowed_cursor) {
+                            continue;
+                        }
+                    }
+
+                    new_data.extend_from_slice(&text[last_match_end..m]);
+                    new_data.extend_from_slice(&self.replacement);
+
+                    edits.push(EditRecord {
+                        start: m,
+                        end,
+                        line_number: line_number_for(m, &line_offsets),
+                        matched: EditPayload::from_bytes(needle),
+                        replacement: EditPayload::from_bytes(&self.replacement),
+                    });
+
+                    last_match_end = end;
+                }
+            }
+            Matcher::MultiLiteral(ac) => {
+                for m in ac.find_iter(text) {
+                    if self.max_replacements > 0 && edits.len() >= self.max_replacements {
+                        break;
+                    }
+
+                    if let Some(ranges) = &self.ranges {
+                        if !is_in_ranges(m.start(), ranges, &line_offsets, total_lines) {
+                            continue;
+                        }
+                    }
+                    if let Some(allowed) = &self.allowed_ranges {
+                        if !check_allowed_range_optimized(m.start(), m.end(), allowed, &mut allowed_cursor) {
+                            continue;
+                        }
+                    }
+
+                    let replacement = &self.replacements[m.pattern().as_usize()];
+                    new_data.extend_from_slice(&text[last_match_end..m.start()]);
+                    new_data.extend_from_slice(replacement);
+
+                    edits.push(EditRecord {
+                        start: m.start(),
+                        end: m.end(),
+                        line_number: line_number_for(m.start(), &line_offsets),
+                        matched: EditPayload::from_bytes(&text[m.start()..m.end()]),
+                        replacement: EditPayload::from_bytes(replacement),
+                    });
+
+                    last_match_end = m.end();
+                }
+            }
+            Matcher::MultiRegex(re) => {
+                for m in re.captures_iter(text) {
+                    if self.max_replacements > 0 && edits.len() >= self.max_replacements {
+                        break;
+                    }
+
+                    let whole = m.get(0).unwrap();
+                    let match_start = whole.start();
+                    let match_end = whole.end();
+
+                    if let Some(ranges) = &self.ranges {
+                        if !is_in_ranges(match_start, ranges, &line_offsets, total_lines) {
+                            continue;
+                        }
+                    }
+                    if let Some(allowed) = &self.allowed_ranges {
+                        if !check_allowed_range_optimized(match_start, match_end, allowed, &mut allowed_cursor) {
+                            continue;
+                        }
+                    }
+
+                    new_data.extend_from_slice(&text[last_match_end..match_start]);
+
+                    let substituted_start = new_data.len();
+                    let rule = multi_regex_rule_index(&m, &self.rule_groups).expect("alternation always matches exactly one rule group");
+                    if self.expand {
+                        m.expand(&self.replacements[rule], &mut new_data);
+                    } else {
+                        new_data.extend_from_slice(&self.replacements[rule]);
+                    }
+
+                    edits.push(EditRecord {
+                        start: match_start,
+                        end: match_end,
+                        line_number: line_number_for(match_start, &line_offsets),
+                        matched: EditPayload::from_bytes(whole.as_bytes()),
+                        replacement: EditPayload::from_bytes(&new_data[substituted_start..]),
+                    });
+
+                    last_match_end = match_end;
+                }
+            }
+        }
+
+        if edits.is_empty() {
+            return (Cow::Borrowed(text), Vec::new());
+        }
+
+        new_data.extend_from_slice(&text[last_match_end..]);
+        (Cow::Owned(new_data), edits)
+    }
+
+    /// Apply this replacer to `src` and write the transformed output to
+    /// `dst` incrementally, without ever materializing the whole input in
+    /// memory the way every other method on this type does — unworkable
+    /// for multi-gigabyte files. See [`crate::engine::execute_file_streaming`]
+    /// for `--stream`'s on-disk-file caller.
+    ///
+    /// Input is read in `STREAM_CHUNK_SIZE` buffers. A [`Matcher::Literal`]'s
+    /// longest possible match is its own needle length, so only that many
+    /// trailing bytes need to be held back across a read, mirroring
+    /// [`crate::input::stream_stdin_text`]; an occurrence that starts before
+    /// that tail but runs into it pulls the cut back to its own start. Every
+    /// other matcher's longest match is unbounded, so instead only complete
+    /// lines are flushed each iteration — the trailing partial line is held
+    /// back and prepended to the next read. A literal match is therefore
+    /// never split across a chunk boundary, and neither is a regex match
+    /// that stays within one line; a regex match that spans a newline can be.
+    ///
+    /// `self.ranges` is translated into each chunk's own (1-based, relative)
+    /// line numbering via a running line counter (see
+    /// `shift_ranges_for_chunk`), instead of computing [`build_line_offsets`]
+    /// over the whole input up front. A negative (count-from-the-end) bound
+    /// needs the file's total line count to resolve, which streaming never
+    /// materializes, so those are rejected here rather than silently
+    /// misinterpreted. `self.allowed_ranges` (absolute byte offsets from a
+    /// plan file) has the same problem and a narrower use case, so it's
+    /// rejected outright instead of partially supported.
+    pub fn replace_stream<R: Read, W: Write>(&self, mut src: R, mut dst: W) -> Result<usize> {
+        if let Some(ranges) = &self.ranges {
+            let has_negative_bound = ranges
+                .iter()
+                .any(|r| r.start < 0 || r.end.map(|e| e < 0).unwrap_or(false));
+            if has_negative_bound {
+                return Err(Error::Validation(
+                    "a negative (count-from-the-end) --range bound requires the file's total \
+                     line count up front, which --stream never materializes"
+                        .into(),
+                ));
+            }
+        }
+        if self.allowed_ranges.is_some() {
+            return Err(Error::Validation(
+                "this replacement mode isn't supported together with --stream".into(),
+            ));
+        }
+
+        let byte_window = self.literal_len();
+
+        let mut held: Vec<u8> = Vec::new();
+        let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut total_replacements = 0usize;
+        let mut current_line: usize = 1;
+
+        loop {
+            let n = src.read(&mut buf).map_err(Error::Io)?;
+            if n == 0 {
+                break;
+            }
+            held.extend_from_slice(&buf[..n]);
+
+            let mut flush_len = match byte_window {
+                Some(window) => held.len().saturating_sub(window),
+                None => held.iter().rposition(|&b| b == b'\n').map_or(0, |pos| pos + 1),
+            };
+            // An occurrence that starts before the held-back tail but runs
+            // into it would be split; flush only up to where it starts.
+            if let Matcher::Literal(needle) = &self.matcher {
+                if let Some(m) = memmem::find_iter(&held, needle).take_while(|&m| m < flush_len).last() {
+                    if m + needle.len() > flush_len {
+                        flush_len = m;
+                    }
+                }
+            }
+            if flush_len == 0 {
+                continue;
+            }
+
+            total_replacements += self.write_stream_chunk(&held[..flush_len], current_line, &mut dst)?;
+            current_line += held[..flush_len].iter().filter(|&&b| b == b'\n').count();
+            held.drain(..flush_len);
+        }
+
+        // Nothing left to arrive, so the whole retained tail can be flushed.
+        total_replacements += self.write_stream_chunk(&held, current_line, &mut dst)?;
+        dst.flush().map_err(Error::Io)?;
+
+        Ok(total_replacements)
+    }
+
+    /// Replace within one `replace_stream` chunk, whose first byte is line
+    /// `base_line` of the overall input, and write the result to `dst`.
+    fn write_stream_chunk<W: Write>(&self, chunk: &[u8], base_line: usize, dst: &mut W) -> Result<usize> {
+        let chunk_ranges = self
+            .ranges
+            .as_ref()
+            .map(|ranges| shift_ranges_for_chunk(ranges, base_line));
+        let (out, replacements) = self.replace_chunk(chunk, chunk_ranges.as_deref());
+        dst.write_all(&out).map_err(Error::Io)?;
+        Ok(replacements)
+    }
+
+    /// Core of [`Self::replace_stream`]: apply this replacer to one chunk of
+    /// a stream, filtering by `ranges` (already translated into the chunk's
+    /// own relative line numbers) instead of `self.ranges`. Always takes the
+    /// per-match loop — never the whole-buffer `regex::Regex::replace_all`
+    /// fast path `replace_with_count` uses — since every match needs to be
+    /// checked against `ranges` individually.
+    fn replace_chunk(&self, text: &[u8], ranges: Option<&[LineRange]>) -> (Vec<u8>, usize) {
+        let mut new_data = Vec::with_capacity(text.len());
+        let mut last_match_end = 0;
+        let mut count = 0;
+
+        let line_index = if ranges.is_some() || self.prefilter.is_some() {
+            let offsets = build_line_offsets(text);
+            let total = total_line_count(text.len(), &offsets);
+            Some((offsets, total))
+        } else {
+            None
+        };
+        let case_fold_template = (self.expand && replacement_has_case_fold(&self.replacement))
+            .then(|| std::str::from_utf8(&self.replacement).unwrap());
+
+        match &self.matcher {
+            Matcher::Regex(re) => {
+                let mut visit = |m: regex::bytes::Captures| -> bool {
+                    if self.max_replacements > 0 && count >= self.max_replacements {
+                        return false;
+                    }
+
+                    let match_start = m.get(0).unwrap().start();
+                    let match_end = m.get(0).unwrap().end();
+
+                    if let Some(ranges) = ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(match_start, ranges, offsets, *total) {
+                            return true;
+                        }
+                    }
+
+                    new_data.extend_from_slice(&text[last_match_end..match_start]);
+                    if let Some(template) = case_fold_template {
+                        expand_with_case_folding(template, &m, &mut new_data);
+                    } else if self.expand {
+                        m.expand(&self.replacement, &mut new_data);
+                    } else {
+                        new_data.extend_from_slice(&self.replacement);
+                    }
+                    last_match_end = match_end;
+                    count += 1;
+                    true
+                };
+
+                if let Some(prefilter) = &self.prefilter {
+                    let (offsets, _) = line_index.as_ref().unwrap();
+                    each_prefiltered_match(re, text, prefilter, offsets, visit);
+                } else {
+                    for m in re.captures_iter(text) {
+                        if !visit(m) {
+                            break;
+                        }
+                    }
+                }
+            }
+            Matcher::Literal(needle) => {
+                for m in memmem::find_iter(text, needle) {
+                    if self.max_replacements > 0 && count >= self.max_replacements {
+                        break;
+                    }
+                    let end = m + needle.len();
+                    if let Some(unicode) = self.literal_word_boundary {
+                        if !literal_match_is_word_bounded(text, m, end, unicode) {
+                            continue;
+                        }
+                    }
+                    if let Some(ranges) = ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(m, ranges, offsets, *total) {
+                            continue;
+                        }
+                    }
+                    new_data.extend_from_slice(&text[last_match_end..m]);
+                    new_data.extend_from_slice(&self.replacement);
+                    last_match_end = end;
+                    count += 1;
+                }
+            }
+            Matcher::MultiLiteral(ac) => {
+                for m in ac.find_iter(text) {
+                    if self.max_replacements > 0 && count >= self.max_replacements {
+                        break;
+                    }
+                    if let Some(ranges) = ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(m.start(), ranges, offsets, *total) {
+                            continue;
+                        }
+                    }
+                    new_data.extend_from_slice(&text[last_match_end..m.start()]);
+                    new_data.extend_from_slice(&self.replacements[m.pattern().as_usize()]);
+                    last_match_end = m.end();
+                    count += 1;
+                }
+            }
+            Matcher::MultiRegex(re) => {
+                for m in re.captures_iter(text) {
+                    if self.max_replacements > 0 && count >= self.max_replacements {
+                        break;
+                    }
+
+                    let match_start = m.get(0).unwrap().start();
+                    let match_end = m.get(0).unwrap().end();
+
+                    if let Some(ranges) = ranges {
+                        let (offsets, total) = line_index.as_ref().unwrap();
+                        if !is_in_ranges(match_start, ranges, offsets, *total) {
+                            continue;
+                        }
+                    }
+
+                    new_data.extend_from_slice(&text[last_match_end..match_start]);
+                    let rule = multi_regex_rule_index(&m, &self.rule_groups)
+                        .expect("alternation always matches exactly one rule group");
+                    if self.expand {
+                        m.expand(&self.replacements[rule], &mut new_data);
+                    } else {
+                        new_data.extend_from_slice(&self.replacements[rule]);
+                    }
+                    last_match_end = match_end;
+                    count += 1;
+                }
+            }
+        }
+
+        new_data.extend_from_slice(&text[last_match_end..]);
+        (new_data, count)
+    }
+}
+
+/// Bytes read from the source per iteration in [`Replacer::replace_stream`].
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Translate absolute `ranges` into the relative (1-based) line numbering of
+/// a chunk whose first byte is line `base_line` of the overall input. A
+/// range that starts before this chunk is clamped so the chunk's own first
+/// line is still included; a range that ends before this chunk started is
+/// collapsed to `{start: 1, end: Some(0)}`, an always-empty range (`start >
+/// end`), rather than a negative bound `is_in_ranges` would misread as
+/// counting from the end. Callers must ensure `ranges` has no negative
+/// bound of its own first — see [`Replacer::replace_stream`].
+fn shift_ranges_for_chunk(ranges: &[LineRange], base_line: usize) -> Vec<LineRange> {
+    let base = base_line as i64 - 1;
+    ranges
+        .iter()
+        .map(|r| {
+            let shifted_end = r.end.map(|e| e - base);
+            if shifted_end.is_some_and(|e| e < 1) {
+                return LineRange { start: 1, end: Some(0) };
+            }
+            LineRange {
+                start: (r.start - base).max(1),
+                end: shifted_end,
+            }
+        })
+        .collect()
+}
+
+/// Precompute line start offsets.
+/// Returns a vector where index i is the byte offset of the start of line i+1.
+///
+/// Keying off `\n` alone already does the right thing for `\r\n`-terminated
+/// (CRLF) files too: a line's terminator is always the `\n`, whether or not
+/// it's preceded by a `\r`, so line numbers and `--range` filtering need no
+/// special CRLF handling here. The regex engine's own anchors (`^`/`$`/`.`)
+/// are the part that needs to know about `\r\n` explicitly — see the
+/// `crlf` flag on [`Replacer::new`].
+fn build_line_offsets(text: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    offsets.push(0);
+    for (i, &b) in text.iter().enumerate() {
+        if b == b'\n' {
+            offsets.push(i + 1);
+        }
+    }
+    offsets
+}
+
+/// Find the 1-based line number containing a byte offset, via binary search
+/// over line start offsets (`line_offsets[i] <= byte_offset < line_offsets[i+1]`).
+fn line_number_for(byte_offset: usize, line_offsets: &[usize]) -> usize {
+    let line_idx = match line_offsets.binary_search(&byte_offset) {
+        Ok(i) => i, // Exact match means start of line i+1 (0-based idx i)
+        Err(i) => i - 1, // Insertion point is i, so it belongs to line i-1 (0-based)
+    };
+    line_idx + 1
+}
+
+/// Number of actual (content-bearing) lines in `text`. `build_line_offsets`
+/// pushes one entry per line start, plus a trailing entry for the position
+/// right after a final newline — which isn't a line unless something
+/// follows it, so that trailing entry is excluded when it lands exactly at
+/// EOF.
+fn total_line_count(text_len: usize, line_offsets: &[usize]) -> usize {
+    match line_offsets.last() {
+        Some(&last) if last == text_len => line_offsets.len() - 1,
+        _ => line_offsets.len(),
+    }
+}
+
+/// Resolve a possibly-negative 1-based line bound against the file's actual
+/// line count, the way a negative list index counts back from the end:
+/// `-1` resolves to the last line, `-5` to the fifth-from-last.
+///
+/// Shared with `engine::line_in_ranges`, which applies the same negative-index
+/// semantics to `--range-lines` against a line number rather than a byte offset.
+pub(crate) fn resolve_bound(n: i64, total_lines: usize) -> usize {
+    if n < 0 {
+        total_lines.saturating_sub((-n) as usize).saturating_add(1)
+    } else {
+        n as usize
+    }
+}
+
+/// Check if a byte offset falls within any of the given line ranges, once
+/// each range's (possibly negative) bounds are resolved against the file's
+/// actual line count. A range whose resolved start is after its resolved
+/// end (e.g. `-1:-5` in a short file) matches nothing rather than erroring.
+fn is_in_ranges(byte_offset: usize, ranges: &[LineRange], line_offsets: &[usize], total_lines: usize) -> bool {
+    let line_number = line_number_for(byte_offset, line_offsets);
+
+    ranges.iter().any(|range| {
+        let start = resolve_bound(range.start, total_lines);
+        let end = range.end.map(|e| resolve_bound(e, total_lines));
+
+        if let Some(end) = end {
+            if start > end {
+                return false;
+            }
+        }
+
+        line_number >= start && end.map(|end| line_number <= end).unwrap_or(true)
+    })
+}
+
+/// Optimized check for allowed ranges using a cursor.
+/// Assumes matches are processed in order and allowed ranges are sorted by start.
+fn check_allowed_range_optimized(start: usize, end: usize, allowed: &[ReplacementRange], cursor: &mut usize) -> bool {
+    // Fast forward cursor: skip ranges that end before the match starts.
+    while *cursor < allowed.len() && allowed[*cursor].end <= start {
+        *cursor += 1;
+    }
+
+    if *cursor >= allowed.len() {
+        return false;
+    }
+
+    let r = &allowed[*cursor];
+    // Check intersection: start < r.end && r.start < end
+    // We know r.end > start (from loop).
+    // So we just need r.start < end.
+    if r.start < end {
+        return true;
+    }
+
+    // No overlap.
+    // Since allowed ranges are sorted by start, any subsequent range r' will have r'.start >= r.start >= end.
+    // So no future overlap is possible for this match.
+    false
+}
+
+/// Byte offset of the start of the line containing `byte_offset`.
+fn line_start_for(byte_offset: usize, line_offsets: &[usize]) -> usize {
+    match line_offsets.binary_search(&byte_offset) {
+        Ok(i) => line_offsets[i],
+        Err(i) => line_offsets[i - 1],
+    }
+}
+
+/// Drive `re` over `text` using `prefilter`'s literal occurrences to pick
+/// where to resume searching, instead of invoking the regex engine at every
+/// byte the way a plain `re.captures_iter(text)` scan would.
+///
+/// For each literal occurrence not already covered by a previous match, the
+/// search resumes from the start of its line (or from just past the
+/// previous match, whichever is later) via `captures_at` — which, unlike
+/// slicing `text`, still sees the real bytes around that position, so
+/// anchors (`^`, `\b`, ...) behave exactly as they would in a full scan.
+/// Restarting from the line start rather than the literal itself is only
+/// sound because `required_literal` refuses patterns that could match
+/// across a line boundary; every match a prefiltered pattern produces is
+/// therefore confined to the line its required literal sits on.
+///
+/// `f` is called with each match in order and returns whether to keep
+/// going; returning `false` (e.g. once `max_replacements` is hit) stops the
+/// scan early, same as `break`ing out of a `captures_iter` loop.
+fn each_prefiltered_match<'t>(
+    re: &Regex,
+    text: &'t [u8],
+    prefilter: &PrefilterFinder,
+    line_offsets: &[usize],
+    mut f: impl FnMut(regex::bytes::Captures<'t>) -> bool,
+) {
+    let mut search_from = 0usize;
+    for candidate in prefilter.finder.find_iter(text) {
+        if candidate < search_from {
+            continue;
+        }
+        let probe_from = std::cmp::max(line_start_for(candidate, line_offsets), search_from);
+        let caps = match re.captures_at(text, probe_from) {
+            Some(caps) => caps,
+            None => break,
+        };
+        let whole = caps.get(0).unwrap();
+        search_from = if whole.end() > whole.start() {
+            whole.end()
+        } else {
+            whole.end() + 1
+        };
+        if !f(caps) {
+            break;
+        }
+    }
+}
+
+/// Which rule's top-level group participated in a [`Matcher::MultiRegex`]
+/// match, by checking each rule's wrapper group (`rule_groups`, recovered
+/// from its `__txed_rule_N` name at construction time) in turn.
+fn multi_regex_rule_index(caps: &regex::bytes::Captures, rule_groups: &[usize]) -> Option<usize> {
+    rule_groups.iter().position(|&group_idx| caps.get(group_idx).is_some())
+}
+
+/// Whether `c` counts as a word character for `\b`-style boundary checks:
+/// Unicode alphanumeric-or-underscore in Unicode mode, ASCII
+/// `[A-Za-z0-9_]` only under `--no-unicode`.
+fn is_word_char(c: char, unicode: bool) -> bool {
+    if unicode {
+        c.is_alphanumeric() || c == '_'
+    } else {
+        c.is_ascii() && (c.is_ascii_alphanumeric() || c == '_')
+    }
+}
+
+/// Whether `pattern`'s first and last characters are both word characters —
+/// the precondition for a word-bounded (`--word-regexp`) `Matcher::Literal`
+/// to ever produce a match, since [`literal_match_is_word_bounded`] rejects
+/// any match whose own edge isn't a word character. A needle whose own edge
+/// isn't a word character (e.g. `"=="`) falls back to the regex engine's
+/// `\b` instead — see `use_literal_matcher` in [`Replacer::new`].
+fn literal_has_word_edges(pattern: &str, unicode: bool) -> bool {
+    let mut chars = pattern.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return false,
+    };
+    let last = chars.next_back().unwrap_or(first);
+    is_word_char(first, unicode) && is_word_char(last, unicode)
+}
+
+/// Decode the UTF-8 scalar ending just before `offset`, if any (`None` at
+/// the start of `text`). Assumes `text` is valid UTF-8, which always holds
+/// here — `Replacer` only ever matches against text decoded to a `String`
+/// (see [`crate::encoding`]).
+fn char_before(text: &[u8], offset: usize) -> Option<char> {
+    if offset == 0 {
+        return None;
+    }
+    let mut start = offset - 1;
+    while start > 0 && text[start] & 0b1100_0000 == 0b1000_0000 {
+        start -= 1;
+    }
+    std::str::from_utf8(&text[start..offset]).ok()?.chars().next()
+}
+
+/// Decode the UTF-8 scalar starting at `offset`, if any (`None` at the end
+/// of `text`).
+fn char_at(text: &[u8], offset: usize) -> Option<char> {
+    if offset >= text.len() {
+        return None;
+    }
+    let mut end = offset + 1;
+    while end < text.len() && text[end] & 0b1100_0000 == 0b1000_0000 {
+        end += 1;
+    }
+    std::str::from_utf8(&text[offset..end]).ok()?.chars().next()
+}
+
+/// Whether a `Matcher::Literal` match over `[start, end)` in `text`
+/// satisfies `\b` semantics on both edges: the character just outside the
+/// match (or the start/end of `text`, which never count as word
+/// characters) must not itself be a word character. The needle's own
+/// edges are guaranteed to be word characters by construction — see
+/// [`literal_has_word_edges`] — so only the outside neighbors need
+/// checking here.
+fn literal_match_is_word_bounded(text: &[u8], start: usize, end: usize, unicode: bool) -> bool {
+    let is_boundary = |c: Option<char>| !c.is_some_and(|c| is_word_char(c, unicode));
+    is_boundary(char_before(text, start)) && is_boundary(char_at(text, end))
+}
+
+/// Whether `replacement` contains a sed/Perl-style case-fold directive
+/// (`\U`, `\L`, `\u`, `\l`, `\E`) that [`expand_with_case_folding`] needs to
+/// interpret. `replace_with_count` checks this to steer clear of the regex
+/// crate's own `replace_all`/`replacen`, which only know how to expand
+/// `$group` references, not our fold state machine.
+fn replacement_has_case_fold(replacement: &[u8]) -> bool {
+    replacement
+        .windows(2)
+        .any(|w| w[0] == b'\\' && matches!(w[1], b'U' | b'L' | b'u' | b'l' | b'E'))
+}
+
+/// Expand `caps` into `replacement`, the same way `Captures::expand` would,
+/// except also interpreting sed/Perl-style case-fold directives: `\U`/`\L`
+/// start an uppercase/lowercase region lasting until the next `\E` (an
+/// unterminated region simply folds to the end of the replacement, not an
+/// error); `\u`/`\l` fold only the one character right after them, then
+/// fall back to whatever region fold was already active. `$group`/
+/// `${name}` references are resolved to their matched bytes first, using
+/// the same grammar [`validate::validate_replacement`] already validates,
+/// so a fold region can span across them (`\Uhello $1\E` uppercases both
+/// the literal text and the captured group).
+fn expand_with_case_folding(replacement: &str, caps: &regex::bytes::Captures, out: &mut Vec<u8>) {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Fold {
+        None,
+        Upper,
+        Lower,
+    }
+
+    fn push_char(c: char, mode: Fold, out: &mut Vec<u8>) {
+        let mut buf = [0u8; 4];
+        match mode {
+            Fold::Upper => {
+                for uc in c.to_uppercase() {
+                    out.extend_from_slice(uc.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+            Fold::Lower => {
+                for lc in c.to_lowercase() {
+                    out.extend_from_slice(lc.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+            Fold::None => out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes()),
+        }
+    }
+
+    fn push_str(s: &str, region: Fold, one_shot: &mut Option<Fold>, out: &mut Vec<u8>) {
+        for c in s.chars() {
+            let mode = one_shot.take().unwrap_or(region);
+            push_char(c, mode, out);
+        }
+    }
+
+    let bytes = replacement.as_bytes();
+    let mut region = Fold::None;
+    let mut one_shot: Option<Fold> = None;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos] == b'\\' && pos + 1 < bytes.len() && matches!(bytes[pos + 1], b'U' | b'L' | b'E' | b'u' | b'l') {
+            match bytes[pos + 1] {
+                b'U' => region = Fold::Upper,
+                b'L' => region = Fold::Lower,
+                b'E' => region = Fold::None,
+                b'u' => one_shot = Some(Fold::Upper),
+                b'l' => one_shot = Some(Fold::Lower),
+                _ => unreachable!(),
+            }
+            pos += 2;
+            continue;
+        }
+
+        if bytes[pos] == b'$' {
+            let rest = &replacement[pos + 1..];
+            let rest_bytes = rest.as_bytes();
+
+            if rest_bytes.first() == Some(&b'$') {
+                push_str("$", region, &mut one_shot, out);
+                pos += 2;
+                continue;
+            }
+
+            let resolved = if rest_bytes.first() == Some(&b'{') {
+                validate::parse_braced_reference(rest_bytes).map(|braced| {
+                    let name = &braced[1..braced.len() - 1];
+                    (name, braced.len())
+                })
+            } else {
+                validate::parse_unbraced_reference(rest_bytes).map(|name| (name, name.len()))
+            };
+
+            if let Some((name, consumed)) = resolved {
+                let cap_ref = match name.parse::<usize>() {
+                    Ok(n) => CaptureRef::Numbered(n),
+                    Err(_) => CaptureRef::Named(name),
+                };
+                let matched = match cap_ref {
+                    CaptureRef::Numbered(n) => caps.get(n).map(|m| m.as_bytes()),
+                    CaptureRef::Named(name) => caps.name(name).map(|m| m.as_bytes()),
+                };
+                // `matched` is always a slice of the original (UTF-8) input
+                // text, so it's always valid UTF-8 itself.
+                if let Some(text) = matched.and_then(|m| std::str::from_utf8(m).ok()) {
+                    push_str(text, region, &mut one_shot, out);
+                }
+                pos += 1 + consumed;
+                continue;
+            }
+
+            push_str("$", region, &mut one_shot, out);
+            pos += 1;
+            continue;
+        }
+
+        let c = replacement[pos..].chars().next().unwrap();
+        let mode = one_shot.take().unwrap_or(region);
+        push_char(c, mode, out);
+        pos += c.len_utf8();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_replacement() {
+        let replacer = Replacer::new(
+            "foo",
+            "bar",
+            false, // fixed_strings (treated as regex since false? No, depends on caller logic. Here false means regex? Wait. engine.rs sets it.
+                   // new() takes fixed_strings directly. If false, it tries regex parse. "foo" is valid regex.)
+            false, // ignore_case
+            false, // smart_case
+            true,  // case_sensitive
+            false, // word_regexp
             false, // multiline
             false, // single_line
             false, // dot_matches_newline
@@ -380,7 +1499,8 @@ mod tests {
             0,     // max_replacements
             None,
             None,
-            false
+            false,
+            ValidationMode::None,
         ).unwrap();
         let input = b"foo baz foo";
         let output = replacer.replace_with_count(input).0;
@@ -406,7 +1526,8 @@ mod tests {
             0,     // max_replacements
             None,
             None,
-            false
+            false,
+            ValidationMode::None,
         ).unwrap();
         let input = b"foo baz foo";
         let output = replacer.replace_with_count(input).0;
@@ -420,7 +1541,8 @@ mod tests {
             r"(\d+)",
             "number-$1",
             false, false, false, true, false, false, false, false, false, false, 0, None, None,
-            false // expand=false
+            false, // expand=false
+            ValidationMode::None,
         ).unwrap();
         let input = b"abc 123 def";
         let output = replacer.replace_with_count(input).0;
@@ -434,7 +1556,8 @@ mod tests {
             r"(\d+)",
             "number-$1",
             false, false, false, true, false, false, false, false, false, false, 0, None, None,
-            true // expand=true
+            true, // expand=true
+            ValidationMode::None,
         ).unwrap();
         let input = b"abc 123 def";
         let output = replacer.replace_with_count(input).0;
@@ -448,7 +1571,8 @@ mod tests {
             "x",
             "y",
             false, false, false, true, false, false, false, false, false, false, 2, None, None,
-            false
+            false,
+            ValidationMode::None,
         ).unwrap();
         let input = b"x x x x";
         let output = replacer.replace_with_count(input).0;
@@ -465,18 +1589,419 @@ mod tests {
             ReplacementRange { start: 4, end: 5 },
             ReplacementRange { start: 0, end: 1 },
         ]; // Unsorted to test sorting
-        
+
         let replacer = Replacer::new(
             "x",
             "y",
-            false, false, false, true, false, false, false, false, false, false, 0, None, 
+            false, false, false, true, false, false, false, false, false, false, 0, None,
             Some(allowed),
-            false
+            false,
+            ValidationMode::None,
         ).unwrap();
-        
+
         let input = b"x x x";
         let (output, count) = replacer.replace_with_count(input);
         assert_eq!(count, 2);
         assert_eq!(&output[..], b"y x y");
     }
+
+    #[test]
+    fn test_multi_range_with_negative_index() {
+        // 6 lines; ranges select line 1 and the last two lines (5, 6),
+        // skipping lines 2-4.
+        let ranges = vec![
+            LineRange { start: 1, end: Some(1) },
+            LineRange { start: -2, end: None },
+        ];
+
+        let replacer = Replacer::new(
+            "x",
+            "y",
+            true, false, false, true, false, false, false, false, false, false, 0,
+            Some(ranges),
+            None,
+            false,
+            ValidationMode::None,
+        ).unwrap();
+
+        let input = b"x\nx\nx\nx\nx\nx";
+        let (output, count) = replacer.replace_with_count(input);
+        assert_eq!(count, 3);
+        assert_eq!(&output[..], b"y\nx\nx\nx\ny\ny");
+    }
+
+    #[test]
+    fn test_multi_literal_single_pass() {
+        let rules = vec![
+            ("foo".to_string(), "one".to_string()),
+            ("bar".to_string(), "two".to_string()),
+        ];
+        let replacer = Replacer::new_multi(
+            &rules,
+            true, // fixed_strings -> AhoCorasick
+            false, false, false, false, false, false, false,
+            0, None, None, false,
+            ValidationMode::None,
+        ).unwrap();
+
+        let input = b"foo baz bar";
+        let (output, count) = replacer.replace_with_count(input);
+        assert_eq!(count, 2);
+        assert_eq!(&output[..], b"one baz two");
+    }
+
+    #[test]
+    fn test_multi_literal_leftmost_longest() {
+        // "foobar" should win over "foo" when both match at the same start.
+        let rules = vec![
+            ("foo".to_string(), "SHORT".to_string()),
+            ("foobar".to_string(), "LONG".to_string()),
+        ];
+        let replacer = Replacer::new_multi(
+            &rules,
+            true, false, false, false, false, false, false, false,
+            0, None, None, false,
+            ValidationMode::None,
+        ).unwrap();
+
+        let input = b"foobar";
+        let (output, count) = replacer.replace_with_count(input);
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"LONG");
+    }
+
+    #[test]
+    fn test_multi_regex_with_expand() {
+        // Each rule becomes its own wrapper group in the combined
+        // alternation, so group numbering is global rather than per-rule:
+        // rule 0 is group 1 (its own `(\d+)` is group 2), rule 1 is group 3
+        // (its `(\d+)` is group 4).
+        let rules = vec![
+            (r"a(\d+)".to_string(), "A$2".to_string()),
+            (r"b(\d+)".to_string(), "B$4".to_string()),
+        ];
+        let replacer = Replacer::new_multi(
+            &rules,
+            false, false, false, false, false, false, false, false,
+            0, None, None,
+            true, // expand
+            ValidationMode::None,
+        ).unwrap();
+
+        let input = b"a1 x b2";
+        let (output, count) = replacer.replace_with_count(input);
+        assert_eq!(count, 2);
+        assert_eq!(&output[..], b"A1 x B2");
+    }
+
+    #[test]
+    fn test_unknown_numbered_capture_strict_errors() {
+        let err = Replacer::new(
+            r"(\d+)",
+            "number-$7",
+            false, false, false, true, false, false, false, false, false, false, 0, None, None,
+            true,
+            ValidationMode::Strict,
+        ).unwrap_err();
+        assert!(err.to_string().contains("unknown capture"));
+    }
+
+    #[test]
+    fn test_unknown_named_capture_strict_errors() {
+        let err = Replacer::new(
+            r"(?P<num>\d+)",
+            "$nope",
+            false, false, false, true, false, false, false, false, false, false, 0, None, None,
+            true,
+            ValidationMode::Strict,
+        ).unwrap_err();
+        assert!(err.to_string().contains("unknown capture"));
+    }
+
+    #[test]
+    fn test_known_named_capture_strict_ok() {
+        let replacer = Replacer::new(
+            r"(?P<num>\d+)",
+            "n=$num",
+            false, false, false, true, false, false, false, false, false, false, 0, None, None,
+            true,
+            ValidationMode::Strict,
+        ).unwrap();
+        let output = replacer.replace_with_count(b"x 42 y").0;
+        assert_eq!(&output[..], b"x n=42 y");
+    }
+
+    #[test]
+    fn test_prefilter_skips_non_matching_text() {
+        // "needle-" is a required literal of the pattern, so the prefilter
+        // should let us skip straight to it rather than scanning byte by
+        // byte through the padding before it.
+        let replacer = Replacer::new(
+            r"needle-(\d+)",
+            "[$1]",
+            false, false, false, true, false, false, false, false, false, false, 0, None, None,
+            true,
+            ValidationMode::None,
+        ).unwrap();
+
+        let mut input = "x ".repeat(1000);
+        input.push_str("needle-42 needle-7");
+        let (output, count) = replacer.replace_with_count(input.as_bytes());
+        assert_eq!(count, 2);
+        assert!(output.ends_with(b"[42] [7]"));
+    }
+
+    #[test]
+    fn test_prefilter_respects_line_ranges() {
+        let ranges = vec![LineRange { start: 2, end: Some(2) }];
+        let replacer = Replacer::new(
+            r"needle-(\d+)",
+            "[$1]",
+            false, false, false, true, false, false, false, false, false, false, 0,
+            Some(ranges),
+            None,
+            true,
+            ValidationMode::None,
+        ).unwrap();
+
+        let input = b"needle-1\nneedle-2\nneedle-3";
+        let (output, count) = replacer.replace_with_count(input);
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"needle-1\n[2]\nneedle-3");
+    }
+
+    #[test]
+    fn test_prefilter_disabled_when_pattern_may_cross_lines() {
+        // A pattern with `.` can match across line boundaries once
+        // `dot_matches_newline` is set, so it must not get a line-bounded
+        // prefilter window.
+        let replacer = Replacer::new(
+            r"needle.+more",
+            "X",
+            false, false, false, true, false, false, false, true, false, false, 0, None, None,
+            false,
+            ValidationMode::None,
+        ).unwrap();
+
+        let input = b"needle\nmore";
+        let output = replacer.replace_with_count(input).0;
+        assert_eq!(&output[..], b"X");
+    }
+
+    #[test]
+    fn test_replace_stream_literal_matches_whole_file_result() {
+        let replacer = Replacer::new(
+            "foo", "bar", true, false, false, true, false, false, false, false, false, false, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+
+        // A chunk size smaller than the needle would defeat the point of
+        // the test, so drive the stream through tiny reads by hand instead
+        // of relying on STREAM_CHUNK_SIZE, which is tuned for real files.
+        let input = b"foofoofoofoofoofoofoofoo".repeat(50);
+        let mut src: Box<dyn std::io::Read + '_> = Box::new(std::io::empty());
+        for piece in input.chunks(7) {
+            src = Box::new(std::io::Read::chain(src, piece));
+        }
+        let mut out = Vec::new();
+        let count = replacer.replace_stream(src, &mut out).unwrap();
+
+        let expected = replacer.replace_with_count(&input).0;
+        assert_eq!(out, &expected[..]);
+        assert_eq!(count, replacer.replace_with_count(&input).1);
+    }
+
+    #[test]
+    fn test_replace_stream_regex_match_past_first_chunk_boundary() {
+        let replacer = Replacer::new(
+            r"needle-\d+", "HIT", false, false, false, true, false, false, false, false, false,
+            false, 0, None, None, false, ValidationMode::None,
+        ).unwrap();
+
+        // Pad the input well past STREAM_CHUNK_SIZE so the match only
+        // appears after at least one full read/flush cycle has already
+        // happened; line-boundary carry-over must still catch it intact.
+        let mut input = Vec::new();
+        while input.len() < 70_000 {
+            input.extend_from_slice(b"padding padding padding\n");
+        }
+        input.extend_from_slice(b"needle-12345\n");
+
+        let mut out = Vec::new();
+        let count = replacer.replace_stream(&input[..], &mut out).unwrap();
+        assert_eq!(count, 1);
+        assert!(out.ends_with(b"HIT\n"));
+    }
+
+    #[test]
+    fn test_replace_stream_applies_shifted_line_ranges_across_chunks() {
+        let ranges = vec![LineRange { start: 2, end: Some(2) }];
+        let replacer = Replacer::new(
+            "needle", "HIT", true, false, false, true, false, false, false, false, false, false,
+            0, Some(ranges), None, false, ValidationMode::None,
+        ).unwrap();
+
+        let input = b"needle\nneedle\nneedle\n".to_vec();
+        let mut out = Vec::new();
+        let count = replacer.replace_stream(&input[..], &mut out).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(&out[..], b"needle\nHIT\nneedle\n");
+    }
+
+    #[test]
+    fn test_replace_stream_rejects_negative_range_bound() {
+        let ranges = vec![LineRange { start: -2, end: None }];
+        let replacer = Replacer::new(
+            "needle", "HIT", true, false, false, true, false, false, false, false, false, false,
+            0, Some(ranges), None, false, ValidationMode::None,
+        ).unwrap();
+
+        let input = b"needle\nneedle\n".to_vec();
+        let mut out = Vec::new();
+        assert!(replacer.replace_stream(&input[..], &mut out).is_err());
+    }
+
+    #[test]
+    fn test_crlf_mode_lets_end_anchor_see_past_the_cr() {
+        // multi-line `$` matches right before the `\n`; without CRLF mode
+        // the `\r` sits between "x" and that position, so "x$" can't match.
+        let replacer = Replacer::new(
+            "x$", "Y", false, false, false, true, false, true, false, false, false, true, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+        let input = b"barx\r\nbaz";
+        let (output, count) = replacer.replace_with_count(input);
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"barY\r\nbaz");
+
+        let replacer_no_crlf = Replacer::new(
+            "x$", "Y", false, false, false, true, false, true, false, false, false, false, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+        let count = replacer_no_crlf.count_matches(input);
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_crlf_file_line_ranges_still_target_the_right_line() {
+        let ranges = vec![LineRange { start: 2, end: Some(2) }];
+        let replacer = Replacer::new(
+            "foo", "bar", true, false, false, true, false, false, false, false, false, true, 0,
+            Some(ranges), None, false, ValidationMode::None,
+        ).unwrap();
+
+        let input = b"foo\r\nfoo\r\nfoo\r\n";
+        let (output, count) = replacer.replace_with_count(input);
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"foo\r\nbar\r\nfoo\r\n");
+    }
+
+    #[test]
+    fn test_word_boundary_literal_matches_at_buffer_start_and_end() {
+        // "foo" sits at the very start and the very end of the buffer, with
+        // no preceding/following char at all — both ends must still count
+        // as boundaries.
+        let replacer = Replacer::new(
+            "foo", "bar", true, false, false, true, true, false, false, false, false, false, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"foo bar foo");
+        assert_eq!(count, 2);
+        assert_eq!(&output[..], b"bar bar bar");
+    }
+
+    #[test]
+    fn test_word_boundary_literal_rejects_matches_adjacent_to_letters() {
+        let replacer = Replacer::new(
+            "foo", "bar", true, false, false, true, true, false, false, false, false, false, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+        // "foobar" and "xfoo" both run the needle's edge straight into
+        // another word character, so neither is a whole-word match.
+        assert_eq!(replacer.count_matches(b"foobar xfoo"), 0);
+    }
+
+    #[test]
+    fn test_word_boundary_literal_accepts_matches_adjacent_to_punctuation() {
+        let replacer = Replacer::new(
+            "foo", "bar", true, false, false, true, true, false, false, false, false, false, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"(foo).foo,");
+        assert_eq!(count, 2);
+        assert_eq!(&output[..], b"(bar).bar,");
+    }
+
+    #[test]
+    fn test_word_boundary_literal_with_non_word_edge_falls_back_to_regex() {
+        // "--" has no word-character edge, so the literal fast path can't
+        // express `\b` for it (see `literal_has_word_edges`) and `new()`
+        // must fall back to `Matcher::Regex` to get correct behavior.
+        let replacer = Replacer::new(
+            "--", "DASH", true, false, false, true, true, false, false, false, false, false, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"a--b");
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"aDASHb");
+    }
+
+    #[test]
+    fn test_case_fold_upper_region_spans_literal_and_group() {
+        let replacer = Replacer::new(
+            r"(\w+)", r"[\U$1\E]", false, false, false, true, false, false, false, false, false, false, 0,
+            None, None, true, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"ok");
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"[OK]");
+    }
+
+    #[test]
+    fn test_case_fold_one_shot_upper_capitalizes_first_letter_only() {
+        let replacer = Replacer::new(
+            r"(\w+)", r"\u$1", false, false, false, true, false, false, false, false, false, false, 0,
+            None, None, true, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"hello world");
+        assert_eq!(count, 2);
+        assert_eq!(&output[..], b"Hello World");
+    }
+
+    #[test]
+    fn test_case_fold_one_shot_lower_only_affects_next_char() {
+        let replacer = Replacer::new(
+            r"(\w+)", r"\l$1", false, false, false, true, false, false, false, false, false, false, 0,
+            None, None, true, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"HELLO WORLD");
+        assert_eq!(count, 2);
+        assert_eq!(&output[..], b"hELLO wORLD");
+    }
+
+    #[test]
+    fn test_case_fold_unterminated_region_folds_to_end_of_replacement() {
+        // No `\E` at all: per sed/Perl semantics this isn't an error, it
+        // just folds every remaining char, including the captured group.
+        let replacer = Replacer::new(
+            r"(\w+)", r"\U$1", false, false, false, true, false, false, false, false, false, false, 0,
+            None, None, true, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"ok");
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"OK");
+    }
+
+    #[test]
+    fn test_case_fold_is_inert_without_expand() {
+        // `\U`/`\E` only mean something in expand mode; otherwise they're
+        // just literal backslash-letter bytes, same as before this feature.
+        let replacer = Replacer::new(
+            "ok", r"\Uyes\E", true, false, false, true, false, false, false, false, false, false, 0,
+            None, None, false, ValidationMode::None,
+        ).unwrap();
+        let (output, count) = replacer.replace_with_count(b"ok");
+        assert_eq!(count, 1);
+        assert_eq!(&output[..], b"\\Uyes\\E".as_slice());
+    }
 }
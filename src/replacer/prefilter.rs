@@ -0,0 +1,160 @@
+use regex_syntax::ast::{self, Ast, RepetitionKind, RepetitionRange};
+
+/// Shortest literal worth memmem-searching for; anything shorter doesn't
+/// discriminate enough to pay for the extra machinery around it.
+const MIN_LITERAL_LEN: usize = 2;
+
+/// Pull a mandatory literal substring out of `pattern` — one that every
+/// match is guaranteed to contain — for use as a [`memchr::memmem`]
+/// prefilter ahead of the regex engine. Returns `None` when no such literal
+/// exists (e.g. `\d+\s*`), when the longest one found is too short to be
+/// worth it, or when `pattern` could plausibly match across a line
+/// boundary, since the line-bounded search window this prefilter enables
+/// isn't safe for that. Any of those cases falls back to the caller's
+/// ordinary full-text regex scan rather than guessing.
+pub fn required_literal(pattern: &str) -> Option<Vec<u8>> {
+    if may_cross_lines(pattern) {
+        return None;
+    }
+    // An inline `i` flag (`(?i)`, `(?im:...)`) makes matching
+    // case-insensitive, which a case-sensitive memmem search can't honor;
+    // like `may_cross_lines`, this errs toward giving up.
+    let inline_ignore_case = pattern.split("(?").skip(1).any(|rest| {
+        let flags = rest.split(&[':', ')'][..]).next().unwrap_or("");
+        flags.split('-').next().unwrap_or("").contains('i')
+    });
+    if inline_ignore_case {
+        return None;
+    }
+
+    let parsed = ast::parse::Parser::new().parse(pattern).ok()?;
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    collect_required_runs(&parsed, &mut current, &mut runs);
+    flush(&mut current, &mut runs);
+
+    runs.into_iter()
+        .max_by_key(String::len)
+        .filter(|lit| lit.len() >= MIN_LITERAL_LEN)
+        .map(String::into_bytes)
+}
+
+/// Conservative check for whether `pattern` could ever match a newline —
+/// explicit `\n`/`\r`, a dot or negated class that isn't restricted to not
+/// match them, or a whitespace class (`\s` includes `\n`). A false positive
+/// here just disables the prefilter; it never affects correctness, so this
+/// errs toward "yes" whenever it can't easily rule it out.
+fn may_cross_lines(pattern: &str) -> bool {
+    pattern.contains('\n')
+        || pattern.contains('\r')
+        || pattern.contains(r"\n")
+        || pattern.contains(r"\r")
+        || pattern.contains(r"\s")
+        || pattern.contains(r"\S")
+        || pattern.contains('.')
+        || pattern.contains("[^")
+}
+
+/// Walk the AST collecting contiguous runs of `Literal` nodes that are
+/// unconditionally present in every match — i.e. not inside an optional
+/// repetition or an alternation branch, either of which can make a
+/// following literal absent from some matches. Each run is flushed into
+/// `runs` whenever something breaks that guarantee; the caller picks the
+/// longest run as the one substring every match must contain.
+fn collect_required_runs(ast: &Ast, current: &mut String, runs: &mut Vec<String>) {
+    match ast {
+        Ast::Literal(lit) => current.push(lit.c),
+        Ast::Concat(concat) => {
+            for ast in &concat.asts {
+                collect_required_runs(ast, current, runs);
+            }
+        }
+        Ast::Group(group) => collect_required_runs(&group.ast, current, runs),
+        Ast::Repetition(rep) => {
+            // Even a required repetition breaks the run around it: `ab+c`
+            // matches `abbc`, which doesn't contain `abc`.
+            flush(current, runs);
+            if repetition_is_required(rep) {
+                collect_required_runs(&rep.ast, current, runs);
+                flush(current, runs);
+            }
+        }
+        Ast::Empty(_) | Ast::Flags(_) => {}
+        Ast::Alternation(_)
+        | Ast::Dot(_)
+        | Ast::Assertion(_)
+        | Ast::ClassUnicode(_)
+        | Ast::ClassPerl(_)
+        | Ast::ClassBracketed(_) => flush(current, runs),
+    }
+}
+
+fn repetition_is_required(rep: &ast::Repetition) -> bool {
+    match &rep.op.kind {
+        RepetitionKind::ZeroOrOne | RepetitionKind::ZeroOrMore => false,
+        RepetitionKind::OneOrMore => true,
+        RepetitionKind::Range(range) => match range {
+            RepetitionRange::Exactly(n) => *n >= 1,
+            RepetitionRange::AtLeast(n) => *n >= 1,
+            RepetitionRange::Bounded(min, _) => *min >= 1,
+        },
+    }
+}
+
+fn flush(current: &mut String, runs: &mut Vec<String>) {
+    if !current.is_empty() {
+        runs.push(std::mem::take(current));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_literal_is_extracted() {
+        assert_eq!(required_literal("foobar"), Some(b"foobar".to_vec()));
+    }
+
+    #[test]
+    fn too_short_literal_is_rejected() {
+        assert_eq!(required_literal("a"), None);
+    }
+
+    #[test]
+    fn optional_prefix_is_excluded_from_the_run() {
+        // "maybe" only appears when the optional group matches, so the only
+        // guaranteed literal is "bar".
+        assert_eq!(required_literal("(maybe)?bar"), Some(b"bar".to_vec()));
+    }
+
+    #[test]
+    fn longest_run_wins() {
+        assert_eq!(required_literal(r"ab\d+abcdef"), Some(b"abcdef".to_vec()));
+    }
+
+    #[test]
+    fn alternation_has_no_required_literal() {
+        assert_eq!(required_literal("foo|bar"), None);
+    }
+
+    #[test]
+    fn required_repetition_keeps_the_literal() {
+        assert_eq!(required_literal("(?:abcde)+"), Some(b"abcde".to_vec()));
+    }
+
+    #[test]
+    fn dot_disables_the_prefilter() {
+        assert_eq!(required_literal("abcde.fghij"), None);
+    }
+
+    #[test]
+    fn whitespace_class_disables_the_prefilter() {
+        assert_eq!(required_literal(r"abcde\sfghij"), None);
+    }
+
+    #[test]
+    fn unparseable_pattern_yields_no_literal() {
+        assert_eq!(required_literal("(abcde"), None);
+    }
+}
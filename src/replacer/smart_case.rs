@@ -0,0 +1,109 @@
+use regex_syntax::ast::{self, Ast};
+
+/// Whether `--smart-case` should turn on case-insensitive matching for this
+/// (already escaped/wrapped) regex source.
+///
+/// A naive `pattern.chars().all(|c| !c.is_uppercase())` misfires on
+/// metacharacters: `\D` or `\bFoo` contain an uppercase `D`/`F` that has
+/// nothing to do with literal text case. Instead, parse the pattern and walk
+/// its AST, collecting only the code points that are actual `Literal` nodes
+/// — character classes (`\D`, `[A-Z]`), flags, group names, and other
+/// escape tokens are ignored. Case-insensitive matching turns on only when
+/// none of those literal code points is uppercase (Unicode-aware, not just
+/// ASCII). A pattern with no literals at all (e.g. `\d+`) is treated as
+/// lowercase, same as today's "nothing to disable insensitivity for" case.
+/// If the pattern fails to parse here, `RegexBuilder::build` will reject it
+/// shortly after anyway, so fall back to the old raw-byte check rather than
+/// guessing.
+pub fn is_lowercase_literal(pattern: &str) -> bool {
+    match ast::parse::Parser::new().parse(pattern) {
+        Ok(parsed) => {
+            let mut literals = String::new();
+            collect_literals(&parsed, &mut literals);
+            literals.chars().all(|c| !c.is_uppercase())
+        }
+        Err(_) => pattern.chars().all(|c| !c.is_uppercase()),
+    }
+}
+
+fn collect_literals(ast: &Ast, out: &mut String) {
+    match ast {
+        Ast::Literal(lit) => out.push(lit.c),
+        Ast::Concat(concat) => {
+            for ast in &concat.asts {
+                collect_literals(ast, out);
+            }
+        }
+        Ast::Alternation(alt) => {
+            for ast in &alt.asts {
+                collect_literals(ast, out);
+            }
+        }
+        Ast::Group(group) => collect_literals(&group.ast, out),
+        Ast::Repetition(rep) => collect_literals(&rep.ast, out),
+        Ast::Empty(_)
+        | Ast::Flags(_)
+        | Ast::Dot(_)
+        | Ast::Assertion(_)
+        | Ast::ClassUnicode(_)
+        | Ast::ClassPerl(_)
+        | Ast::ClassBracketed(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_lowercase_literal_is_lowercase() {
+        assert!(is_lowercase_literal("foo"));
+    }
+
+    #[test]
+    fn plain_uppercase_literal_is_not_lowercase() {
+        assert!(!is_lowercase_literal("Foo"));
+    }
+
+    #[test]
+    fn class_shorthand_uppercase_letter_is_ignored() {
+        // \D is a non-digit class, not a literal 'D'.
+        assert!(is_lowercase_literal(r"\D"));
+    }
+
+    #[test]
+    fn word_boundary_wrapped_literal_ignores_boundary_markers() {
+        assert!(!is_lowercase_literal(r"\bFoo\b"));
+        assert!(is_lowercase_literal(r"\bfoo\b"));
+    }
+
+    #[test]
+    fn bracketed_class_with_uppercase_range_is_ignored() {
+        assert!(is_lowercase_literal(r"[A-Z]+"));
+    }
+
+    #[test]
+    fn all_metacharacters_no_literals_is_lowercase() {
+        assert!(is_lowercase_literal(r"\d+\s*"));
+    }
+
+    #[test]
+    fn unicode_uppercase_literal_is_detected() {
+        assert!(!is_lowercase_literal("Über"));
+        assert!(is_lowercase_literal("über"));
+    }
+
+    #[test]
+    fn alternation_checks_every_branch() {
+        assert!(!is_lowercase_literal("foo|Bar"));
+        assert!(is_lowercase_literal("foo|bar"));
+    }
+
+    #[test]
+    fn unparseable_pattern_falls_back_to_raw_check() {
+        // Unbalanced group; can't parse as an AST, so we fall back to the
+        // old raw-byte scan rather than erroring here (build() will reject
+        // it with a proper regex error shortly after).
+        assert!(!is_lowercase_literal("(Foo"));
+    }
+}
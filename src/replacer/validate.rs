@@ -3,10 +3,28 @@ use crate::model::ValidationMode;
 use std::borrow::Cow;
 use std::str::CharIndices;
 
+/// A capture group reference as named in a replacement string, resolved to
+/// either a numbered group (`$1`, `${1}`) or a named group (`$foo`, `${foo}`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureRef<'a> {
+    Numbered(usize),
+    Named(&'a str),
+}
+
 /// Validate replacement string for valid capture group references.
 /// Checks for $0, $1, $2, ..., ${1}, ${name}.
-/// Detects ambiguous forms like $1bad (should be ${1}bad).
-pub fn validate_replacement(replacement: &str, mode: ValidationMode) -> Result<Cow<'_, str>> {
+/// Detects ambiguous forms like $1bad (should be ${1}bad), and, via `known`,
+/// flags references to capture groups the pattern doesn't actually have.
+///
+/// `known` is a cheap predicate rather than a `HashSet` so the caller can
+/// thread in whatever group metadata its regex engine exposes (capture
+/// names, `captures_len()`) without this module depending on the regex
+/// crate's types directly.
+pub fn validate_replacement(
+    replacement: &str,
+    mode: ValidationMode,
+    known: impl Fn(CaptureRef) -> bool,
+) -> Result<Cow<'_, str>> {
     if mode == ValidationMode::None {
         return Ok(Cow::Borrowed(replacement));
     }
@@ -18,8 +36,15 @@ pub fn validate_replacement(replacement: &str, mode: ValidationMode) -> Result<C
     for capture in CaptureIter::new(replacement) {
         let name = capture.name;
         // Handle braced references: ${...}
-        if name.starts_with('{') && name.ends_with('}') {
-            // Braced is unambiguous
+        if let Some(inner) = name.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            // Braced is unambiguous; still check it refers to a real group.
+            let cap_ref = match inner.parse::<usize>() {
+                Ok(n) => CaptureRef::Numbered(n),
+                Err(_) => CaptureRef::Named(inner),
+            };
+            if !known(cap_ref) {
+                report_unknown(mode, &format!("${{{}}}", inner))?;
+            }
             continue;
         }
 
@@ -73,9 +98,30 @@ pub fn validate_replacement(replacement: &str, mode: ValidationMode) -> Result<C
                         }
                         ValidationMode::None => unreachable!(),
                     }
+
+                    let cap_ref = name[..digit_count].parse::<usize>().map(CaptureRef::Numbered);
+                    if let Ok(cap_ref) = cap_ref {
+                        if !known(cap_ref) {
+                            report_unknown(mode, &format!("${}", &name[..digit_count]))?;
+                        }
+                    }
+                    continue;
                 }
+
+                // Unambiguous unbraced numeric reference, e.g. `$1`.
+                if let Ok(n) = name.parse::<usize>() {
+                    if !known(CaptureRef::Numbered(n)) {
+                        report_unknown(mode, &format!("${}", name))?;
+                    }
+                }
+                continue;
             }
         }
+
+        // Unbraced named reference, e.g. `$foo`.
+        if !known(CaptureRef::Named(name)) {
+            report_unknown(mode, &format!("${}", name))?;
+        }
     }
 
     if modified {
@@ -86,6 +132,23 @@ pub fn validate_replacement(replacement: &str, mode: ValidationMode) -> Result<C
     }
 }
 
+/// Handle a reference to a capture group the pattern doesn't have, per
+/// `mode`: error in `Strict`, warn-and-continue in `Warn`, and never called
+/// at all in `None` (caller short-circuits before reaching here).
+fn report_unknown(mode: ValidationMode, reference: &str) -> Result<()> {
+    match mode {
+        ValidationMode::Strict => Err(Error::UnknownCaptureReference(format!(
+            "Replacement references unknown capture group `{}`.",
+            reference
+        ))),
+        ValidationMode::Warn => {
+            eprintln!("WARN: Replacement references unknown capture group `{}`.", reference);
+            Ok(())
+        }
+        ValidationMode::None => Ok(()),
+    }
+}
+
 /// A capture group reference found in the replacement string.
 #[derive(Debug)]
 struct Capture<'a> {
@@ -178,7 +241,11 @@ impl<'a> Iterator for CaptureIter<'a> {
 /// Parse a braced reference: ${...}
 /// Returns the full content including braces, e.g. "{foo}".
 /// Actually logic below returns "{foo}".
-fn parse_braced_reference(bytes: &[u8]) -> Option<&str> {
+///
+/// `pub(crate)` so [`crate::replacer::expand_with_case_folding`] can resolve
+/// `$group`/`${name}` references with the exact same grammar this module
+/// already validates, instead of re-deriving its own.
+pub(crate) fn parse_braced_reference(bytes: &[u8]) -> Option<&str> {
     assert_eq!(bytes[0], b'{');
     let mut end = 1;
     while end < bytes.len() && bytes[end] != b'}' {
@@ -194,7 +261,7 @@ fn parse_braced_reference(bytes: &[u8]) -> Option<&str> {
 
 /// Parse an unbraced reference: $name where name consists of valid characters.
 /// Returns name, e.g. "foo".
-fn parse_unbraced_reference(bytes: &[u8]) -> Option<&str> {
+pub(crate) fn parse_unbraced_reference(bytes: &[u8]) -> Option<&str> {
     let mut end = 0;
     while end < bytes.len() && is_valid_capture_char(bytes[end]) {
         end += 1;
@@ -227,7 +294,7 @@ mod tests {
             ("$1_", false),     // underscore after digits is ambiguous
         ];
         for (input, should_validate) in cases {
-            let result = validate_replacement(input, ValidationMode::Strict);
+            let result = validate_replacement(input, ValidationMode::Strict, |_| true);
             if should_validate {
                 assert!(
                     result.is_ok(),
@@ -250,22 +317,42 @@ mod tests {
     #[test]
     fn test_ambiguous_capture_warn() {
         // $1bad -> ${1}bad
-        let result = validate_replacement("$1bad", ValidationMode::Warn).unwrap();
+        let result = validate_replacement("$1bad", ValidationMode::Warn, |_| true).unwrap();
         assert_eq!(result, "${1}bad");
 
         // $1bad$2ok -> ${1}bad${2}ok
-        let result = validate_replacement("$1bad$2ok", ValidationMode::Warn).unwrap();
+        let result = validate_replacement("$1bad$2ok", ValidationMode::Warn, |_| true).unwrap();
         assert_eq!(result, "${1}bad${2}ok");
 
         // $10bad -> ${10}bad
-        let result = validate_replacement("$10bad", ValidationMode::Warn).unwrap();
+        let result = validate_replacement("$10bad", ValidationMode::Warn, |_| true).unwrap();
         assert_eq!(result, "${10}bad");
     }
 
     #[test]
     fn test_ambiguous_capture_none() {
         let input = "$1bad";
-        let result = validate_replacement(input, ValidationMode::None).unwrap();
+        let result = validate_replacement(input, ValidationMode::None, |_| true).unwrap();
         assert_eq!(result, input);
     }
+
+    #[test]
+    fn test_unknown_numbered_reference_strict() {
+        let result = validate_replacement("$1", ValidationMode::Strict, |_| false);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_unknown_named_reference_warn_proceeds() {
+        let result = validate_replacement("$foo", ValidationMode::Warn, |_| false).unwrap();
+        assert_eq!(result, "$foo");
+    }
+
+    #[test]
+    fn test_known_braced_reference_ok() {
+        let result =
+            validate_replacement("${name}", ValidationMode::Strict, |r| r == CaptureRef::Named("name"))
+                .unwrap();
+        assert_eq!(result, "${name}");
+    }
 }
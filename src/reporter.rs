@@ -1,6 +1,10 @@
-use crate::events::{Event, FileEvent, Policies, RunEnd, RunStart, SkipReason};
+use crate::events::{
+    DiffHunkData, DiffLineData, DiffLineTag, Event, FileEvent, Policies, RgJsonEndData, RgJsonEvent,
+    RgJsonMatchData, RgJsonPathData, RgJsonStats, RgJsonSubmatch, RgJsonText, RunEnd, RunStart, SkipReason,
+};
 use crate::model::Pipeline;
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::path::PathBuf;
 
 /// Result of processing a single file.
@@ -20,12 +24,23 @@ pub struct FileResult {
     pub skipped: Option<String>,
     /// Diff lines (if dry_run or preview).
     pub diff: Option<String>,
+    /// Same diff as `diff`, but as structured hunks rather than rendered
+    /// text, for `Report::print_json`'s `diff_hunks` event field.
+    pub diff_hunks: Option<Vec<crate::diff::DiffHunk>>,
     /// Whether the diff is binary (sanitized).
     pub diff_is_binary: bool,
+    /// Which read strategy `write::read_file` picked for this file:
+    /// `"mmap"` or `"buffered"`. `None` when the file was never read (e.g.
+    /// skipped before reading, or `--stdin-text`).
+    pub io: Option<String>,
     /// Full generated content (for stdin-text mode).
     pub generated_content: Option<String>,
     /// Whether this file is virtual (not on disk).
     pub is_virtual: bool,
+    /// Per-replacement detail, populated only when `Pipeline::emit_edits` is set.
+    pub edits: Option<Vec<crate::events::EditRecord>>,
+    /// Path of the pre-edit safety copy, if `Pipeline::backup_suffix` was set.
+    pub backup_path: Option<PathBuf>,
 }
 
 /// Overall execution report.
@@ -257,7 +272,7 @@ impl Report {
         // The content is inside the JSON event.
 
         let start = RunStart {
-            schema_version: "1".into(),
+            schema_version: "2".into(),
             tool_version: tool_version.into(),
             mode: mode.into(),
             input_mode: input_mode.into(),
@@ -291,6 +306,7 @@ impl Report {
                     "binary file" => SkipReason::Binary,
                     "symlink" => SkipReason::Symlink,
                     "glob exclude" => SkipReason::GlobExclude,
+                    "outside-allowed-root" => SkipReason::OutsideAllowedRoot,
                     other => SkipReason::Other(other.to_string()),
                 };
                 FileEvent::Skipped {
@@ -303,9 +319,13 @@ impl Report {
                     modified: file.modified,
                     replacements: file.replacements,
                     diff: file.diff.clone(),
+                    diff_hunks: file.diff_hunks.as_deref().map(structured_diff_hunks),
+                    io: file.io.clone(),
                     generated_content: file.generated_content.clone(),
                     diff_is_binary: file.diff_is_binary,
                     is_virtual: file.is_virtual,
+                    edits: file.edits.clone(),
+                    backup_path: file.backup_path.clone(),
                 }
             };
             println!("{}", serde_json::to_string(&Event::File(event)).unwrap());
@@ -343,4 +363,138 @@ impl Report {
             println!("</file>");
         }
     }
+
+    /// Print report as a standard unified diff: `---`/`+++` file headers
+    /// ahead of each modified file's hunks, with no other commentary, so the
+    /// output can be piped straight into `git apply`/`patch`. Errors go to
+    /// stderr instead of corrupting the patch stream; unmodified or skipped
+    /// files produce nothing. `redactions` runs over the `---`/`+++` paths
+    /// the same way it already runs over each file's diff body, so a path
+    /// under a volatile temp dir doesn't churn a committed golden fixture.
+    pub fn print_patch(&self, redactions: &[(String, String)]) {
+        if let Some(msg) = &self.policy_violation {
+            eprintln!("Policy Error: {}", msg);
+        }
+        let rules = crate::diff::redaction_rules(redactions);
+        for file in &self.files {
+            if let Some(err) = &file.error {
+                eprintln!("  {}: ERROR - {}", file.path.display(), err);
+                continue;
+            }
+            if let Some(diff) = &file.diff {
+                let display = crate::diff::apply_redactions(&file.path.display().to_string(), &rules);
+                println!("--- a/{0}\n+++ b/{0}", display);
+                print!("{}", diff);
+            }
+        }
+    }
+
+    /// Print report as ripgrep-compatible (`rg --json`) newline-delimited
+    /// JSON: a `begin` event, one `match` event per applied replacement,
+    /// and a terminating `end` event with per-file stats, for every file.
+    pub fn print_json_lines(&self) {
+        let elapsed = self.duration_ms as f64 / 1000.0;
+        for file in &self.files {
+            let path = RgJsonText { text: file.path.display().to_string() };
+            println!(
+                "{}",
+                serde_json::to_string(&RgJsonEvent::Begin { data: RgJsonPathData { path: path.clone() } }).unwrap()
+            );
+
+            let edits = file.edits.as_deref().unwrap_or(&[]);
+            for edit in edits {
+                let data = RgJsonMatchData {
+                    path: path.clone(),
+                    line_number: edit.line_number,
+                    absolute_offset: edit.start,
+                    submatches: vec![RgJsonSubmatch {
+                        matched: edit.matched.clone(),
+                        replacement: edit.replacement.clone(),
+                        start: edit.start,
+                        end: edit.end,
+                    }],
+                };
+                println!("{}", serde_json::to_string(&RgJsonEvent::Match { data }).unwrap());
+            }
+
+            let matched_lines = edits.iter().map(|e| e.line_number).collect::<std::collections::BTreeSet<_>>().len();
+            let bytes = edits.iter().map(|e| e.end - e.start).sum();
+            let end = RgJsonEvent::End {
+                data: RgJsonEndData {
+                    path,
+                    stats: RgJsonStats { matches: edits.len(), matched_lines, bytes, elapsed },
+                },
+            };
+            println!("{}", serde_json::to_string(&end).unwrap());
+        }
+    }
+
+    /// Print report as terse tab-separated lines for shell scripting: one
+    /// line per file (`modified\t<path>\t<replacements>`,
+    /// `unmodified\t<path>\t<replacements>`, `skipped\t<path>\t<reason>`, or
+    /// `error\t<path>\t<message>`), then a single summary line
+    /// (`files=N modified=M replacements=R errors=true|false`). Splits files
+    /// into the same success/skipped/error cases `print_json` does, so the
+    /// two formats stay in lockstep; there's no `run_start` line to skip
+    /// over first.
+    pub fn print_shell(&self) {
+        for file in &self.files {
+            if let Some(err) = &file.error {
+                println!("error\t{}\t{}", file.path.display(), err);
+            } else if let Some(reason) = &file.skipped {
+                println!("skipped\t{}\t{}", file.path.display(), reason);
+            } else if file.modified {
+                println!("modified\t{}\t{}", file.path.display(), file.replacements);
+            } else {
+                println!("unmodified\t{}\t{}", file.path.display(), file.replacements);
+            }
+        }
+        println!(
+            "files={} modified={} replacements={} errors={}",
+            self.total, self.modified, self.replacements, self.has_errors
+        );
+    }
+
+    /// Print report as NUL-terminated records, one per file:
+    /// `<path>\t<modified>\t<replacements>\0`. Like `print_shell`'s file
+    /// lines but NUL- rather than newline-terminated, so paths containing
+    /// spaces or embedded newlines still parse unambiguously downstream
+    /// (e.g. `xargs -0`). Complements `--files0` on the input side.
+    pub fn print_files0(&self) {
+        let mut stdout = std::io::stdout();
+        for file in &self.files {
+            write!(stdout, "{}\t{}\t{}\0", file.path.display(), file.modified, file.replacements).unwrap();
+        }
+        stdout.flush().unwrap();
+    }
+}
+
+/// Convert [`crate::diff::DiffHunk`]s into the wire-format [`DiffHunkData`]
+/// consumed by `Report::print_json`. Hunks that fail to parse (shouldn't
+/// happen — these are always our own [`crate::diff::unified_diff`] output)
+/// are silently dropped rather than failing the whole report.
+fn structured_diff_hunks(hunks: &[crate::diff::DiffHunk]) -> Vec<DiffHunkData> {
+    hunks
+        .iter()
+        .filter_map(|hunk| crate::diff::structure_hunk(hunk).ok())
+        .map(|structured| DiffHunkData {
+            old_start: structured.old_start,
+            old_lines: structured.old_lines,
+            new_start: structured.new_start,
+            new_lines: structured.new_lines,
+            lines: structured
+                .lines
+                .into_iter()
+                .map(|line| DiffLineData {
+                    tag: match line.tag {
+                        crate::diff::LineTag::Context => DiffLineTag::Context,
+                        crate::diff::LineTag::Removed => DiffLineTag::Removed,
+                        crate::diff::LineTag::Added => DiffLineTag::Added,
+                        crate::diff::LineTag::NoNewline => DiffLineTag::NoNewline,
+                    },
+                    text: line.text,
+                })
+                .collect(),
+        })
+        .collect()
 }
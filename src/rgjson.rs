@@ -31,9 +31,8 @@ pub enum RgKind {
 #[derive(Debug, Deserialize)]
 pub struct RgData {
     pub path: Option<RgTextOrBytes>,
-    // These fields are part of the ripgrep JSON schema but not directly used by stedi's current replacement logic.
-    // Kept for schema compliance and potential future use (e.g., verbose reporting, validation).
-    #[allow(dead_code)]
+    /// The full matched line's text, used by `input::read_rg_json` to build
+    /// a conflict-detection anchor (see [`crate::input::RipgrepAnchor`]).
     #[serde(default)]
     pub lines: Option<RgTextOrBytes>,
     #[allow(dead_code)]
@@ -63,6 +62,10 @@ pub struct RgSubmatch {
 pub enum RgTextOrBytes {
     Text { text: String },
     Bytes { bytes: String },
+    /// Newer search-tool protocols inline match/line content as a raw JSON
+    /// array of byte integers instead of a tagged `{text: ...}`/`{bytes: ...}`
+    /// object. Accepted transparently alongside the other two shapes.
+    Array(Vec<u8>),
 }
 
 impl RgTextOrBytes {
@@ -77,6 +80,7 @@ impl RgTextOrBytes {
                     .map_err(|e| anyhow!("base64 decode failed: {e}"))?;
                 Ok(Cow::Owned(raw))
             }
+            Self::Array(bytes) => Ok(Cow::Owned(bytes.clone())),
         }
     }
 
@@ -86,7 +90,7 @@ impl RgTextOrBytes {
     pub fn as_string_lossy(&self) -> Result<Cow<'_, str>> {
         match self {
             Self::Text { text } => Ok(Cow::Borrowed(text)),
-            Self::Bytes { .. } => {
+            Self::Bytes { .. } | Self::Array(_) => {
                 let raw = self.as_bytes()?;
                 match raw {
                     Cow::Borrowed(b) => Ok(String::from_utf8_lossy(b)),
@@ -176,7 +180,11 @@ impl DeinterleavingSink {
 impl RgSink for DeinterleavingSink {
     fn handle(&mut self, msg: RgMessage) -> Result<()> {
         match msg.kind {
-            RgKind::Match | RgKind::Context => {
+            // Only `match` records carry submatches we can turn into edits.
+            // `context` lines have no submatches and would only pad the
+            // per-path event list; `begin`/`end`/`summary` are bookkeeping
+            // records with no bearing on byte ranges, so both are ignored.
+            RgKind::Match => {
                 if let Some(data) = msg.data {
                      if let Some(ref path_obj) = data.path {
                          let path = path_obj.to_os_string()?;
@@ -185,8 +193,9 @@ impl RgSink for DeinterleavingSink {
                 }
                 Ok(())
             }
-            _ => Ok(()),
+            RgKind::Begin | RgKind::Context | RgKind::End | RgKind::Summary => Ok(()),
         }
     }
 }
 
+
@@ -0,0 +1,215 @@
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// One machine-applicable edit extracted from a rustc/clippy diagnostic
+/// span: the exact byte range to replace and the text to put there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustfixSpan {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub replacement: String,
+}
+
+/// One file's surviving (non-overlapping) rustfix edits, ready to apply.
+/// Mirrors [`crate::diff::FilePatch`]'s shape for the `--patch` input mode.
+#[derive(Debug, Clone)]
+pub struct RustfixPatch {
+    pub path: PathBuf,
+    pub spans: Vec<RustfixSpan>,
+}
+
+/// One `cargo build`/`cargo clippy --message-format=json` line. Only
+/// `"reason": "compiler-message"` records carry a diagnostic; every other
+/// reason (`compiler-artifact`, `build-script-executed`, ...) is bookkeeping
+/// with no spans and is skipped.
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<Diagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diagnostic {
+    #[serde(default)]
+    spans: Vec<DiagnosticSpan>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiagnosticSpan {
+    file_name: PathBuf,
+    byte_start: usize,
+    byte_end: usize,
+    #[serde(default)]
+    suggested_replacement: Option<String>,
+    #[serde(default)]
+    suggestion_applicability: Option<String>,
+}
+
+/// Parse a `cargo build`/`cargo clippy --message-format=json` NDJSON stream,
+/// grouping every `MachineApplicable` suggestion by the file it targets.
+///
+/// Within a file, edits are sorted by `byte_start` descending and any whose
+/// range overlaps one already kept is dropped, keeping the one with the
+/// higher start — unlike `input::normalize_ranges`, which rejects
+/// overlapping `--rg-json` submatches outright — so the survivors can be
+/// spliced in back-to-front without shifting earlier offsets. Lines that aren't a
+/// `compiler-message` record, or carry no `MachineApplicable` suggestion,
+/// are skipped rather than rejected.
+pub fn parse_rustfix<R: BufRead>(reader: R) -> Result<Vec<RustfixPatch>> {
+    let mut by_file: BTreeMap<PathBuf, Vec<RustfixSpan>> = BTreeMap::new();
+
+    for line in reader.lines() {
+        let line = line.map_err(Error::Io)?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Ok(msg) = serde_json::from_str::<CargoMessage>(line) else {
+            continue;
+        };
+        if msg.reason != "compiler-message" {
+            continue;
+        }
+        let Some(diag) = msg.message else {
+            continue;
+        };
+
+        for span in diag.spans {
+            if span.suggestion_applicability.as_deref() != Some("MachineApplicable") {
+                continue;
+            }
+            let Some(replacement) = span.suggested_replacement else {
+                continue;
+            };
+            by_file.entry(span.file_name).or_default().push(RustfixSpan {
+                byte_start: span.byte_start,
+                byte_end: span.byte_end,
+                replacement,
+            });
+        }
+    }
+
+    let mut patches = Vec::with_capacity(by_file.len());
+    for (path, mut spans) in by_file {
+        spans.sort_by(|a, b| b.byte_start.cmp(&a.byte_start));
+        let mut kept: Vec<RustfixSpan> = Vec::with_capacity(spans.len());
+        for span in spans {
+            if let Some(prev) = kept.last() {
+                if span.byte_end > prev.byte_start {
+                    continue;
+                }
+            }
+            kept.push(span);
+        }
+        patches.push(RustfixPatch { path, spans: kept });
+    }
+
+    Ok(patches)
+}
+
+/// Apply every surviving edit in `patch` to `original`, splicing from the
+/// bottom of the file up (spans are already sorted descending by
+/// `byte_start` and non-overlapping; see [`parse_rustfix`]), and return the
+/// patched content and the number of edits applied.
+///
+/// A span whose range is no longer valid UTF-8 char boundaries, or that's
+/// out of bounds, means the file has changed since the diagnostic was
+/// generated; it's skipped rather than risking a corrupt splice.
+pub fn apply_rustfix(original: &str, patch: &RustfixPatch) -> (String, usize) {
+    let mut content = original.to_string();
+    let mut applied = 0usize;
+
+    for span in &patch.spans {
+        if span.byte_start > span.byte_end || span.byte_end > content.len() {
+            continue;
+        }
+        if !content.is_char_boundary(span.byte_start) || !content.is_char_boundary(span.byte_end) {
+            continue;
+        }
+        content.replace_range(span.byte_start..span.byte_end, &span.replacement);
+        applied += 1;
+    }
+
+    (content, applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cargo_message(file: &str, byte_start: usize, byte_end: usize, replacement: &str, applicability: &str) -> String {
+        format!(
+            r#"{{"reason":"compiler-message","message":{{"spans":[{{"file_name":"{file}","byte_start":{byte_start},"byte_end":{byte_end},"suggested_replacement":"{replacement}","suggestion_applicability":"{applicability}"}}]}}}}"#
+        )
+    }
+
+    #[test]
+    fn parse_rustfix_skips_non_compiler_message_lines() {
+        let input = "{\"reason\":\"compiler-artifact\"}\n";
+        let patches = parse_rustfix(input.as_bytes()).unwrap();
+        assert!(patches.is_empty());
+    }
+
+    #[test]
+    fn parse_rustfix_skips_non_machine_applicable_suggestions() {
+        let line = cargo_message("src/lib.rs", 0, 3, "foo", "MaybeIncorrect");
+        let patches = parse_rustfix(line.as_bytes()).unwrap();
+        assert!(patches.is_empty());
+    }
+
+    #[test]
+    fn parse_rustfix_groups_machine_applicable_suggestions_by_file() {
+        let input = format!(
+            "{}\n{}\n",
+            cargo_message("src/lib.rs", 0, 3, "foo", "MachineApplicable"),
+            cargo_message("src/lib.rs", 10, 13, "bar", "MachineApplicable"),
+        );
+        let patches = parse_rustfix(input.as_bytes()).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].path, PathBuf::from("src/lib.rs"));
+        assert_eq!(patches[0].spans.len(), 2);
+        // Sorted descending by byte_start.
+        assert_eq!(patches[0].spans[0].byte_start, 10);
+        assert_eq!(patches[0].spans[1].byte_start, 0);
+    }
+
+    #[test]
+    fn parse_rustfix_drops_overlapping_suggestion_keeping_higher_start() {
+        let input = format!(
+            "{}\n{}\n",
+            cargo_message("src/lib.rs", 0, 5, "foo", "MachineApplicable"),
+            cargo_message("src/lib.rs", 3, 8, "bar", "MachineApplicable"),
+        );
+        let patches = parse_rustfix(input.as_bytes()).unwrap();
+        assert_eq!(patches[0].spans.len(), 1);
+        assert_eq!(patches[0].spans[0].byte_start, 3);
+    }
+
+    #[test]
+    fn apply_rustfix_splices_every_span() {
+        let patch = RustfixPatch {
+            path: PathBuf::from("x.rs"),
+            spans: vec![
+                RustfixSpan { byte_start: 6, byte_end: 9, replacement: "bar".into() },
+                RustfixSpan { byte_start: 0, byte_end: 3, replacement: "baz".into() },
+            ],
+        };
+        let (new_content, applied) = apply_rustfix("foo = foo", &patch);
+        assert_eq!(applied, 2);
+        assert_eq!(new_content, "baz = bar");
+    }
+
+    #[test]
+    fn apply_rustfix_skips_out_of_bounds_span() {
+        let patch = RustfixPatch {
+            path: PathBuf::from("x.rs"),
+            spans: vec![RustfixSpan { byte_start: 0, byte_end: 100, replacement: "x".into() }],
+        };
+        let (new_content, applied) = apply_rustfix("short", &patch);
+        assert_eq!(applied, 0);
+        assert_eq!(new_content, "short");
+    }
+}
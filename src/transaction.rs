@@ -0,0 +1,126 @@
+use crate::write::StagedEntry;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// Coordinates the final commit of a `--transaction all` run.
+///
+/// `write::stage_file` has already written every modified file's new
+/// contents to a sibling temp file in its target directory by the time an
+/// entry reaches [`TransactionManager::stage`]. What's missing is making the
+/// *set* of renames atomic: if any one of them can't be completed, none of
+/// the originals should end up changed.
+///
+/// This is implemented as a backup-then-swap: each target is first moved
+/// aside to a same-directory backup path (a cheap, same-filesystem rename),
+/// then every staged temp file is renamed into place. If any rename in
+/// either phase fails, every backup made so far is renamed back over its
+/// target, which undoes the swap regardless of whether that particular
+/// target had already been updated.
+pub struct TransactionManager {
+    entries: Vec<StagedEntry>,
+}
+
+struct Pending {
+    target: PathBuf,
+    backup: PathBuf,
+    temp: NamedTempFile,
+    durable: bool,
+    /// Pre-edit safety copy made for the user (`--backup-suffix`), distinct
+    /// from `backup` above (this transaction's own swap backup). Cleaned up
+    /// on rollback, since the swap backup already restores the original.
+    content_backup: Option<PathBuf>,
+}
+
+impl TransactionManager {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Queue an already-staged temp file to be committed as part of this
+    /// transaction's final swap.
+    pub fn stage(&mut self, entry: StagedEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Commit every staged entry, or none at all.
+    pub fn commit(self) -> Result<(), String> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut pending: Vec<Pending> = Vec::with_capacity(self.entries.len());
+
+        // Phase 1: move every target aside to a backup sibling so the swap
+        // can be undone no matter how far phase 2 gets.
+        for entry in self.entries {
+            let durable = entry.durable();
+            let content_backup = entry.backup_path();
+            let (temp, target) = entry.into_parts();
+            let backup = backup_path(&target);
+            if let Err(e) = fs::rename(&target, &backup) {
+                rollback(&pending);
+                return Err(format!(
+                    "atomic file swap creation failure: could not stage {} for commit: {}",
+                    target.display(),
+                    e
+                ));
+            }
+            pending.push(Pending { target, backup, temp, durable, content_backup });
+        }
+
+        // Phase 2: rename every staged temp file into place.
+        for p in &pending {
+            if let Err(e) = fs::rename(p.temp.path(), &p.target) {
+                rollback(&pending);
+                return Err(format!(
+                    "commit rename failed for {}: {}",
+                    p.target.display(),
+                    e
+                ));
+            }
+        }
+
+        // Every target has its new content; the backups are no longer needed.
+        for p in &pending {
+            let _ = fs::remove_file(&p.backup);
+        }
+
+        // Durability: fsync each distinct parent directory once, after every
+        // rename in the batch has already succeeded, so a single crash-safe
+        // pass covers the whole transaction instead of one fsync per file.
+        let durable_dirs: HashSet<PathBuf> = pending
+            .iter()
+            .filter(|p| p.durable)
+            .filter_map(|p| p.target.parent().map(Path::to_path_buf))
+            .collect();
+        for dir in &durable_dirs {
+            let _ = crate::write::fsync_dir(dir);
+        }
+
+        Ok(())
+    }
+}
+
+/// Restore every target to its pre-commit contents by renaming its backup
+/// back over it. Safe to call whether or not phase 2 reached a given entry:
+/// if it didn't, `target` doesn't exist yet (phase 1 moved it to `backup`)
+/// and the rename simply recreates it; if it did, the rename overwrites the
+/// newly-swapped-in content with the original.
+fn rollback(pending: &[Pending]) {
+    for p in pending.iter().rev() {
+        let _ = fs::rename(&p.backup, &p.target);
+        if let Some(content_backup) = &p.content_backup {
+            let _ = fs::remove_file(content_backup);
+        }
+    }
+}
+
+fn backup_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(format!(".txed-tx-{}", std::process::id()));
+    target.with_file_name(name)
+}
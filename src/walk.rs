@@ -0,0 +1,68 @@
+use crate::model::Symlinks;
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+
+/// Options controlling recursive directory expansion, mirroring the
+/// `--recursive`/`--hidden`/`--no-ignore`/`--max-depth` CLI flags.
+pub struct WalkOptions {
+    pub hidden: bool,
+    pub no_ignore: bool,
+    pub max_depth: Option<usize>,
+    pub symlinks: Symlinks,
+}
+
+/// A directory entry the walker couldn't visit (permission denied, a
+/// dangling symlink loop, etc.), paired with the path it happened under so
+/// callers can surface it as a per-file error instead of losing the detail.
+pub struct WalkError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Expand a list of positional paths into a flat file list: directories are
+/// walked recursively per `opts`, honoring VCS ignore rules unless
+/// `no_ignore` is set; plain file paths pass through unchanged. Per-entry
+/// walk failures (e.g. permission denied on a subdirectory) don't abort the
+/// expansion; they're returned alongside the files that were found so the
+/// caller can report them as file-level errors.
+pub fn expand_paths(paths: &[PathBuf], opts: &WalkOptions) -> (Vec<PathBuf>, Vec<WalkError>) {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk_dir(path, opts, &mut files, &mut errors);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    (files, errors)
+}
+
+fn walk_dir(root: &Path, opts: &WalkOptions, files: &mut Vec<PathBuf>, errors: &mut Vec<WalkError>) {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!opts.hidden)
+        .ignore(!opts.no_ignore)
+        .git_ignore(!opts.no_ignore)
+        .git_global(!opts.no_ignore)
+        .git_exclude(!opts.no_ignore)
+        .parents(!opts.no_ignore)
+        .follow_links(opts.symlinks == Symlinks::Follow)
+        .max_depth(opts.max_depth);
+
+    for entry in builder.build() {
+        match entry {
+            Ok(entry) => {
+                // Directories (including `root` itself) are yielded by the
+                // walker too; only files feed the edit pipeline.
+                if entry.file_type().is_some_and(|ft| ft.is_file()) {
+                    files.push(entry.into_path());
+                }
+            }
+            Err(e) => {
+                let path = e.path().map(Path::to_path_buf).unwrap_or_else(|| root.to_path_buf());
+                errors.push(WalkError { path, message: e.to_string() });
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::engine;
+use crate::error::{Error, Result};
+use crate::input::InputItem;
+use crate::model::Pipeline;
+use crate::reporter::Report;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+/// Paths a given `InputItem` resolves to on disk, i.e. what `--watch` should
+/// register with the filesystem notifier. `StdinText` has none, since it
+/// isn't backed by a file.
+fn watched_path(item: &InputItem) -> Option<&std::path::Path> {
+    match item {
+        InputItem::Path(p) => Some(p.as_path()),
+        InputItem::RipgrepMatch { path, .. } => Some(path.as_path()),
+        InputItem::EditPlan { path, .. } => Some(path.as_path()),
+        InputItem::StdinText(_) => None,
+    }
+}
+
+/// Run `pipeline` against `inputs` once, then keep re-running it every time
+/// one of the resolved input paths changes on disk, until interrupted
+/// (Ctrl-C) or the watcher's channel closes. Each cycle reuses `execute`
+/// as-is, so the same glob/symlink/binary gating and policy checks apply
+/// every time; `on_report` is called once per cycle with the full first
+/// report, then with a report containing only the files that were
+/// modified, skipped, or errored on that cycle.
+pub fn run(pipeline: &Pipeline, inputs: Vec<InputItem>, debounce: Duration, mut on_report: impl FnMut(&Report)) -> Result<()> {
+    let paths: Vec<PathBuf> = inputs.iter().filter_map(|item| watched_path(item).map(|p| p.to_path_buf())).collect();
+    if paths.is_empty() {
+        return Err(Error::Validation("--watch requires at least one file path input".into()));
+    }
+
+    let (tx, rx) = channel();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|e| Error::Validation(format!("Failed to start file watcher: {}", e)))?;
+
+    for path in &paths {
+        watcher
+            .watch(path, RecursiveMode::NonRecursive)
+            .map_err(|e| Error::Validation(format!("Failed to watch {:?}: {}", path, e)))?;
+    }
+
+    on_report(&engine::execute(pipeline.clone(), inputs.clone(), Vec::new())?);
+
+    loop {
+        // Block for the first change, then drain whatever else arrives
+        // within the debounce window so a burst of writes/renames from one
+        // save only triggers a single re-run.
+        if rx.recv().is_err() {
+            return Ok(());
+        }
+        while rx.recv_timeout(debounce).is_ok() {}
+
+        let mut report = engine::execute(pipeline.clone(), inputs.clone(), Vec::new())?;
+        report.files.retain(|f| f.modified || f.error.is_some() || f.skipped.is_some());
+        on_report(&report);
+    }
+}
@@ -0,0 +1,416 @@
+use crate::error::{Error, Result};
+use globset::Glob;
+use std::path::Path;
+
+/// A parsed `--when` expression, modeled on Cargo's `cfg(...)` syntax
+/// combined with key/operator/value comparisons:
+///
+/// ```text
+/// expr  := pred | "all" "(" list ")" | "any" "(" list ")" | "not" "(" expr ")"
+/// list  := expr ("," expr)*
+/// pred  := key op value
+/// key   := "ext" | "path" | "contains" | "size"
+/// op    := "=" | ">" | "<" | ">=" | "<="
+/// value := string | number
+/// ```
+///
+/// Supported predicates: `ext = "rs"` (file extension, case-insensitive),
+/// `path = "glob:**/src/**"` (glob match against the full path; a value
+/// without the `glob:` prefix is matched verbatim), `contains = "TODO"`
+/// (substring search over the already-read file content), and `size >
+/// 4096` (file byte length; `=`, `>`, `<`, `>=`, `<=` are all valid, and
+/// the number may carry a `k`/`m`/`g` suffix for KiB/MiB/GiB). Gates
+/// whether a file is edited, combining path, content, and size checks
+/// that `--glob-include`/`--glob-exclude` alone can't express.
+#[derive(Debug, Clone)]
+pub enum WhenExpr {
+    All(Vec<WhenExpr>),
+    Any(Vec<WhenExpr>),
+    Not(Box<WhenExpr>),
+    Pred { key: String, op: Op, val: Value },
+}
+
+/// Comparison operator for a leaf predicate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+}
+
+/// Right-hand side of a leaf predicate.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Str(String),
+    Num(u64),
+}
+
+/// Per-file facts a `WhenExpr` is evaluated against.
+pub struct FileContext<'a> {
+    pub path: &'a Path,
+    pub content: &'a str,
+    pub len: u64,
+}
+
+impl WhenExpr {
+    /// Parse a `--when` expression, consuming the whole string.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parser = Parser::new(input);
+        let expr = parser.parse_expr()?;
+        parser.expect_end()?;
+        Ok(expr)
+    }
+
+    /// Evaluate this predicate against a file. `all`/`any` over an empty
+    /// list are `true`/`false` respectively (Rust's `Iterator::all`/`any`
+    /// already behave this way on an empty iterator), and both
+    /// short-circuit, so a `contains` leaf later in the list never reads
+    /// the buffer once an earlier, cheaper leaf has already decided the
+    /// result.
+    pub fn eval(&self, ctx: &FileContext) -> bool {
+        match self {
+            WhenExpr::All(exprs) => exprs.iter().all(|e| e.eval(ctx)),
+            WhenExpr::Any(exprs) => exprs.iter().any(|e| e.eval(ctx)),
+            WhenExpr::Not(inner) => !inner.eval(ctx),
+            WhenExpr::Pred { key, op, val } => eval_pred(key, *op, val, ctx),
+        }
+    }
+}
+
+fn eval_pred(key: &str, op: Op, val: &Value, ctx: &FileContext) -> bool {
+    match key {
+        "ext" => {
+            let Value::Str(want) = val else { return false };
+            ctx.path.extension().is_some_and(|e| e.eq_ignore_ascii_case(want))
+        }
+        "path" => {
+            let Value::Str(want) = val else { return false };
+            match want.strip_prefix("glob:") {
+                Some(glob) => Glob::new(glob)
+                    .map(|g| g.compile_matcher().is_match(ctx.path))
+                    .unwrap_or(false),
+                None => ctx.path == Path::new(want),
+            }
+        }
+        "contains" => {
+            let Value::Str(needle) = val else { return false };
+            ctx.content.contains(needle.as_str())
+        }
+        "size" => {
+            let Value::Num(n) = val else { return false };
+            match op {
+                Op::Eq => ctx.len == *n,
+                Op::Gt => ctx.len > *n,
+                Op::Lt => ctx.len < *n,
+                Op::Ge => ctx.len >= *n,
+                Op::Le => ctx.len <= *n,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Parse a size like `10k`/`4M`/`1g`/`512` (no suffix means bytes) into a
+/// byte count, using 1024-based multipliers.
+fn parse_size(s: &str) -> Result<u64> {
+    let (num_part, mult) = match s.chars().last() {
+        Some('k') | Some('K') => (&s[..s.len() - 1], 1024),
+        Some('m') | Some('M') => (&s[..s.len() - 1], 1024 * 1024),
+        Some('g') | Some('G') => (&s[..s.len() - 1], 1024 * 1024 * 1024),
+        _ => (s, 1),
+    };
+    let n: u64 = num_part.trim().parse().map_err(|_| {
+        Error::Validation(format!(
+            "invalid size '{}' in --when expression: expected a number optionally suffixed with k, m, or g",
+            s
+        ))
+    })?;
+    Ok(n * mult)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.char_indices().peekable(),
+            input,
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn expect_char(&mut self, expected: char) -> Result<()> {
+        self.skip_ws();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((i, c)) => Err(Error::Validation(format!(
+                "expected '{}' at offset {} in --when expression, found '{}'",
+                expected, i, c
+            ))),
+            None => Err(Error::Validation(format!(
+                "expected '{}' but --when expression ended",
+                expected
+            ))),
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<()> {
+        self.skip_ws();
+        match self.chars.peek() {
+            None => Ok(()),
+            Some(&(i, c)) => Err(Error::Validation(format!(
+                "unexpected trailing '{}' at offset {} in --when expression",
+                c, i
+            ))),
+        }
+    }
+
+    fn read_ident(&mut self) -> Result<String> {
+        self.skip_ws();
+        let start = match self.chars.peek() {
+            Some(&(i, c)) if c.is_alphabetic() || c == '_' => i,
+            _ => return Err(Error::Validation("expected an identifier in --when expression".into())),
+        };
+        let mut end = start;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                end = i + c.len_utf8();
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        Ok(self.input[start..end].to_string())
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        self.expect_char('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((_, c)) => s.push(c),
+                None => return Err(Error::Validation("unterminated string literal in --when expression".into())),
+            }
+        }
+    }
+
+    fn read_number_token(&mut self) -> Result<String> {
+        self.skip_ws();
+        let start = match self.chars.peek() {
+            Some(&(i, c)) if c.is_ascii_digit() => i,
+            _ => return Err(Error::Validation("expected a number in --when expression".into())),
+        };
+        let mut end = start;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if c.is_ascii_digit() {
+                end = i + c.len_utf8();
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        if let Some(&(i, c)) = self.chars.peek() {
+            if matches!(c, 'k' | 'K' | 'm' | 'M' | 'g' | 'G') {
+                end = i + c.len_utf8();
+                self.chars.next();
+            }
+        }
+        Ok(self.input[start..end].to_string())
+    }
+
+    fn parse_op(&mut self) -> Result<Op> {
+        self.skip_ws();
+        match self.chars.peek().copied() {
+            Some((_, '=')) => {
+                self.chars.next();
+                Ok(Op::Eq)
+            }
+            Some((_, '>')) => {
+                self.chars.next();
+                if self.chars.peek().map(|&(_, c)| c) == Some('=') {
+                    self.chars.next();
+                    Ok(Op::Ge)
+                } else {
+                    Ok(Op::Gt)
+                }
+            }
+            Some((_, '<')) => {
+                self.chars.next();
+                if self.chars.peek().map(|&(_, c)| c) == Some('=') {
+                    self.chars.next();
+                    Ok(Op::Le)
+                } else {
+                    Ok(Op::Lt)
+                }
+            }
+            Some((i, c)) => Err(Error::Validation(format!(
+                "expected a comparison operator (=, >, <, >=, <=) at offset {} in --when expression, found '{}'",
+                i, c
+            ))),
+            None => Err(Error::Validation(
+                "expected a comparison operator but --when expression ended".into(),
+            )),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<WhenExpr> {
+        let key = self.read_ident()?;
+        match key.as_str() {
+            "not" => {
+                self.expect_char('(')?;
+                let inner = self.parse_expr()?;
+                self.expect_char(')')?;
+                Ok(WhenExpr::Not(Box::new(inner)))
+            }
+            "all" | "any" => {
+                self.expect_char('(')?;
+                let mut exprs = vec![self.parse_expr()?];
+                while self.peek_char() == Some(',') {
+                    self.chars.next();
+                    exprs.push(self.parse_expr()?);
+                }
+                self.expect_char(')')?;
+                if key == "all" {
+                    Ok(WhenExpr::All(exprs))
+                } else {
+                    Ok(WhenExpr::Any(exprs))
+                }
+            }
+            "ext" | "contains" => {
+                let op = self.parse_op()?;
+                if op != Op::Eq {
+                    return Err(Error::Validation(format!(
+                        "'{}' only supports '=' in --when expression",
+                        key
+                    )));
+                }
+                let value = self.read_string()?;
+                Ok(WhenExpr::Pred { key, op, val: Value::Str(value) })
+            }
+            "path" => {
+                let op = self.parse_op()?;
+                if op != Op::Eq {
+                    return Err(Error::Validation("'path' only supports '=' in --when expression".into()));
+                }
+                let value = self.read_string()?;
+                if let Some(glob) = value.strip_prefix("glob:") {
+                    Glob::new(glob).map_err(|e| {
+                        Error::Validation(format!("invalid glob '{}' in --when expression: {}", glob, e))
+                    })?;
+                }
+                Ok(WhenExpr::Pred { key, op, val: Value::Str(value) })
+            }
+            "size" => {
+                let op = self.parse_op()?;
+                let token = self.read_number_token()?;
+                let n = parse_size(&token)?;
+                Ok(WhenExpr::Pred { key, op, val: Value::Num(n) })
+            }
+            other => Err(Error::Validation(format!(
+                "unknown --when predicate '{}' (expected ext, path, contains, size, not, all, or any)",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn ctx<'a>(path: &'a Path, content: &'a str) -> FileContext<'a> {
+        FileContext { path, content, len: content.len() as u64 }
+    }
+
+    #[test]
+    fn parses_and_matches_path_glob() {
+        let expr = WhenExpr::parse("path = \"glob:src/**/*.rs\"").unwrap();
+        assert!(expr.eval(&ctx(&PathBuf::from("src/main.rs"), "")));
+        assert!(!expr.eval(&ctx(&PathBuf::from("tests/main.rs"), "")));
+    }
+
+    #[test]
+    fn parses_and_matches_path_verbatim() {
+        let expr = WhenExpr::parse("path = \"src/main.rs\"").unwrap();
+        assert!(expr.eval(&ctx(&PathBuf::from("src/main.rs"), "")));
+        assert!(!expr.eval(&ctx(&PathBuf::from("src/other.rs"), "")));
+    }
+
+    #[test]
+    fn parses_and_matches_ext() {
+        let expr = WhenExpr::parse("ext = \"rs\"").unwrap();
+        assert!(expr.eval(&ctx(&PathBuf::from("main.rs"), "")));
+        assert!(!expr.eval(&ctx(&PathBuf::from("main.toml"), "")));
+    }
+
+    #[test]
+    fn parses_and_matches_contains() {
+        let expr = WhenExpr::parse("contains = \"@generated\"").unwrap();
+        assert!(expr.eval(&ctx(&PathBuf::from("a.rs"), "// @generated\n")));
+        assert!(!expr.eval(&ctx(&PathBuf::from("a.rs"), "// hand-written\n")));
+    }
+
+    #[test]
+    fn parses_and_matches_size_comparisons() {
+        let big = "x".repeat(11 * 1024);
+        let small = "x".repeat(1024);
+
+        let gt = WhenExpr::parse("size > 10k").unwrap();
+        assert!(gt.eval(&ctx(&PathBuf::from("a.rs"), &big)));
+        assert!(!gt.eval(&ctx(&PathBuf::from("a.rs"), &small)));
+
+        let le = WhenExpr::parse("size <= 1024").unwrap();
+        assert!(le.eval(&ctx(&PathBuf::from("a.rs"), &small)));
+        assert!(!le.eval(&ctx(&PathBuf::from("a.rs"), &big)));
+    }
+
+    #[test]
+    fn combines_all_not() {
+        let expr = WhenExpr::parse("all(path = \"glob:src/**/*.rs\", not(contains = \"@generated\"))").unwrap();
+        assert!(expr.eval(&ctx(&PathBuf::from("src/main.rs"), "fn main() {}")));
+        assert!(!expr.eval(&ctx(&PathBuf::from("src/main.rs"), "// @generated\n")));
+        assert!(!expr.eval(&ctx(&PathBuf::from("tests/main.rs"), "fn main() {}")));
+    }
+
+    #[test]
+    fn empty_all_is_true_empty_any_is_false() {
+        assert!(WhenExpr::All(vec![]).eval(&ctx(&PathBuf::from("a"), "")));
+        assert!(!WhenExpr::Any(vec![]).eval(&ctx(&PathBuf::from("a"), "")));
+    }
+
+    #[test]
+    fn rejects_unknown_predicate() {
+        assert!(WhenExpr::parse("bogus = \"x\"").is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_operator_for_string_key() {
+        assert!(WhenExpr::parse("ext > \"rs\"").is_err());
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        assert!(WhenExpr::parse("ext = \"rs\" extra").is_err());
+    }
+}
@@ -1,17 +1,45 @@
 use crate::error::{Error, Result};
-use crate::model::PermissionsMode;
+use crate::model::{PermissionsMode, WriteStrategy};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Write;
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 
-/// Options for file writing.
+/// Files smaller than this are read with a normal buffered `fs::read`
+/// rather than mapped: mapping has fixed per-call overhead (syscalls, page
+/// table setup) that isn't worth paying for small inputs.
+pub const MMAP_MIN_SIZE: u64 = 64 * 1024;
+
+/// Options for file writing (and, via `read_file`, the matching read path).
 #[derive(Debug, Clone)]
 pub struct WriteOptions {
     /// If true, do not follow symbolic links (operate on symlink itself).
     pub no_follow_symlinks: bool,
     /// Permissions handling mode.
     pub permissions: PermissionsMode,
+    /// Force `read_file` to use a buffered read even for large files,
+    /// skipping the memory-mapped fast path entirely.
+    pub force_buffered_read: bool,
+    /// Files at or above this size are memory-mapped rather than read with
+    /// a buffered `fs::read`; see [`MMAP_MIN_SIZE`] for the default.
+    pub mmap_min_size: u64,
+    /// Best-effort: restore the original file's owner/group (Unix only).
+    pub preserve_ownership: bool,
+    /// Best-effort: restore the original file's mtime/atime.
+    pub preserve_timestamps: bool,
+    /// Best-effort: copy the original file's extended attributes (Unix only).
+    pub preserve_xattrs: bool,
+    /// Fsync the temp file before the rename and the containing directory
+    /// afterward, so the write survives a crash immediately after commit.
+    pub durable: bool,
+    /// If set, preserve the file's pre-edit bytes under a suffixed sibling
+    /// path before its replacement content is committed in its place.
+    pub backup: Option<BackupSpec>,
+    /// How the eventual write is committed; see [`WriteStrategy`].
+    pub write_strategy: WriteStrategy,
 }
 
 impl Default for WriteOptions {
@@ -19,29 +47,246 @@ impl Default for WriteOptions {
         Self {
             no_follow_symlinks: false,
             permissions: PermissionsMode::default(),
+            force_buffered_read: false,
+            mmap_min_size: MMAP_MIN_SIZE,
+            preserve_ownership: false,
+            preserve_timestamps: false,
+            preserve_xattrs: false,
+            durable: false,
+            backup: None,
+            write_strategy: WriteStrategy::default(),
+        }
+    }
+}
+
+/// Configuration for the safety copy made before a modified file's original
+/// content is replaced.
+#[derive(Debug, Clone)]
+pub struct BackupSpec {
+    /// Appended to the target's file name to form the backup path, e.g.
+    /// a suffix of `.bak` turns `file.txt` into `file.txt.bak`.
+    pub suffix: String,
+}
+
+impl BackupSpec {
+    fn backup_path(&self, target: &Path) -> PathBuf {
+        let mut name = target.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        name.push(&self.suffix);
+        target.with_file_name(name)
+    }
+}
+
+/// Whether each best-effort attribute-preservation step requested via
+/// `WriteOptions` actually succeeded, so callers can surface partial
+/// failures (e.g. `chown` returning `EPERM` for an unprivileged process)
+/// instead of silently dropping ownership. `None` means that attribute
+/// wasn't requested at all.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PreservationOutcome {
+    pub ownership: Option<bool>,
+    pub timestamps: Option<bool>,
+    pub xattrs: Option<bool>,
+}
+
+/// A file's contents as read for processing: either a full in-memory copy
+/// or a read-only memory map. Both deref to `[u8]` so callers can treat
+/// them identically.
+pub enum FileBytes {
+    Buffered(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Buffered(v) => v,
+            FileBytes::Mapped(m) => m,
         }
     }
 }
 
+/// Read a file's contents for processing, memory-mapping large regular
+/// files and falling back to a normal buffered read otherwise.
+///
+/// The buffered path is used for non-regular files (pipes, devices, and
+/// the like), empty files (mapping an empty file is invalid on most
+/// platforms), files smaller than `options.mmap_min_size` (defaults to
+/// [`MMAP_MIN_SIZE`]), and whenever `options.force_buffered_read` is set.
+/// `options.write_strategy == WriteStrategy::Mmap` forces the mapped path
+/// regardless of `mmap_min_size`, for zero-copy scanning of every file.
+///
+/// Callers must drop the returned `FileBytes` before staging a write to
+/// the *same* path: on Windows, renaming over a file with a live mapping
+/// fails, so nothing returned from here may still be alive when
+/// `stage_file`/`StagedEntry::commit` runs for that path. Processing these
+/// bytes into an owned output buffer before writing — as `engine::process_file`
+/// does — satisfies this naturally, since the mapping has no remaining
+/// references by the time the write is staged.
+pub fn read_file(path: &Path, options: &WriteOptions) -> Result<FileBytes> {
+    let metadata = fs::metadata(path)?;
+
+    let use_mmap = !options.force_buffered_read
+        && metadata.is_file()
+        && metadata.len() > 0
+        && (metadata.len() >= options.mmap_min_size
+            || options.write_strategy == WriteStrategy::Mmap);
+
+    if use_mmap {
+        let file = fs::File::open(path)?;
+        // SAFETY: this process does not concurrently truncate or write to
+        // `file` while the mapping is alive; the same external-modification
+        // hazard applies here as to a plain `fs::read` racing a writer.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(FileBytes::Mapped(mmap))
+    } else {
+        Ok(FileBytes::Buffered(fs::read(path)?))
+    }
+}
+
 /// A staged file write, ready to be committed.
 pub struct StagedEntry {
-    temp: NamedTempFile,
+    backend: StagedBackend,
     target: PathBuf,
+    preservation: PreservationOutcome,
+    durable: bool,
+    backup_path: Option<PathBuf>,
+}
+
+/// How a [`StagedEntry`] was written, mirroring [`WriteStrategy`].
+enum StagedBackend {
+    /// Content already sits in a sibling temp file; `commit` renames it
+    /// into place.
+    Atomic(NamedTempFile),
+    /// Content has already been written directly to the target (truncate
+    /// and rewrite); `commit` has nothing left to do.
+    InPlace,
 }
 
 impl StagedEntry {
-    /// Commit the staged file (atomic rename).
+    /// Commit the staged file. For `Atomic` entries this is the rename; if
+    /// durability was requested, the temp file was already `sync_all`'d by
+    /// `stage_file` before this runs, so here we additionally fsync the
+    /// parent directory afterward so the renamed directory entry itself
+    /// survives a crash (skipped on Windows, which has no directory-fsync
+    /// equivalent — durability there relies on the rename alone). `InPlace`
+    /// entries were already written in full by `stage_file`, so there's
+    /// nothing to do here.
     pub fn commit(self) -> Result<()> {
-        self.temp.persist(&self.target).map_err(|e| Error::Io(e.error))?;
+        match self.backend {
+            StagedBackend::Atomic(temp) => {
+                temp.persist(&self.target).map_err(|e| Error::Io(e.error))?;
+                if self.durable {
+                    fsync_parent_dir(&self.target)?;
+                }
+            }
+            StagedBackend::InPlace => {}
+        }
         Ok(())
     }
+
+    /// Whether each requested attribute-preservation step succeeded.
+    pub fn preservation(&self) -> PreservationOutcome {
+        self.preservation
+    }
+
+    /// Whether this entry was staged with `durable` writes requested.
+    pub(crate) fn durable(&self) -> bool {
+        self.durable
+    }
+
+    /// Path of the pre-edit safety copy created for this entry, if backups
+    /// were requested.
+    pub fn backup_path(&self) -> Option<PathBuf> {
+        self.backup_path.clone()
+    }
+
+    /// Split into the underlying temp file and its intended target path.
+    /// Used by the `transaction` module, which needs to drive the final
+    /// rename itself so it can coordinate an all-or-nothing commit across
+    /// every staged entry in a batch. Only `Atomic` entries have a temp
+    /// file to hand over; `--transaction all` rejects `WriteStrategy::InPlace`
+    /// up front (see `engine::execute`), so this never sees an `InPlace`
+    /// entry in practice.
+    pub(crate) fn into_parts(self) -> (NamedTempFile, PathBuf) {
+        match self.backend {
+            StagedBackend::Atomic(temp) => (temp, self.target),
+            StagedBackend::InPlace => unreachable!(
+                "WriteStrategy::InPlace is rejected together with --transaction all before any file is staged"
+            ),
+        }
+    }
+}
+
+/// Fsync the directory containing `path` so a just-renamed directory entry
+/// is durable. A no-op on Windows, which doesn't support opening and
+/// syncing a directory handle this way.
+pub(crate) fn fsync_parent_dir(path: &Path) -> Result<()> {
+    let parent = path.parent()
+        .ok_or_else(|| Error::InvalidPath(path.to_path_buf()))?;
+    fsync_dir(parent)
 }
 
-/// Prepare a file for writing (create temp, write content, copy perms).
+/// Fsync a directory itself (as opposed to a file within it). A no-op on
+/// Windows, which doesn't support opening and syncing a directory handle
+/// this way; durability there relies on the rename alone.
+pub(crate) fn fsync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let handle = fs::File::open(dir)?;
+        handle.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+    }
+    Ok(())
+}
+
+/// Prepare a file for writing, following `options.write_strategy`:
+/// `Atomic`/`Mmap` stage the new content in a sibling temp file for
+/// `StagedEntry::commit` to rename into place; `InPlace` truncates and
+/// rewrites the target directly, for directories that are read-only but
+/// whose files are still writable (a plain `NamedTempFile::new_in` can't be
+/// created there at all). `Mmap` only changes how `read_file` scans the
+/// *input*; by the time a write is staged the mapping backing that read is
+/// long gone (see `read_file`'s doc comment), so it commits exactly like
+/// `Atomic`.
 pub fn stage_file(path: &Path, data: &[u8], options: &WriteOptions) -> Result<StagedEntry> {
     let target_path = resolve_symlink(path, options)?;
 
-    // Write atomically using a temporary file in the same directory
+    // Source metadata, captured once up front: used for permission-mode
+    // bits below and, if requested, ownership/timestamp/xattr preservation.
+    let source_metadata = fs::metadata(&target_path).ok();
+
+    // The caller only stages a write when the file is actually going to
+    // change, so a backup made here always reflects genuinely pre-edit
+    // bytes. Must happen before `target_path` is touched, i.e. before the
+    // eventual rename in `commit`/`into_parts` (or, for `InPlace`, before
+    // the truncate-and-rewrite below).
+    let backup_path = match (&options.backup, &source_metadata) {
+        (Some(spec), Some(_)) => Some(create_backup(&target_path, spec, options.write_strategy)?),
+        _ => None,
+    };
+
+    match options.write_strategy {
+        WriteStrategy::InPlace => {
+            stage_in_place(&target_path, data, options, source_metadata, backup_path)
+        }
+        WriteStrategy::Atomic | WriteStrategy::Mmap => {
+            stage_atomic(&target_path, data, options, source_metadata, backup_path)
+        }
+    }
+}
+
+fn stage_atomic(
+    target_path: &Path,
+    data: &[u8],
+    options: &WriteOptions,
+    source_metadata: Option<fs::Metadata>,
+    backup_path: Option<PathBuf>,
+) -> Result<StagedEntry> {
     let parent = target_path.parent()
         .ok_or_else(|| Error::InvalidPath(target_path.to_path_buf()))?;
 
@@ -50,7 +295,7 @@ pub fn stage_file(path: &Path, data: &[u8], options: &WriteOptions) -> Result<St
     // Set permissions
     match options.permissions {
         PermissionsMode::Preserve => {
-            if let Ok(metadata) = fs::metadata(&target_path) {
+            if let Some(ref metadata) = source_metadata {
                 temp.as_file().set_permissions(metadata.permissions()).ok();
             }
         }
@@ -70,18 +315,329 @@ pub fn stage_file(path: &Path, data: &[u8], options: &WriteOptions) -> Result<St
         temp.flush()?;
     }
 
+    if options.durable {
+        temp.as_file().sync_all()?;
+    }
+
+    let mut preservation = PreservationOutcome::default();
+    if let Some(ref metadata) = source_metadata {
+        // Ownership and xattrs are applied to the temp file directly (it
+        // becomes `target_path` on rename); timestamps too, since setting
+        // them after the rename would have the same effect but gains
+        // nothing and risks a race if another process reads mid-way.
+        if options.preserve_ownership {
+            preservation.ownership = Some(preserve_ownership(temp.path(), metadata));
+        }
+        if options.preserve_xattrs {
+            preservation.xattrs = Some(preserve_xattrs(target_path, temp.path()));
+        }
+        if options.preserve_timestamps {
+            preservation.timestamps = Some(preserve_timestamps(temp.path(), metadata));
+        }
+    }
+
+    Ok(StagedEntry {
+        backend: StagedBackend::Atomic(temp),
+        target: target_path.to_path_buf(),
+        preservation,
+        durable: options.durable,
+        backup_path,
+    })
+}
+
+/// Truncate and rewrite `target_path` directly, with no sibling temp file
+/// and no rename. Unlike `stage_atomic`, a reader can observe a half-written
+/// file mid-write (and a crash or error partway through leaves the file
+/// truncated rather than untouched), which is exactly the tradeoff this
+/// strategy is for: it only needs write permission on the file itself, not
+/// on its containing directory.
+fn stage_in_place(
+    target_path: &Path,
+    data: &[u8],
+    options: &WriteOptions,
+    source_metadata: Option<fs::Metadata>,
+    backup_path: Option<PathBuf>,
+) -> Result<StagedEntry> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(target_path)?;
+
+    match options.permissions {
+        PermissionsMode::Preserve => {}
+        PermissionsMode::Fixed(mode) => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let p = fs::Permissions::from_mode(mode);
+                file.set_permissions(p)?;
+            }
+        }
+    }
+
+    if !data.is_empty() {
+        file.write_all(data)?;
+    }
+    if options.durable {
+        file.sync_all()?;
+    }
+
+    // Unlike `stage_atomic`, this rewrites the target's existing inode
+    // rather than replacing it, so ownership, xattrs, and (when
+    // `PermissionsMode::Preserve`) permissions never actually changed and
+    // there's nothing to restore. Only mtime/atime are disturbed by the
+    // write itself.
+    let mut preservation = PreservationOutcome::default();
+    if let Some(ref metadata) = source_metadata {
+        if options.preserve_ownership {
+            preservation.ownership = Some(true);
+        }
+        if options.preserve_xattrs {
+            preservation.xattrs = Some(true);
+        }
+        if options.preserve_timestamps {
+            preservation.timestamps = Some(preserve_timestamps(target_path, metadata));
+        }
+    }
+
     Ok(StagedEntry {
-        temp,
-        target: target_path,
+        backend: StagedBackend::InPlace,
+        target: target_path.to_path_buf(),
+        preservation,
+        durable: options.durable,
+        backup_path,
     })
 }
 
 /// Write data to a file atomically.
 /// Preserves file permissions and handles symbolic links according to options.
-pub fn write_file(path: &Path, data: &[u8], options: &WriteOptions) -> Result<()> {
+/// Returns which attribute-preservation steps (if any were requested) succeeded,
+/// along with the backup path if one was created.
+pub fn write_file(path: &Path, data: &[u8], options: &WriteOptions) -> Result<(PreservationOutcome, Option<PathBuf>)> {
     let staged = stage_file(path, data, options)?;
+    let preservation = staged.preservation();
+    let backup_path = staged.backup_path();
     staged.commit()?;
-    Ok(())
+    Ok((preservation, backup_path))
+}
+
+/// Like `stage_file`, but the replacement content is produced by `write_body`
+/// writing directly into the destination — the staged temp file, or (for
+/// `WriteStrategy::InPlace`) the truncated target itself — instead of being
+/// handed over as one fully-materialized `&[u8]`. For streaming callers (see
+/// `Replacer::replace_stream`) that never hold a whole file's transformed
+/// content in memory. Everything else (permission bits, backup, ownership/
+/// timestamp/xattr preservation) matches `stage_file` exactly; `write_body`
+/// returns its own count (e.g. replacements made) alongside the staged entry.
+fn stage_file_streamed(
+    path: &Path,
+    options: &WriteOptions,
+    write_body: impl FnOnce(&mut dyn Write) -> Result<usize>,
+) -> Result<(StagedEntry, usize)> {
+    let target_path = resolve_symlink(path, options)?;
+    let source_metadata = fs::metadata(&target_path).ok();
+
+    let backup_path = match (&options.backup, &source_metadata) {
+        (Some(spec), Some(_)) => Some(create_backup(&target_path, spec, options.write_strategy)?),
+        _ => None,
+    };
+
+    match options.write_strategy {
+        WriteStrategy::InPlace => stage_in_place_streamed(&target_path, write_body, options, source_metadata, backup_path),
+        WriteStrategy::Atomic | WriteStrategy::Mmap => stage_atomic_streamed(&target_path, write_body, options, source_metadata, backup_path),
+    }
+}
+
+fn stage_atomic_streamed(
+    target_path: &Path,
+    write_body: impl FnOnce(&mut dyn Write) -> Result<usize>,
+    options: &WriteOptions,
+    source_metadata: Option<fs::Metadata>,
+    backup_path: Option<PathBuf>,
+) -> Result<(StagedEntry, usize)> {
+    let parent = target_path.parent()
+        .ok_or_else(|| Error::InvalidPath(target_path.to_path_buf()))?;
+
+    let mut temp = NamedTempFile::new_in(parent)?;
+
+    match options.permissions {
+        PermissionsMode::Preserve => {
+            if let Some(ref metadata) = source_metadata {
+                temp.as_file().set_permissions(metadata.permissions()).ok();
+            }
+        }
+        PermissionsMode::Fixed(mode) => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let p = fs::Permissions::from_mode(mode);
+                temp.as_file().set_permissions(p)?;
+            }
+        }
+    }
+
+    let replacements = write_body(&mut temp)?;
+    temp.flush()?;
+
+    if options.durable {
+        temp.as_file().sync_all()?;
+    }
+
+    let mut preservation = PreservationOutcome::default();
+    if let Some(ref metadata) = source_metadata {
+        if options.preserve_ownership {
+            preservation.ownership = Some(preserve_ownership(temp.path(), metadata));
+        }
+        if options.preserve_xattrs {
+            preservation.xattrs = Some(preserve_xattrs(target_path, temp.path()));
+        }
+        if options.preserve_timestamps {
+            preservation.timestamps = Some(preserve_timestamps(temp.path(), metadata));
+        }
+    }
+
+    Ok((StagedEntry {
+        backend: StagedBackend::Atomic(temp),
+        target: target_path.to_path_buf(),
+        preservation,
+        durable: options.durable,
+        backup_path,
+    }, replacements))
+}
+
+fn stage_in_place_streamed(
+    target_path: &Path,
+    write_body: impl FnOnce(&mut dyn Write) -> Result<usize>,
+    options: &WriteOptions,
+    source_metadata: Option<fs::Metadata>,
+    backup_path: Option<PathBuf>,
+) -> Result<(StagedEntry, usize)> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(target_path)?;
+
+    match options.permissions {
+        PermissionsMode::Preserve => {}
+        PermissionsMode::Fixed(mode) => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let p = fs::Permissions::from_mode(mode);
+                file.set_permissions(p)?;
+            }
+        }
+    }
+
+    let replacements = write_body(&mut file)?;
+
+    if options.durable {
+        file.sync_all()?;
+    }
+
+    let mut preservation = PreservationOutcome::default();
+    if let Some(ref metadata) = source_metadata {
+        if options.preserve_ownership {
+            preservation.ownership = Some(true);
+        }
+        if options.preserve_xattrs {
+            preservation.xattrs = Some(true);
+        }
+        if options.preserve_timestamps {
+            preservation.timestamps = Some(preserve_timestamps(target_path, metadata));
+        }
+    }
+
+    Ok((StagedEntry {
+        backend: StagedBackend::InPlace,
+        target: target_path.to_path_buf(),
+        preservation,
+        durable: options.durable,
+        backup_path,
+    }, replacements))
+}
+
+/// Streamed counterpart to `write_file`: stages via `stage_file_streamed`
+/// and commits immediately. `--stream` never supports `--transaction all`
+/// (see `engine::execute_file_streaming`), so unlike `process_file`'s
+/// buffered writes, a streamed write has no "stage now, commit later"
+/// path — it always writes straight through.
+pub fn write_file_streamed(
+    path: &Path,
+    options: &WriteOptions,
+    write_body: impl FnOnce(&mut dyn Write) -> Result<usize>,
+) -> Result<(PreservationOutcome, Option<PathBuf>, usize)> {
+    let (staged, replacements) = stage_file_streamed(path, options, write_body)?;
+    let preservation = staged.preservation();
+    let backup_path = staged.backup_path();
+    staged.commit()?;
+    Ok((preservation, backup_path, replacements))
+}
+
+/// Preserve `target`'s current (pre-edit) bytes at the backup path computed
+/// from `spec`. Tries a hardlink first, since it's atomic and doesn't copy
+/// data; falls back to a real copy when the link can't be made (e.g. across
+/// filesystems). With `WriteStrategy::InPlace` it always copies, since that
+/// strategy rewrites the target's inode, which a hardlink would share.
+/// Replaces any backup left over from a previous run.
+fn create_backup(target: &Path, spec: &BackupSpec, strategy: WriteStrategy) -> Result<PathBuf> {
+    let backup_path = spec.backup_path(target);
+    let _ = fs::remove_file(&backup_path);
+    if strategy == WriteStrategy::InPlace || fs::hard_link(target, &backup_path).is_err() {
+        fs::copy(target, &backup_path)?;
+    }
+    Ok(backup_path)
+}
+
+/// Best-effort: `chown` the staged temp file to the source file's uid/gid.
+/// Failures (most commonly `EPERM` for an unprivileged process trying to
+/// change ownership) are reported back rather than silently ignored, but
+/// never abort the write.
+#[cfg(unix)]
+fn preserve_ownership(path: &Path, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    std::os::unix::fs::chown(path, Some(metadata.uid()), Some(metadata.gid())).is_ok()
+}
+
+#[cfg(not(unix))]
+fn preserve_ownership(_path: &Path, _metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Best-effort: restore mtime/atime on the staged temp file from `metadata`.
+fn preserve_timestamps(path: &Path, metadata: &fs::Metadata) -> bool {
+    let mtime = filetime::FileTime::from_last_modification_time(metadata);
+    let atime = filetime::FileTime::from_last_access_time(metadata);
+    filetime::set_file_times(path, atime, mtime).is_ok()
+}
+
+/// Best-effort: copy every extended attribute from `source` onto `dest`
+/// (the staged temp file). Returns false if any attribute failed to copy,
+/// even if others succeeded, so callers know the copy was incomplete.
+#[cfg(unix)]
+fn preserve_xattrs(source: &Path, dest: &Path) -> bool {
+    let names = match xattr::list(source) {
+        Ok(names) => names,
+        Err(_) => return false,
+    };
+
+    let mut all_ok = true;
+    for name in names {
+        match xattr::get(source, &name) {
+            Ok(Some(value)) => {
+                if xattr::set(dest, &name, &value).is_err() {
+                    all_ok = false;
+                }
+            }
+            _ => all_ok = false,
+        }
+    }
+    all_ok
+}
+
+#[cfg(not(unix))]
+fn preserve_xattrs(_source: &Path, _dest: &Path) -> bool {
+    false
 }
 
 /// Resolve symbolic links according to options.
@@ -99,4 +655,143 @@ fn resolve_symlink(path: &Path, options: &WriteOptions) -> Result<PathBuf> {
     }
     // Not a symlink
     Ok(path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write as _;
+
+    #[test]
+    fn read_file_uses_buffered_path_below_threshold() {
+        let mut temp = NamedTempFile::new().unwrap();
+        temp.write_all(b"small").unwrap();
+        let options = WriteOptions::default();
+        let bytes = read_file(temp.path(), &options).unwrap();
+        assert!(matches!(bytes, FileBytes::Buffered(_)));
+        assert_eq!(&bytes[..], b"small");
+    }
+
+    #[test]
+    fn read_file_uses_mmap_path_above_threshold() {
+        let mut temp = NamedTempFile::new().unwrap();
+        let data = vec![b'x'; MMAP_MIN_SIZE as usize + 1];
+        temp.write_all(&data).unwrap();
+        temp.flush().unwrap();
+        let options = WriteOptions::default();
+        let bytes = read_file(temp.path(), &options).unwrap();
+        assert!(matches!(bytes, FileBytes::Mapped(_)));
+        assert_eq!(bytes.len(), data.len());
+    }
+
+    #[test]
+    fn read_file_force_buffered_skips_mmap() {
+        let mut temp = NamedTempFile::new().unwrap();
+        let data = vec![b'x'; MMAP_MIN_SIZE as usize + 1];
+        temp.write_all(&data).unwrap();
+        temp.flush().unwrap();
+        let options = WriteOptions {
+            force_buffered_read: true,
+            ..WriteOptions::default()
+        };
+        let bytes = read_file(temp.path(), &options).unwrap();
+        assert!(matches!(bytes, FileBytes::Buffered(_)));
+    }
+
+    #[test]
+    fn read_file_honors_custom_mmap_min_size() {
+        let mut temp = NamedTempFile::new().unwrap();
+        temp.write_all(b"tiny").unwrap();
+        temp.flush().unwrap();
+        let options = WriteOptions {
+            mmap_min_size: 1,
+            ..WriteOptions::default()
+        };
+        let bytes = read_file(temp.path(), &options).unwrap();
+        assert!(matches!(bytes, FileBytes::Mapped(_)));
+    }
+
+    #[test]
+    fn read_file_mmap_strategy_forces_mapping_below_threshold() {
+        let mut temp = NamedTempFile::new().unwrap();
+        temp.write_all(b"tiny").unwrap();
+        temp.flush().unwrap();
+        let options = WriteOptions {
+            write_strategy: WriteStrategy::Mmap,
+            ..WriteOptions::default()
+        };
+        let bytes = read_file(temp.path(), &options).unwrap();
+        assert!(matches!(bytes, FileBytes::Mapped(_)));
+    }
+
+    #[test]
+    fn stage_file_atomic_replaces_inode() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("file.txt");
+        fs::write(&target, b"old").unwrap();
+        let before = fs::metadata(&target).unwrap();
+
+        let options = WriteOptions::default();
+        write_file(&target, b"new", &options).unwrap();
+
+        let after = fs::metadata(&target).unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert_ne!(before.ino(), after.ino());
+        }
+    }
+
+    #[test]
+    fn stage_file_in_place_keeps_inode_and_needs_no_dir_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("file.txt");
+        fs::write(&target, b"old content").unwrap();
+        #[cfg(unix)]
+        let before_ino = {
+            use std::os::unix::fs::MetadataExt;
+            fs::metadata(&target).unwrap().ino()
+        };
+
+        let options = WriteOptions {
+            write_strategy: WriteStrategy::InPlace,
+            ..WriteOptions::default()
+        };
+        write_file(&target, b"new", &options).unwrap();
+
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert_eq!(before_ino, fs::metadata(&target).unwrap().ino());
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn stage_file_in_place_succeeds_with_read_only_parent_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("file.txt");
+        fs::write(&target, b"old").unwrap();
+
+        let mut perms = fs::metadata(dir.path()).unwrap().permissions();
+        perms.set_mode(0o555);
+        fs::set_permissions(dir.path(), perms.clone()).unwrap();
+
+        let options = WriteOptions {
+            write_strategy: WriteStrategy::InPlace,
+            ..WriteOptions::default()
+        };
+        let result = write_file(&target, b"new", &options);
+
+        // Restore write permission so the tempdir can clean itself up.
+        perms.set_mode(0o755);
+        fs::set_permissions(dir.path(), perms).unwrap();
+
+        result.unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,78 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn type_list_prints_builtin_types_and_exits_without_find_replace() {
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.arg("--type-list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rust: "));
+}
+
+#[test]
+fn type_restricts_edits_to_matching_type() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "foo").unwrap();
+    fs::write(dir.path().join("b.md"), "foo").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.current_dir(dir.path())
+        .arg("foo")
+        .arg("bar")
+        .arg("--type")
+        .arg("rust")
+        .arg("a.rs")
+        .arg("b.md")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(dir.path().join("a.rs")).unwrap(), "bar");
+    assert_eq!(fs::read_to_string(dir.path().join("b.md")).unwrap(), "foo");
+}
+
+#[test]
+fn type_not_excludes_matching_type() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "foo").unwrap();
+    fs::write(dir.path().join("b.md"), "foo").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.current_dir(dir.path())
+        .arg("foo")
+        .arg("bar")
+        .arg("--type-not")
+        .arg("rust")
+        .arg("a.rs")
+        .arg("b.md")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(dir.path().join("a.rs")).unwrap(), "foo");
+    assert_eq!(fs::read_to_string(dir.path().join("b.md")).unwrap(), "bar");
+}
+
+#[test]
+fn type_add_registers_a_new_type_usable_with_type() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.xyz"), "foo").unwrap();
+    fs::write(dir.path().join("b.md"), "foo").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.current_dir(dir.path())
+        .arg("foo")
+        .arg("bar")
+        .arg("--type-add")
+        .arg("xyz:*.xyz")
+        .arg("--type")
+        .arg("xyz")
+        .arg("a.xyz")
+        .arg("b.md")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(dir.path().join("a.xyz")).unwrap(), "bar");
+    assert_eq!(fs::read_to_string(dir.path().join("b.md")).unwrap(), "foo");
+}
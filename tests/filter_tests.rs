@@ -0,0 +1,24 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn filter_restricts_edits_to_matching_extension() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "foo").unwrap();
+    fs::write(dir.path().join("b.md"), "foo").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.current_dir(dir.path())
+        .arg("foo")
+        .arg("bar")
+        .arg("--filter")
+        .arg(r#"ext = "rs""#)
+        .arg("a.rs")
+        .arg("b.md")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(dir.path().join("a.rs")).unwrap(), "bar");
+    assert_eq!(fs::read_to_string(dir.path().join("b.md")).unwrap(), "foo");
+}
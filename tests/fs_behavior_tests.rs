@@ -161,3 +161,43 @@ fn test_binary_skip_with_other_files() {
     // Text changed
     assert_eq!(fs::read_to_string(&txt_file).unwrap(), "bar");
 }
+
+#[test]
+fn test_backup_flag_uses_default_suffix() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("backup.txt");
+    fs::write(&file, "foo").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("sd2");
+    cmd.arg("foo")
+       .arg("bar")
+       .arg(file.to_str().unwrap())
+       .arg("--backup")
+       .assert()
+       .success();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "bar");
+    let backup = dir.path().join("backup.txt~");
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "foo");
+}
+
+#[test]
+fn test_backup_suffix_overrides_backup_flag_default() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("backup.txt");
+    fs::write(&file, "foo").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("sd2");
+    cmd.arg("foo")
+       .arg("bar")
+       .arg(file.to_str().unwrap())
+       .arg("--backup")
+       .arg("--backup-suffix=.bak")
+       .assert()
+       .success();
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "bar");
+    let backup = dir.path().join("backup.txt.bak");
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "foo");
+    assert!(!dir.path().join("backup.txt~").exists());
+}
@@ -120,3 +120,80 @@ fn test_glob_exclude_only() {
     assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "bar");
     assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "foo");
 }
+
+#[test]
+fn test_stream_basic() {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_txed"));
+    cmd.arg("foo")
+        .arg("bar")
+        .arg("--stdin-text")
+        .arg("--stream")
+        .write_stdin("hello foo world")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("hello bar world"));
+}
+
+#[test]
+fn test_stream_match_across_chunk_boundary() {
+    // The internal read chunk size is 64 KiB; place the needle straddling
+    // that boundary to exercise the overlap-tail logic rather than relying
+    // on it landing cleanly inside one chunk.
+    let mut input = vec![b'x'; 64 * 1024 - 1];
+    input.extend_from_slice(b"foo");
+    input.extend(vec![b'y'; 1024]);
+
+    let mut expected = vec![b'x'; 64 * 1024 - 1];
+    expected.extend_from_slice(b"bar");
+    expected.extend(vec![b'y'; 1024]);
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_txed"));
+    let assert = cmd
+        .arg("foo")
+        .arg("bar")
+        .arg("--stdin-text")
+        .arg("--stream")
+        .write_stdin(input)
+        .assert()
+        .success();
+
+    assert_eq!(assert.get_output().stdout, expected);
+}
+
+#[test]
+fn test_stream_file_basic() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    fs::write(&file_path, "hello foo world").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_txed"));
+    cmd.current_dir(dir.path())
+        .arg("foo")
+        .arg("bar")
+        .arg("--stream")
+        .arg("file.txt")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "hello bar world");
+}
+
+#[test]
+fn test_stream_file_rejects_transaction_all() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    fs::write(&file_path, "foo").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_txed"));
+    cmd.current_dir(dir.path())
+        .arg("foo")
+        .arg("bar")
+        .arg("--stream")
+        .arg("--transaction")
+        .arg("all")
+        .arg("file.txt")
+        .assert()
+        .failure();
+
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "foo");
+}
@@ -74,3 +74,59 @@ fn test_explicit_format_agent() {
         .stdout(predicate::str::contains("+baz bar"))
         .stdout(predicate::str::contains("</file>"));
 }
+
+#[test]
+fn test_explicit_format_shell() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("shell.txt");
+    fs::write(&file_path, "foo bar").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("stedi");
+    cmd.arg("foo")
+        .arg("baz")
+        .arg(&file_path)
+        .arg("--dry-run")
+        .arg("--format=shell");
+
+    cmd.assert()
+        .stdout(predicate::str::contains(format!("modified\t{}\t1", file_path.display())))
+        .stdout(predicate::str::contains("files=1 modified=1 replacements=1 errors=false"));
+}
+
+#[test]
+fn test_explicit_format_files0() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("files0.txt");
+    fs::write(&file_path, "foo bar").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("stedi");
+    cmd.arg("foo")
+        .arg("baz")
+        .arg(&file_path)
+        .arg("--dry-run")
+        .arg("--format=files0");
+
+    let expected = format!("{}\ttrue\t1\0", file_path.display());
+    cmd.assert().stdout(predicate::eq(expected.into_bytes()));
+}
+
+#[test]
+fn test_context_flag_widens_hunk() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("context.txt");
+    fs::write(&file_path, "a\nb\nc\nX\ne\nf\ng\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("stedi");
+    cmd.arg("X")
+        .arg("d")
+        .arg(&file_path)
+        .arg("--dry-run")
+        .arg("--context=2");
+
+    // With 2 lines of context on each side of the one-line change, the hunk
+    // should cover lines 2-6 (" b", " c", "-X", "+d", " e", " f").
+    cmd.assert()
+        .stdout(predicate::str::contains("@@ -2,5 +2,5 @@"))
+        .stdout(predicate::str::contains(" b\n"))
+        .stdout(predicate::str::contains(" f\n"));
+}
@@ -0,0 +1,23 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+/// `--patch` round-trips a real unified diff, including a hunk whose last
+/// line has no trailing newline — the shape that previously made
+/// `parse_patch` hard-fail with "unrecognized diff line marker".
+#[test]
+fn patch_applies_a_unified_diff_with_a_no_trailing_newline_hunk() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "a\nb").unwrap();
+
+    let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+X\n\\ No newline at end of file\n";
+
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.current_dir(dir.path())
+        .arg("--patch")
+        .write_stdin(patch)
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a\nX");
+}
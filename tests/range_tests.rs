@@ -58,3 +58,41 @@ fn test_range_start_unbounded() {
     let content = fs::read_to_string(&file_path).unwrap();
     assert_eq!(content, "foo\nfoo\nbar\nbar");
 }
+
+#[test]
+fn test_range_multiple_comma_separated() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test_range_4.txt");
+    fs::write(&file_path, "foo\nfoo\nfoo\nfoo\nfoo").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_txed"));
+    cmd.arg("foo")
+        .arg("bar")
+        .arg("--range")
+        .arg("1,3:4")
+        .arg(file_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(content, "bar\nfoo\nbar\nbar\nfoo");
+}
+
+#[test]
+fn test_range_negative_index_from_end() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test_range_5.txt");
+    fs::write(&file_path, "foo\nfoo\nfoo\nfoo\nfoo").unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_txed"));
+    cmd.arg("foo")
+        .arg("bar")
+        .arg("--range")
+        .arg("-2:")
+        .arg(file_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(content, "foo\nfoo\nfoo\nbar\nbar");
+}
@@ -0,0 +1,33 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn use_subcommand_applies_a_recipe_from_the_config_file() {
+    let config_home = tempdir().unwrap();
+    let recipes_dir = config_home.path().join("txed");
+    fs::create_dir_all(&recipes_dir).unwrap();
+    fs::write(
+        recipes_dir.join("recipes.toml"),
+        r#"
+        [recipes.fix-imports]
+        find = "foo"
+        with = "bar"
+        "#,
+    )
+    .unwrap();
+
+    let workdir = tempdir().unwrap();
+    fs::write(workdir.path().join("a.txt"), "foo baz").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.current_dir(workdir.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .arg("use")
+        .arg("fix-imports")
+        .arg("a.txt")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(workdir.path().join("a.txt")).unwrap(), "bar baz");
+}
@@ -0,0 +1,20 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn rustfix_applies_a_machine_applicable_suggestion() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("a.rs");
+    fs::write(&path, "fn main() { let x = 1; }").unwrap();
+
+    let file_name = path.to_str().unwrap().replace('\\', "\\\\");
+    let message = format!(
+        r#"{{"reason":"compiler-message","message":{{"spans":[{{"file_name":"{file_name}","byte_start":20,"byte_end":21,"suggested_replacement":"2","suggestion_applicability":"MachineApplicable"}}]}}}}"#
+    );
+
+    let mut cmd = cargo_bin_cmd!("txed");
+    cmd.arg("--rustfix").write_stdin(message).assert().success();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fn main() { let x = 2; }");
+}
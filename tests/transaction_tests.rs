@@ -56,6 +56,39 @@ fn test_transaction_all_mode_rollback() {
     assert_eq!(fs::read_to_string(&f2).unwrap(), "foo");
 }
 
+#[test]
+fn test_transaction_all_mode_rollback_with_threads() {
+    // Same as test_transaction_all_mode_rollback, but with an explicit
+    // worker count: staging and the commit barrier must still hold even
+    // when files are farmed out across multiple threads.
+    let dir = tempdir().unwrap();
+    let f1 = dir.path().join("f1.txt");
+    let f2 = dir.path().join("f2.txt");
+    let f3 = dir.path().join("f3.txt");
+    fs::write(&f1, "foo").unwrap();
+    fs::write(&f2, "foo").unwrap();
+    fs::write(&f3, "foo").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("sd2");
+    cmd.arg("foo")
+       .arg("bar")
+       .arg("--transaction")
+       .arg("all")
+       .arg("--threads")
+       .arg("4")
+       .arg("--expect")
+       .arg("10") // We have 3 matches, so this will fail
+       .arg(f1.to_str().unwrap())
+       .arg(f2.to_str().unwrap())
+       .arg(f3.to_str().unwrap())
+       .assert()
+       .failure();
+
+    assert_eq!(fs::read_to_string(&f1).unwrap(), "foo");
+    assert_eq!(fs::read_to_string(&f2).unwrap(), "foo");
+    assert_eq!(fs::read_to_string(&f3).unwrap(), "foo");
+}
+
 #[test]
 fn test_transaction_all_mode_success() {
     let dir = tempdir().unwrap();
@@ -0,0 +1,38 @@
+use std::fs;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+/// `--watch` runs one synchronous pass over its inputs before it starts
+/// blocking on filesystem events (see `watch::run`); exercise that pass
+/// without depending on live fs-event delivery, which is flaky in a
+/// sandboxed CI environment.
+#[test]
+fn watch_performs_an_initial_synchronous_pass() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "foo").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_txed"))
+        .current_dir(dir.path())
+        .arg("foo")
+        .arg("bar")
+        .arg("--watch")
+        .arg("a.txt")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if fs::read_to_string(&path).unwrap() == "bar" {
+            break;
+        }
+        assert!(Instant::now() < deadline, "--watch did not perform its initial pass in time");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}